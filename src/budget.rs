@@ -0,0 +1,213 @@
+//! Tracking of memory used by built textures.
+
+use std::ptr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use error::TextureError;
+
+/// Running total of memory used by textures built with the budget.
+/// Shared between builders via `Arc` and updated atomically,
+/// so textures can be built and disposed on different threads.
+#[derive(Debug, Default)]
+pub struct TextureBudget {
+    used: AtomicU64,
+    soft_limit: Option<u64>,
+    hard_limit: Option<u64>,
+}
+
+impl TextureBudget {
+    /// Create budget without limits.
+    pub fn new() -> Self {
+        TextureBudget::default()
+    }
+
+    /// Set limit above which building texture logs a warning.
+    /// Warning is logged only with `logging` feature.
+    #[must_use]
+    pub fn with_soft_limit(mut self, limit: u64) -> Self {
+        self.soft_limit = Some(limit);
+        self
+    }
+
+    /// Set limit building texture can't exceed.
+    /// Texture that doesn't fit fails with `TextureError::BudgetExceeded` before allocation.
+    #[must_use]
+    pub fn with_hard_limit(mut self, limit: u64) -> Self {
+        self.hard_limit = Some(limit);
+        self
+    }
+
+    /// Estimated bytes used by textures that are not disposed yet.
+    pub fn used(&self) -> u64 {
+        self.used.load(Ordering::Relaxed)
+    }
+
+    /// Limit above which building texture logs a warning.
+    /// Warning is logged only with `logging` feature.
+    pub fn soft_limit(&self) -> Option<u64> {
+        self.soft_limit
+    }
+
+    /// Limit building texture can't exceed.
+    pub fn hard_limit(&self) -> Option<u64> {
+        self.hard_limit
+    }
+}
+
+/// Budgets are equal only if they are the same budget.
+impl PartialEq for TextureBudget {
+    fn eq(&self, other: &Self) -> bool {
+        ptr::eq(self, other)
+    }
+}
+
+/// Memory charged to a budget.
+/// Credited back when dropped.
+#[derive(Debug)]
+pub(crate) struct Charge {
+    budget: Arc<TextureBudget>,
+    size: u64,
+}
+
+impl Charge {
+    /// Charge `size` bytes to the budget unless it exceeds hard limit.
+    pub(crate) fn new(budget: &Arc<TextureBudget>, size: u64) -> Result<Self, TextureError> {
+        let mut used = budget.used.load(Ordering::Relaxed);
+        loop {
+            let total = used.saturating_add(size);
+            if let Some(limit) = budget.hard_limit {
+                if total > limit {
+                    Err(TextureError::BudgetExceeded {
+                        requested: size,
+                        used,
+                        limit,
+                    })?;
+                }
+            }
+            match budget.used.compare_exchange_weak(
+                used,
+                total,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(actual) => used = actual,
+            }
+        }
+
+        #[cfg(feature = "logging")]
+        if let Some(limit) = budget.soft_limit {
+            let total = used.saturating_add(size);
+            if used <= limit && total > limit {
+                warn!(
+                    "Texture memory budget soft limit of {} bytes is exceeded: {} bytes used",
+                    limit, total
+                );
+            }
+        }
+
+        Ok(Charge {
+            budget: budget.clone(),
+            size,
+        })
+    }
+
+    /// Budget charged.
+    pub(crate) fn budget(&self) -> &Arc<TextureBudget> {
+        &self.budget
+    }
+}
+
+impl Drop for Charge {
+    fn drop(&mut self) {
+        self.budget.used.fetch_sub(self.size, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Barrier;
+    use std::thread;
+
+    #[test]
+    fn concurrent_charges_are_credited_back() {
+        let budget = Arc::new(TextureBudget::new());
+        let threads = (0..8)
+            .map(|_| {
+                let budget = budget.clone();
+                thread::spawn(move || {
+                    for size in 1..1000 {
+                        let charge = Charge::new(&budget, size).unwrap();
+                        assert!(budget.used() >= size);
+                        drop(charge);
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(budget.used(), 0);
+    }
+
+    #[test]
+    fn hard_limit_holds_under_contention() {
+        let budget = Arc::new(TextureBudget::new().with_hard_limit(1000));
+        let barrier = Arc::new(Barrier::new(8));
+        let threads = (0..8)
+            .map(|_| {
+                let (budget, barrier) = (budget.clone(), barrier.clone());
+                thread::spawn(move || {
+                    barrier.wait();
+                    (0..100)
+                        .filter_map(|_| Charge::new(&budget, 10).ok())
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+        let charges = threads
+            .into_iter()
+            .flat_map(|thread| thread.join().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(charges.len(), 100);
+        assert_eq!(budget.used(), 1000);
+        match Charge::new(&budget, 1) {
+            Err(TextureError::BudgetExceeded {
+                requested: 1,
+                used: 1000,
+                limit: 1000,
+            }) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+        drop(charges);
+        assert_eq!(budget.used(), 0);
+    }
+
+    #[cfg(feature = "logging")]
+    #[test]
+    fn soft_limit_is_logged_once() {
+        let logs = ::tests::captured_logs();
+
+        let budget = Arc::new(TextureBudget::new().with_soft_limit(100));
+        let charges = (0..3)
+            .map(|_| Charge::new(&budget, 40).unwrap())
+            .collect::<Vec<_>>();
+        let warnings = logs
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|record| record.1.contains("soft limit"))
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            warnings,
+            [(
+                log::Level::Warn,
+                "Texture memory budget soft limit of 100 bytes is exceeded: 120 bytes used".into()
+            )]
+        );
+        drop(charges);
+    }
+}
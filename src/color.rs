@@ -0,0 +1,19 @@
+//! Color space helpers.
+
+/// Convert sRGB-encoded value in `[0, 1]` to linear.
+pub(crate) fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Convert linear value in `[0, 1]` to sRGB encoding.
+pub(crate) fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
@@ -0,0 +1,200 @@
+//! Command recording helpers.
+
+use error::TextureError;
+use std::borrow::Borrow;
+use std::ops::Range;
+
+use hal::buffer;
+use hal::command::{BufferImageCopy, CommandBufferFlags, RawCommandBuffer};
+use hal::image::{State, SubresourceRange};
+use hal::memory::{Barrier, Dependencies, Properties};
+use hal::pool::{RawCommandBufferLevel, RawCommandPool};
+use hal::pso::PipelineStage;
+use hal::queue::{RawCommandQueue, RawSubmission};
+use hal::{Backend, Device};
+
+use render::{Buffer, Factory};
+
+/// Staging resources of commands recorded but not yet complete.
+/// Must be released with `complete` after the commands are complete,
+/// dropping it leaks the resources.
+#[derive(Debug)]
+pub struct PendingUpload<B: Backend> {
+    staging: Option<Buffer<B>>,
+}
+
+impl<B> PendingUpload<B>
+where
+    B: Backend,
+{
+    pub(crate) fn new(staging: Option<Buffer<B>>) -> Self {
+        PendingUpload { staging }
+    }
+
+    /// Release staging resources.
+    /// Commands reading them must be complete.
+    pub fn complete(self, factory: &mut Factory<B>) {
+        if let Some(staging) = self.staging {
+            factory.destroy_buffer(staging);
+        }
+    }
+}
+
+/// Record barrier transitioning `range` of the image between states.
+pub(crate) fn image_barrier<B>(
+    cmd: &mut B::CommandBuffer,
+    image: &B::Image,
+    range: SubresourceRange,
+    states: Range<State>,
+    stages: Range<PipelineStage>,
+) where
+    B: Backend,
+{
+    cmd.pipeline_barrier(
+        stages,
+        Dependencies::empty(),
+        Some(Barrier::Image {
+            states,
+            target: image,
+            range,
+        }),
+    );
+}
+
+/// Record commands with `record` into command buffer allocated from `pool`,
+/// submit it to `queue` and wait until it is complete.
+/// Nothing is submitted if `record` fails. Command buffer is freed back to `pool`.
+/// Returns `TextureError::DeviceLost` if waiting for the commands fails.
+pub(crate) fn submit_once<B, T, F>(
+    factory: &mut Factory<B>,
+    queue: &mut B::CommandQueue,
+    pool: &mut B::CommandPool,
+    record: F,
+) -> Result<T, TextureError>
+where
+    B: Backend,
+    F: FnOnce(&mut Factory<B>, &mut B::CommandBuffer) -> Result<T, TextureError>,
+{
+    let mut cmd = pool
+        .allocate(1, RawCommandBufferLevel::Primary)
+        .pop()
+        .expect("Pool allocates requested command buffers");
+    cmd.begin(CommandBufferFlags::ONE_TIME_SUBMIT, Default::default());
+    let result = record(factory, &mut cmd);
+    cmd.finish();
+
+    let result = result.and_then(|value| {
+        let fence = factory.create_fence(false);
+        unsafe {
+            queue.submit_raw(
+                RawSubmission {
+                    cmd_buffers: Some(&cmd),
+                    wait_semaphores: &[],
+                    signal_semaphores: &[],
+                },
+                Some(&fence),
+            );
+        }
+        // Wait without timeout fails only when device is lost.
+        let complete = factory.wait_for_fence(&fence, !0);
+        factory.destroy_fence(fence);
+        if complete {
+            Ok(value)
+        } else {
+            Err(TextureError::DeviceLost)
+        }
+    });
+    unsafe {
+        pool.free(vec![cmd]);
+    }
+    result
+}
+
+/// Create host-visible buffer and write `chunks` into it.
+/// Each chunk starts at offset aligned to `align`.
+/// Returns buffer and offsets of the chunks.
+pub(crate) fn create_staging<B>(
+    factory: &mut Factory<B>,
+    chunks: &[&[u8]],
+    align: u64,
+) -> Result<(Buffer<B>, Vec<u64>), TextureError>
+where
+    B: Backend,
+{
+    let mut offsets = Vec::with_capacity(chunks.len());
+    let mut size = 0;
+    for chunk in chunks {
+        offsets.push(size);
+        size = (size + chunk.len() as u64).div_ceil(align) * align;
+    }
+
+    let buffer = factory.create_buffer(
+        align,
+        size,
+        buffer::Usage::TRANSFER_SRC,
+        Properties::CPU_VISIBLE,
+    )?;
+
+    write_staging(factory, &buffer, chunks, &offsets)?;
+    Ok((buffer, offsets))
+}
+
+/// Write `chunks` at `offsets` into host-visible buffer.
+pub(crate) fn write_staging<B>(
+    factory: &Factory<B>,
+    buffer: &Buffer<B>,
+    chunks: &[&[u8]],
+    offsets: &[u64],
+) -> Result<(), TextureError>
+where
+    B: Backend,
+{
+    let block = buffer.block();
+    let ptr = factory.map_memory(block.memory(), block.range())?;
+    for (chunk, &offset) in chunks.iter().zip(offsets) {
+        unsafe {
+            ::std::ptr::copy_nonoverlapping(chunk.as_ptr(), ptr.add(offset as usize), chunk.len());
+        }
+    }
+    if !block.properties().contains(Properties::COHERENT) {
+        factory.flush_mapped_memory_ranges(Some((block.memory(), block.range())));
+    }
+    factory.unmap_memory(block.memory());
+    Ok(())
+}
+
+/// Read first `size` bytes of host-visible buffer.
+pub(crate) fn read_staging<B>(
+    factory: &Factory<B>,
+    buffer: &Buffer<B>,
+    size: usize,
+) -> Result<Vec<u8>, TextureError>
+where
+    B: Backend,
+{
+    let block = buffer.block();
+    let ptr = factory.map_memory(block.memory(), block.range())?;
+    if !block.properties().contains(Properties::COHERENT) {
+        factory.invalidate_mapped_memory_ranges(Some((block.memory(), block.range())));
+    }
+    let data = unsafe { ::std::slice::from_raw_parts(ptr, size).to_vec() };
+    factory.unmap_memory(block.memory());
+    Ok(data)
+}
+
+/// Record copy from staging buffer into image in `TransferDstOptimal` layout.
+pub(crate) fn copy_buffer_to_image<B>(
+    cmd: &mut B::CommandBuffer,
+    buffer: &Buffer<B>,
+    image: &B::Image,
+    regions: &[BufferImageCopy],
+) where
+    B: Backend,
+{
+    cmd.copy_buffer_to_image(
+        buffer.borrow(),
+        image,
+        ::hal::image::Layout::TransferDstOptimal,
+        regions,
+    );
+}
@@ -0,0 +1,211 @@
+//! Comparison of texture data.
+
+use hal::format::Format;
+use hal::image::{Extent, Layer};
+
+use error::TextureError;
+use format;
+use mip;
+
+/// Limits under which compared data is considered matching.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CompareTolerance {
+    /// Largest normalized channel difference of matching texels.
+    pub channel: f32,
+    /// Number of differing texels allowed.
+    pub texels: usize,
+}
+
+impl CompareTolerance {
+    /// Tolerance that accepts only identical data.
+    pub fn exact() -> Self {
+        CompareTolerance {
+            channel: 0.0,
+            texels: 0,
+        }
+    }
+
+    /// Tolerance with allowed channel difference and number of differing texels.
+    pub fn new(channel: f32, texels: usize) -> Self {
+        CompareTolerance { channel, texels }
+    }
+}
+
+impl Default for CompareTolerance {
+    fn default() -> Self {
+        CompareTolerance::exact()
+    }
+}
+
+/// Result of data comparison.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CompareResult {
+    /// Largest normalized channel difference.
+    pub max_difference: f32,
+    /// Number of texels with channel difference exceeding tolerance.
+    pub differing_texels: usize,
+    /// Column, row and slice of the texel with largest difference if data differs.
+    pub worst_texel: Option<(u32, u32, u32)>,
+    /// Tolerance the data was compared with.
+    pub tolerance: CompareTolerance,
+}
+
+impl CompareResult {
+    /// Check if data matches within tolerance.
+    pub fn matches(&self) -> bool {
+        self.differing_texels <= self.tolerance.texels
+    }
+}
+
+/// Compare tightly packed data of `format`.
+/// Data consists of slices of `height` rows with `width` texels.
+pub fn compare(
+    a: &[u8],
+    b: &[u8],
+    format: Format,
+    width: u32,
+    height: u32,
+    tolerance: CompareTolerance,
+) -> Result<CompareResult, TextureError> {
+    if a.len() != b.len() {
+        Err(TextureError::LevelSize {
+            level: 0,
+            expected: a.len(),
+            actual: b.len(),
+        })?;
+    }
+
+    let slices = a.len() / format::slice_size(format, width, height).max(1);
+    let extent = Extent {
+        width,
+        height,
+        depth: 1,
+    };
+    let (channels, a) = mip::decode(format, extent, width, height, a, slices as Layer)
+        .ok_or(TextureError::DecodeUnsupportedFormat { format })?;
+    let (_, b) = mip::decode(format, extent, width, height, b, slices as Layer)
+        .ok_or(TextureError::DecodeUnsupportedFormat { format })?;
+    Ok(compare_texels(channels, width, height, &a, &b, tolerance))
+}
+
+/// Compare decoded texels.
+pub(crate) fn compare_texels(
+    channels: usize,
+    width: u32,
+    height: u32,
+    a: &[f32],
+    b: &[f32],
+    tolerance: CompareTolerance,
+) -> CompareResult {
+    let mut result = CompareResult {
+        max_difference: 0.0,
+        differing_texels: 0,
+        worst_texel: None,
+        tolerance,
+    };
+
+    let texels = a.chunks_exact(channels).zip(b.chunks_exact(channels));
+    for (index, (a, b)) in texels.enumerate() {
+        let difference = a
+            .iter()
+            .zip(b)
+            .map(|(a, b)| {
+                if a == b || (a.is_nan() && b.is_nan()) {
+                    0.0
+                } else {
+                    let difference = (a - b).abs();
+                    if difference.is_nan() {
+                        f32::INFINITY
+                    } else {
+                        difference
+                    }
+                }
+            })
+            .fold(0.0, f32::max);
+
+        if difference > tolerance.channel {
+            result.differing_texels += 1;
+        }
+        if difference > result.max_difference {
+            let index = index as u32;
+            result.max_difference = difference;
+            result.worst_texel = Some((
+                index % width,
+                index / width % height,
+                index / (width * height),
+            ));
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_worst_texel() {
+        let a = [0u8, 0, 0, 0, 10, 20, 30, 40];
+        let b = [0u8, 0, 0, 0, 10, 20, 30, 91];
+        let result = compare(&a, &b, Format::Rgba8Unorm, 2, 1, CompareTolerance::exact()).unwrap();
+        assert_eq!(result.differing_texels, 1);
+        assert_eq!(result.max_difference, 0.2);
+        assert_eq!(result.worst_texel, Some((1, 0, 0)));
+        assert!(!result.matches());
+
+        let result = compare(
+            &a,
+            &b,
+            Format::Rgba8Unorm,
+            2,
+            1,
+            CompareTolerance::new(0.25, 0),
+        );
+        assert!(result.unwrap().matches());
+    }
+
+    #[test]
+    fn compares_float_and_16_bit_data() {
+        let half = |values: &[u16]| {
+            values
+                .iter()
+                .flat_map(|value| value.to_ne_bytes().to_vec())
+                .collect::<Vec<_>>()
+        };
+        let result = compare(
+            &half(&[0, 65535]),
+            &half(&[0, 0]),
+            Format::R16Unorm,
+            1,
+            2,
+            CompareTolerance::exact(),
+        )
+        .unwrap();
+        assert_eq!(result.worst_texel, Some((0, 1, 0)));
+        assert_eq!(result.max_difference, 1.0);
+
+        let nan = f32::NAN.to_ne_bytes();
+        let result = compare(
+            &nan,
+            &nan,
+            Format::R32Float,
+            1,
+            1,
+            CompareTolerance::exact(),
+        );
+        assert!(result.unwrap().matches());
+        match compare(
+            &nan,
+            &[0; 2],
+            Format::R32Float,
+            1,
+            1,
+            CompareTolerance::exact(),
+        ) {
+            Err(TextureError::LevelSize { .. }) => {}
+            other => panic!("{:?}", other),
+        }
+    }
+}
@@ -0,0 +1,713 @@
+//! Block compression of texture data on CPU.
+
+use hal::format::Format;
+use hal::image::{Extent, Layer};
+use hal::Features;
+
+/// Block compressed format data is encoded into on build.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum BlockCompression {
+    /// RGB with 1-bit alpha. Requires `Rgba8Unorm` or `Rgba8Srgb` data.
+    /// Texels with alpha below one half become transparent.
+    Bc1,
+    /// Single channel. Encodes red channel of `R8Unorm`, `Rg8Unorm` or `Rgba8Unorm` data.
+    Bc4,
+    /// Two channels. Encodes red and green channels of `Rg8Unorm` or `Rgba8Unorm` data.
+    Bc5,
+}
+
+/// Effort spent on choosing block endpoints.
+/// Both are deterministic.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum CompressionQuality {
+    /// Endpoints span bounding box of block values.
+    Fast,
+    /// Endpoints are fitted along principal axis of block colors and refined,
+    /// BC4 and BC5 blocks try both interpolation modes.
+    High,
+}
+
+impl BlockCompression {
+    /// Compressed format for data of `source` format.
+    /// Returns `None` if the data can't be compressed that way.
+    pub fn format(&self, source: Format) -> Option<Format> {
+        match (*self, source) {
+            (BlockCompression::Bc1, Format::Rgba8Unorm) => Some(Format::Bc1RgbaUnorm),
+            (BlockCompression::Bc1, Format::Rgba8Srgb) => Some(Format::Bc1RgbaSrgb),
+            (BlockCompression::Bc4, Format::R8Unorm)
+            | (BlockCompression::Bc4, Format::Rg8Unorm)
+            | (BlockCompression::Bc4, Format::Rgba8Unorm) => Some(Format::Bc4Unorm),
+            (BlockCompression::Bc5, Format::Rg8Unorm)
+            | (BlockCompression::Bc5, Format::Rgba8Unorm) => Some(Format::Bc5Unorm),
+            _ => None,
+        }
+    }
+}
+
+/// Kind of data compression format is automatically chosen for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum CompressionHint {
+    /// Color with optional cutout alpha. Compressed to BC1, alpha is reduced to 1 bit.
+    ColorSrgb,
+    /// Tangent space normal map with X and Y in red and green channels.
+    /// Compressed to BC5, shader reconstructs Z.
+    NormalMap,
+    /// Single channel data such as roughness or height. Compressed to BC4.
+    SingleChannel,
+}
+
+impl CompressionHint {
+    /// Best compression for the hint the device supports and data of `format` can be encoded with.
+    /// Returns `None` if data should be left uncompressed.
+    pub fn select(&self, format: Format, features: Features) -> Option<BlockCompression> {
+        if !features.contains(Features::FORMAT_BC) {
+            return None;
+        }
+        let compression = match *self {
+            CompressionHint::ColorSrgb => BlockCompression::Bc1,
+            CompressionHint::NormalMap => BlockCompression::Bc5,
+            CompressionHint::SingleChannel => BlockCompression::Bc4,
+        };
+        compression.format(format).map(|_| compression)
+    }
+}
+
+/// Uncompressed data of one mip level.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct LevelData<'a> {
+    /// Format of the data.
+    pub format: Format,
+    /// Extent of the level.
+    pub extent: Extent,
+    /// Texels between rows.
+    pub data_width: u32,
+    /// Rows between slices.
+    pub data_height: u32,
+    /// Number of array layers.
+    pub layers: Layer,
+    /// Texels of all layers.
+    pub data: &'a [u8],
+}
+
+/// Compress all layers of the level.
+/// Partial blocks at the edges repeat the last row and column.
+pub(crate) fn compress(
+    compression: BlockCompression,
+    quality: CompressionQuality,
+    level: LevelData,
+) -> Vec<u8> {
+    let LevelData {
+        format,
+        extent,
+        data,
+        ..
+    } = level;
+    let channels = format.surface_desc().bits as usize / 8;
+    let (width, height) = (extent.width as usize, extent.height as usize);
+    let (data_width, data_height) = (level.data_width as usize, level.data_height as usize);
+    let (blocks_x, blocks_y) = (width.div_ceil(4), height.div_ceil(4));
+    let slices = extent.depth as usize * level.layers as usize;
+    let block_size = match compression {
+        BlockCompression::Bc1 | BlockCompression::Bc4 => 8,
+        BlockCompression::Bc5 => 16,
+    };
+
+    let mut out = Vec::with_capacity(blocks_x * blocks_y * slices * block_size);
+    let mut block = [[0u8; 4]; 16];
+    for slice in 0..slices {
+        for by in 0..blocks_y {
+            for bx in 0..blocks_x {
+                for (index, texel) in block.iter_mut().enumerate() {
+                    let x = (bx * 4 + index % 4).min(width - 1);
+                    let y = (by * 4 + index / 4).min(height - 1);
+                    let offset = ((slice * data_height + y) * data_width + x) * channels;
+                    texel[..channels].copy_from_slice(&data[offset..offset + channels]);
+                }
+                match compression {
+                    BlockCompression::Bc1 => encode_bc1(&block, quality, &mut out),
+                    BlockCompression::Bc4 => encode_bc4(&channel(&block, 0), quality, &mut out),
+                    BlockCompression::Bc5 => {
+                        encode_bc4(&channel(&block, 0), quality, &mut out);
+                        encode_bc4(&channel(&block, 1), quality, &mut out);
+                    }
+                }
+            }
+        }
+    }
+    out
+}
+
+fn channel(block: &[[u8; 4]; 16], index: usize) -> [u8; 16] {
+    let mut values = [0; 16];
+    for (value, texel) in values.iter_mut().zip(block) {
+        *value = texel[index];
+    }
+    values
+}
+
+/// Encode block of RGBA texels as BC1.
+/// Blocks with transparent texels use three color mode.
+fn encode_bc1(block: &[[u8; 4]; 16], quality: CompressionQuality, out: &mut Vec<u8>) {
+    let transparent = block.iter().map(|texel| texel[3] < 128).collect::<Vec<_>>();
+    let colors = block
+        .iter()
+        .zip(&transparent)
+        .filter(|&(_, &transparent)| !transparent)
+        .map(|(texel, _)| [texel[0] as f32, texel[1] as f32, texel[2] as f32])
+        .collect::<Vec<_>>();
+
+    if colors.is_empty() {
+        out.extend_from_slice(&[0, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF]);
+        return;
+    }
+
+    let three_color = colors.len() < 16;
+    let (a, b) = match quality {
+        CompressionQuality::Fast => bounding_box(&colors),
+        CompressionQuality::High => principal_endpoints(&colors),
+    };
+    let mut best = bc1_block(block, &transparent, pack_565(a), pack_565(b), three_color);
+
+    if quality == CompressionQuality::High && !three_color {
+        let palette = bc1_palette(best.0, best.1, false);
+        let indices = (0..16)
+            .map(|index| nearest(&palette[..4], block[index]))
+            .collect::<Vec<_>>();
+        if let Some((a, b)) = refine_565(block, &indices) {
+            let refined = bc1_block(block, &transparent, a, b, false);
+            if refined.3 < best.3 {
+                best = refined;
+            }
+        }
+    }
+
+    let (c0, c1, indices, _) = best;
+    out.extend_from_slice(&c0.to_le_bytes());
+    out.extend_from_slice(&c1.to_le_bytes());
+    out.extend_from_slice(&indices.to_le_bytes());
+}
+
+/// Order endpoints for the mode and choose indices.
+/// Returns endpoints, packed indices and squared error of opaque texels.
+fn bc1_block(
+    block: &[[u8; 4]; 16],
+    transparent: &[bool],
+    a: u16,
+    b: u16,
+    three_color: bool,
+) -> (u16, u16, u32, u32) {
+    // Four color mode requires `c0 > c1`, three color mode requires `c0 <= c1`.
+    let (c0, c1) = if three_color == (a <= b) {
+        (a, b)
+    } else {
+        (b, a)
+    };
+    let palette = bc1_palette(c0, c1, three_color);
+    let opaque = if three_color {
+        &palette[..3]
+    } else {
+        &palette[..]
+    };
+    let equal = c0 == c1 && !three_color;
+
+    let mut indices = 0u32;
+    let mut error = 0;
+    for (index, texel) in block.iter().enumerate() {
+        let selected = if transparent[index] {
+            3
+        } else if equal {
+            0
+        } else {
+            nearest(opaque, *texel)
+        };
+        if !transparent[index] {
+            error += distance(palette[selected], *texel);
+        }
+        indices |= (selected as u32) << (index * 2);
+    }
+    (c0, c1, indices, error)
+}
+
+fn bc1_palette(c0: u16, c1: u16, three_color: bool) -> [[u8; 3]; 4] {
+    let (p0, p1) = (unpack_565(c0), unpack_565(c1));
+    let mix = |w0: u32, w1: u32| {
+        let mut color = [0; 3];
+        for (channel, value) in color.iter_mut().enumerate() {
+            *value = ((p0[channel] as u32 * w0 + p1[channel] as u32 * w1) / (w0 + w1)) as u8;
+        }
+        color
+    };
+    if three_color {
+        [p0, p1, mix(1, 1), [0, 0, 0]]
+    } else {
+        [p0, p1, mix(2, 1), mix(1, 2)]
+    }
+}
+
+/// Least squares fit of endpoints to texels given their indices in four color mode.
+fn refine_565(block: &[[u8; 4]; 16], indices: &[usize]) -> Option<(u16, u16)> {
+    const WEIGHTS: [f32; 4] = [1.0, 0.0, 2.0 / 3.0, 1.0 / 3.0];
+    let (mut aa, mut ab, mut bb) = (0.0, 0.0, 0.0);
+    let (mut ax, mut bx) = ([0.0f32; 3], [0.0f32; 3]);
+    for (texel, &index) in block.iter().zip(indices) {
+        let a = WEIGHTS[index];
+        let b = 1.0 - a;
+        aa += a * a;
+        ab += a * b;
+        bb += b * b;
+        for channel in 0..3 {
+            ax[channel] += a * texel[channel] as f32;
+            bx[channel] += b * texel[channel] as f32;
+        }
+    }
+    let det = aa * bb - ab * ab;
+    if det.abs() < 1e-6 {
+        return None;
+    }
+    let mut c0 = [0.0; 3];
+    let mut c1 = [0.0; 3];
+    for channel in 0..3 {
+        c0[channel] = (bb * ax[channel] - ab * bx[channel]) / det;
+        c1[channel] = (aa * bx[channel] - ab * ax[channel]) / det;
+    }
+    Some((pack_565(c0), pack_565(c1)))
+}
+
+/// Corners of bounding box along the diagonal that follows colors.
+fn bounding_box(colors: &[[f32; 3]]) -> ([f32; 3], [f32; 3]) {
+    let mut min = [255.0f32; 3];
+    let mut max = [0.0f32; 3];
+    let mut mean = [0.0f32; 3];
+    for color in colors {
+        for channel in 0..3 {
+            min[channel] = min[channel].min(color[channel]);
+            max[channel] = max[channel].max(color[channel]);
+            mean[channel] += color[channel] / colors.len() as f32;
+        }
+    }
+
+    // Flip channels that decrease while the widest one increases.
+    let widest = (0..3)
+        .max_by(|&a, &b| (max[a] - min[a]).partial_cmp(&(max[b] - min[b])).unwrap())
+        .unwrap();
+    for channel in 0..3 {
+        let covariance = colors
+            .iter()
+            .map(|color| (color[widest] - mean[widest]) * (color[channel] - mean[channel]))
+            .sum::<f32>();
+        if covariance < 0.0 {
+            ::std::mem::swap(&mut min[channel], &mut max[channel]);
+        }
+    }
+    (max, min)
+}
+
+/// Extremes of colors projected onto their principal axis.
+fn principal_endpoints(colors: &[[f32; 3]]) -> ([f32; 3], [f32; 3]) {
+    let count = colors.len() as f32;
+    let mut mean = [0.0f32; 3];
+    for color in colors {
+        for channel in 0..3 {
+            mean[channel] += color[channel] / count;
+        }
+    }
+
+    let mut covariance = [[0.0f32; 3]; 3];
+    for color in colors {
+        let d = [color[0] - mean[0], color[1] - mean[1], color[2] - mean[2]];
+        for i in 0..3 {
+            for j in 0..3 {
+                covariance[i][j] += d[i] * d[j];
+            }
+        }
+    }
+
+    // Power iteration starting from bounding box diagonal.
+    let (max, min) = bounding_box(colors);
+    let mut axis = [max[0] - min[0], max[1] - min[1], max[2] - min[2]];
+    for _ in 0..8 {
+        let next = [
+            dot(covariance[0], axis),
+            dot(covariance[1], axis),
+            dot(covariance[2], axis),
+        ];
+        let length = dot(next, next).sqrt();
+        if length < 1e-6 {
+            break;
+        }
+        axis = [next[0] / length, next[1] / length, next[2] / length];
+    }
+    let length = dot(axis, axis).sqrt();
+    if length < 1e-6 {
+        return (mean, mean);
+    }
+    let axis = [axis[0] / length, axis[1] / length, axis[2] / length];
+
+    let (mut low, mut high) = (f32::INFINITY, f32::NEG_INFINITY);
+    for color in colors {
+        let t = dot(
+            [color[0] - mean[0], color[1] - mean[1], color[2] - mean[2]],
+            axis,
+        );
+        low = low.min(t);
+        high = high.max(t);
+    }
+    let point = |t: f32| {
+        [
+            mean[0] + axis[0] * t,
+            mean[1] + axis[1] * t,
+            mean[2] + axis[2] * t,
+        ]
+    };
+    (point(high), point(low))
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn pack_565(color: [f32; 3]) -> u16 {
+    let quantize = |value: f32, max: f32| (value.clamp(0.0, 255.0) * max / 255.0).round() as u16;
+    quantize(color[0], 31.0) << 11 | quantize(color[1], 63.0) << 5 | quantize(color[2], 31.0)
+}
+
+fn unpack_565(color: u16) -> [u8; 3] {
+    let r = (color >> 11 & 0x1F) as u8;
+    let g = (color >> 5 & 0x3F) as u8;
+    let b = (color & 0x1F) as u8;
+    [r << 3 | r >> 2, g << 2 | g >> 4, b << 3 | b >> 2]
+}
+
+fn distance(color: [u8; 3], texel: [u8; 4]) -> u32 {
+    (0..3)
+        .map(|channel| {
+            let d = color[channel] as i32 - texel[channel] as i32;
+            (d * d) as u32
+        })
+        .sum()
+}
+
+fn nearest(palette: &[[u8; 3]], texel: [u8; 4]) -> usize {
+    (0..palette.len())
+        .min_by_key(|&index| distance(palette[index], texel))
+        .unwrap()
+}
+
+/// Encode block of single channel values as BC4.
+fn encode_bc4(values: &[u8; 16], quality: CompressionQuality, out: &mut Vec<u8>) {
+    let min = *values.iter().min().unwrap();
+    let max = *values.iter().max().unwrap();
+    let mut best = bc4_block(values, max, min);
+
+    if quality == CompressionQuality::High {
+        // Six value mode represents 0 and 255 exactly,
+        // so endpoints only need to span the rest.
+        let inner = values
+            .iter()
+            .cloned()
+            .filter(|&value| value != 0 && value != 255);
+        let low = inner.clone().min();
+        let high = inner.max();
+        if let (Some(low), Some(high)) = (low, high) {
+            let six = bc4_block(values, low, high);
+            if six.2 < best.2 {
+                best = six;
+            }
+        }
+    }
+
+    let (r0, r1, indices, _) = best;
+    out.push(r0);
+    out.push(r1);
+    out.extend_from_slice(&indices.to_le_bytes()[..6]);
+}
+
+/// Choose indices for endpoints. Mode is selected by endpoint order.
+/// Returns endpoints, packed indices and squared error.
+fn bc4_block(values: &[u8; 16], r0: u8, r1: u8) -> (u8, u8, u64, u32) {
+    let palette = bc4_palette(r0, r1);
+    let mut indices = 0u64;
+    let mut error = 0;
+    for (index, &value) in values.iter().enumerate() {
+        let selected = (0..8)
+            .min_by_key(|&entry| (palette[entry] as i32 - value as i32).abs())
+            .unwrap();
+        let d = palette[selected] as i32 - value as i32;
+        error += (d * d) as u32;
+        indices |= (selected as u64) << (index * 3);
+    }
+    (r0, r1, indices, error)
+}
+
+fn bc4_palette(r0: u8, r1: u8) -> [u8; 8] {
+    let (a, b) = (r0 as u32, r1 as u32);
+    let mut palette = [r0, r1, 0, 0, 0, 0, 0, 0];
+    if r0 > r1 {
+        for (i, value) in palette.iter_mut().enumerate().skip(2) {
+            *value = (((8 - i as u32) * a + (i as u32 - 1) * b + 3) / 7) as u8;
+        }
+    } else {
+        for (i, value) in palette.iter_mut().enumerate().take(6).skip(2) {
+            *value = (((6 - i as u32) * a + (i as u32 - 1) * b + 2) / 5) as u8;
+        }
+        palette[6] = 0;
+        palette[7] = 255;
+    }
+    palette
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Decode BC1 block the way GPU does, with exact interpolation.
+    fn decode_bc1(block: &[u8]) -> Vec<[u8; 4]> {
+        let c0 = u16::from_le_bytes([block[0], block[1]]);
+        let c1 = u16::from_le_bytes([block[2], block[3]]);
+        let (p0, p1) = (unpack_565(c0), unpack_565(c1));
+        let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+        let mix = |t: f32| {
+            let mut texel = [0, 0, 0, 255];
+            for channel in 0..3 {
+                let (a, b) = (p0[channel] as f32, p1[channel] as f32);
+                texel[channel] = (a + (b - a) * t).round() as u8;
+            }
+            texel
+        };
+        (0..16)
+            .map(|index| match (indices >> (index * 2) & 3, c0 > c1) {
+                (0, _) => mix(0.0),
+                (1, _) => mix(1.0),
+                (2, true) => mix(1.0 / 3.0),
+                (3, true) => mix(2.0 / 3.0),
+                (2, false) => mix(0.5),
+                _ => [0; 4],
+            })
+            .collect()
+    }
+
+    /// Decode BC4 block the way GPU does, with exact interpolation.
+    fn decode_bc4(block: &[u8]) -> Vec<u8> {
+        let (r0, r1) = (block[0] as f32, block[1] as f32);
+        let mut bits = [0; 8];
+        bits[..6].copy_from_slice(&block[2..8]);
+        let indices = u64::from_le_bytes(bits);
+        (0..16)
+            .map(|index| {
+                let mix = |t: f32| (r0 + (r1 - r0) * t).round() as u8;
+                match (indices >> (index * 3) & 7, r0 > r1) {
+                    (0, _) => mix(0.0),
+                    (1, _) => mix(1.0),
+                    (i, true) => mix((i - 1) as f32 / 7.0),
+                    (6, false) => 0,
+                    (7, false) => 255,
+                    (i, false) => mix((i - 1) as f32 / 5.0),
+                }
+            })
+            .collect()
+    }
+
+    /// Compress `data` of single layer 2D image and decode blocks back to RGBA texels.
+    fn round_trip(
+        compression: BlockCompression,
+        quality: CompressionQuality,
+        format: Format,
+        (width, height): (u32, u32),
+        data: &[u8],
+    ) -> Vec<[u8; 4]> {
+        let level = LevelData {
+            format,
+            extent: Extent {
+                width,
+                height,
+                depth: 1,
+            },
+            data_width: width,
+            data_height: height,
+            layers: 1,
+            data,
+        };
+        let blocks = compress(compression, quality, level);
+        assert_eq!(blocks, compress(compression, quality, level));
+        let block_size = blocks.len() / (width.div_ceil(4) * height.div_ceil(4)) as usize;
+        let blocks_x = width.div_ceil(4) as usize;
+
+        let mut texels = vec![[0; 4]; (width * height) as usize];
+        for (index, block) in blocks.chunks(block_size).enumerate() {
+            let decoded = match compression {
+                BlockCompression::Bc1 => decode_bc1(block),
+                BlockCompression::Bc4 => decode_bc4(block)
+                    .into_iter()
+                    .map(|red| [red, 0, 0, 255])
+                    .collect(),
+                BlockCompression::Bc5 => decode_bc4(&block[..8])
+                    .into_iter()
+                    .zip(decode_bc4(&block[8..]))
+                    .map(|(red, green)| [red, green, 0, 255])
+                    .collect(),
+            };
+            for (texel_index, texel) in decoded.into_iter().enumerate() {
+                let x = index % blocks_x * 4 + texel_index % 4;
+                let y = index / blocks_x * 4 + texel_index / 4;
+                if x < width as usize && y < height as usize {
+                    texels[y * width as usize + x] = texel;
+                }
+            }
+        }
+        texels
+    }
+
+    fn max_error(decoded: &[[u8; 4]], source: &[u8], channels: usize) -> u8 {
+        decoded
+            .iter()
+            .zip(source.chunks(4))
+            .flat_map(|(decoded, source)| {
+                (0..channels).map(move |channel| {
+                    (decoded[channel] as i32 - source[channel] as i32).unsigned_abs() as u8
+                })
+            })
+            .max()
+            .unwrap()
+    }
+
+    /// Texels along line in color space, brightening to the bottom right.
+    fn gradient(width: u32, height: u32) -> Vec<u8> {
+        (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x + y) * 255 / (width + height - 2)))
+            .flat_map(|t| vec![t as u8, (64 + t / 2) as u8, (255 - t) as u8, 255])
+            .collect()
+    }
+
+    #[test]
+    fn bc1_blocks_decode_close_to_source() {
+        let data = gradient(8, 8);
+        for &quality in &[CompressionQuality::Fast, CompressionQuality::High] {
+            let decoded = round_trip(
+                BlockCompression::Bc1,
+                quality,
+                Format::Rgba8Unorm,
+                (8, 8),
+                &data,
+            );
+            let error = max_error(&decoded, &data, 4);
+            assert!(error <= 20, "{:?} error {}", quality, error);
+        }
+    }
+
+    #[test]
+    fn bc1_cutout_alpha_is_preserved() {
+        let mut data = gradient(4, 4);
+        for texel in data.chunks_mut(4).step_by(3) {
+            texel[3] = 0;
+        }
+        let decoded = round_trip(
+            BlockCompression::Bc1,
+            CompressionQuality::High,
+            Format::Rgba8Unorm,
+            (4, 4),
+            &data,
+        );
+        for (decoded, source) in decoded.iter().zip(data.chunks(4)) {
+            assert_eq!(decoded[3], if source[3] == 0 { 0 } else { 255 });
+        }
+    }
+
+    #[test]
+    fn bc4_and_bc5_blocks_decode_close_to_source() {
+        let data = gradient(8, 4);
+        for &quality in &[CompressionQuality::Fast, CompressionQuality::High] {
+            let bc4 = round_trip(
+                BlockCompression::Bc4,
+                quality,
+                Format::Rgba8Unorm,
+                (8, 4),
+                &data,
+            );
+            assert!(max_error(&bc4, &data, 1) <= 10);
+            let bc5 = round_trip(
+                BlockCompression::Bc5,
+                quality,
+                Format::Rgba8Unorm,
+                (8, 4),
+                &data,
+            );
+            assert!(max_error(&bc5, &data, 2) <= 10);
+        }
+
+        let extremes = [
+            0u8, 255, 0, 128, 255, 64, 0, 255, 192, 0, 255, 32, 0, 255, 0, 255,
+        ];
+        let decoded = round_trip(
+            BlockCompression::Bc4,
+            CompressionQuality::High,
+            Format::R8Unorm,
+            (4, 4),
+            &extremes,
+        );
+        let red = decoded.iter().map(|texel| texel[0]).collect::<Vec<_>>();
+        for (&decoded, &source) in red.iter().zip(&extremes) {
+            if source == 0 || source == 255 {
+                assert_eq!(decoded, source);
+            }
+        }
+    }
+
+    #[test]
+    fn partial_blocks_clamp_to_edge() {
+        let data = gradient(6, 5);
+        let level = LevelData {
+            format: Format::Rgba8Unorm,
+            extent: Extent {
+                width: 6,
+                height: 5,
+                depth: 1,
+            },
+            data_width: 6,
+            data_height: 5,
+            layers: 1,
+            data: &data,
+        };
+        let blocks = compress(BlockCompression::Bc1, CompressionQuality::High, level);
+        assert_eq!(blocks.len(), 4 * 8);
+        let decoded = round_trip(
+            BlockCompression::Bc1,
+            CompressionQuality::High,
+            Format::Rgba8Unorm,
+            (6, 5),
+            &data,
+        );
+        assert!(max_error(&decoded, &data, 4) <= 20);
+    }
+
+    #[test]
+    fn auto_compression_depends_on_features_and_data() {
+        let bc = Features::FORMAT_BC;
+        let cases = [
+            (
+                CompressionHint::ColorSrgb,
+                Format::Rgba8Srgb,
+                Some(BlockCompression::Bc1),
+            ),
+            (CompressionHint::ColorSrgb, Format::R8Unorm, None),
+            (
+                CompressionHint::NormalMap,
+                Format::Rg8Unorm,
+                Some(BlockCompression::Bc5),
+            ),
+            (CompressionHint::NormalMap, Format::Rgba8Srgb, None),
+            (
+                CompressionHint::SingleChannel,
+                Format::R8Unorm,
+                Some(BlockCompression::Bc4),
+            ),
+            (CompressionHint::SingleChannel, Format::Rgba16Float, None),
+        ];
+        for &(hint, format, expected) in &cases {
+            assert_eq!(hint.select(format, bc), expected, "{:?} {:?}", hint, format);
+            assert_eq!(hint.select(format, Features::empty()), None);
+        }
+    }
+}
@@ -0,0 +1,527 @@
+//! Conversions of source data into texel formats.
+
+use hal::format::{ChannelType, Format, SurfaceType};
+
+use error::TextureError;
+
+/// Map each index through the palette into RGBA8 texels.
+/// Texels with `transparent` index get zero alpha.
+pub(crate) fn expand_indexed(
+    indices: &[u8],
+    palette: &[[u8; 4]],
+    transparent: Option<u8>,
+) -> Result<Vec<[u8; 4]>, TextureError> {
+    indices
+        .iter()
+        .map(|&index| match palette.get(index as usize) {
+            Some(&[r, g, b, _]) if Some(index) == transparent => Ok([r, g, b, 0]),
+            Some(&color) => Ok(color),
+            None => Err(TextureError::IndexOutOfRange {
+                index,
+                palette: palette.len(),
+            }),
+        })
+        .collect()
+}
+
+/// Order of packed texels within a byte.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum BitOrder {
+    /// First texel is in the most significant bits.
+    MsbFirst,
+    /// First texel is in the least significant bits.
+    LsbFirst,
+}
+
+/// Unpack rows of 1, 2 or 4 bits per texel padded to byte boundary
+/// into one byte per texel.
+/// With `full_range` values are scaled to cover `0 ..= 255`.
+pub(crate) fn expand_bitfield(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    bits: u8,
+    order: BitOrder,
+    full_range: bool,
+) -> Result<Vec<u8>, TextureError> {
+    if bits != 1 && bits != 2 && bits != 4 {
+        Err(TextureError::UnsupportedBitDepth { bits })?;
+    }
+    let width = width as usize;
+    let row_size = (width * bits as usize).div_ceil(8);
+    let expected = row_size * height as usize;
+    if data.len() < expected {
+        Err(TextureError::LevelSize {
+            level: 0,
+            expected,
+            actual: data.len(),
+        })?;
+    }
+
+    let per_byte = 8 / bits as usize;
+    let mask = (1u8 << bits) - 1;
+    let scale = if full_range { 255 / mask } else { 1 };
+    let mut texels = Vec::with_capacity(width * height as usize);
+    for row in data[..expected].chunks(row_size) {
+        texels.extend((0..width).map(|x| {
+            let slot = x % per_byte;
+            let shift = match order {
+                BitOrder::MsbFirst => 8 - bits as usize * (slot + 1),
+                BitOrder::LsbFirst => bits as usize * slot,
+            };
+            ((row[x / per_byte] >> shift) & mask) * scale
+        }));
+    }
+    Ok(texels)
+}
+
+/// Set alpha to zero for RGBA8 texels which color channels are within `tolerance` of `key`.
+/// Data consists of slices of `height` rows with `width` texels.
+/// With `bleed` color of keyed texels is replaced by average of opaque neighbours
+/// to avoid dark halos under bilinear filtering.
+pub(crate) fn chroma_key(
+    data: &mut [u8],
+    width: usize,
+    height: usize,
+    key: [u8; 3],
+    tolerance: u8,
+    bleed: bool,
+) {
+    let matches = |texel: &[u8]| {
+        texel
+            .iter()
+            .zip(&key)
+            .all(|(&value, &key)| (value as i16 - key as i16).abs() <= tolerance as i16)
+    };
+    for texel in data.chunks_exact_mut(4) {
+        if matches(texel) {
+            texel[3] = 0;
+        }
+    }
+
+    if !bleed || width == 0 || height == 0 {
+        return;
+    }
+    let slice_size = width * height * 4;
+    for slice in data.chunks_exact_mut(slice_size) {
+        let source = slice.to_vec();
+        for y in 0..height {
+            for x in 0..width {
+                let index = (y * width + x) * 4;
+                if source[index + 3] != 0 {
+                    continue;
+                }
+                let mut sum = [0u32; 3];
+                let mut count = 0;
+                for ny in y.saturating_sub(1)..(y + 2).min(height) {
+                    for nx in x.saturating_sub(1)..(x + 2).min(width) {
+                        let neighbour = &source[(ny * width + nx) * 4..][..4];
+                        if neighbour[3] != 0 {
+                            for (sum, &value) in sum.iter_mut().zip(neighbour) {
+                                *sum += value as u32;
+                            }
+                            count += 1;
+                        }
+                    }
+                }
+                for (value, sum) in slice[index..index + 3].iter_mut().zip(&sum) {
+                    if let Some(mean) = sum.checked_div(count) {
+                        *value = mean as u8;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// How border added around the image is filled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum BorderMode {
+    /// Fill with RGBA color. Requires format with 8-bit channels.
+    Solid([u8; 4]),
+    /// Repeat nearest edge texel.
+    ClampEdge,
+}
+
+/// Bytes of single texel of `format` with 8-bit channels filled with RGBA `color`.
+pub(crate) fn solid_texel(format: Format, color: [u8; 4]) -> Option<Vec<u8>> {
+    let [r, g, b, a] = color;
+    let texel = match format.base_format().0 {
+        SurfaceType::R8 => vec![r],
+        SurfaceType::R8_G8 => vec![r, g],
+        SurfaceType::R8_G8_B8 => vec![r, g, b],
+        SurfaceType::B8_G8_R8 => vec![b, g, r],
+        SurfaceType::R8_G8_B8_A8 => vec![r, g, b, a],
+        SurfaceType::B8_G8_R8_A8 => vec![b, g, r, a],
+        _ => return None,
+    };
+    Some(texel)
+}
+
+/// Surround each slice with `border_x` columns and `border_y` rows.
+/// Slices consist of `data_height` rows with `data_width` texels of which
+/// `height` rows with `width` texels are used.
+/// Border is filled with `solid` texel or with nearest edge texels if `None`.
+/// Result is tightly packed.
+pub(crate) fn pad(
+    data: &[u8],
+    texel_size: usize,
+    (width, height): (usize, usize),
+    (data_width, data_height): (usize, usize),
+    slices: usize,
+    (border_x, border_y): (usize, usize),
+    solid: Option<&[u8]>,
+) -> Vec<u8> {
+    let padded_width = width + border_x * 2;
+    let padded_height = height + border_y * 2;
+    let mut padded = Vec::with_capacity(padded_width * padded_height * slices * texel_size);
+    for slice in 0..slices {
+        for padded_y in 0..padded_height {
+            let y = padded_y.saturating_sub(border_y).min(height - 1);
+            let inside_y = padded_y >= border_y && padded_y < border_y + height;
+            for padded_x in 0..padded_width {
+                let x = padded_x.saturating_sub(border_x).min(width - 1);
+                let inside = inside_y && padded_x >= border_x && padded_x < border_x + width;
+                match solid {
+                    Some(texel) if !inside => padded.extend_from_slice(texel),
+                    _ => {
+                        let offset = ((slice * data_height + y) * data_width + x) * texel_size;
+                        padded.extend_from_slice(&data[offset..offset + texel_size]);
+                    }
+                }
+            }
+        }
+    }
+    padded
+}
+
+/// Dithering applied when texels are converted into format with less precision.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DitherMode {
+    /// Round to nearest representable value.
+    #[default]
+    None,
+    /// Ordered dithering with 4x4 Bayer matrix.
+    Bayer4,
+    /// Ordered dithering with 8x8 Bayer matrix.
+    Bayer8,
+    /// Floyd-Steinberg error diffusion.
+    FloydSteinberg,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Source {
+    Rgba8,
+    Bgra8,
+    Rgba32Float,
+}
+
+/// Conversion of RGBA texels into packed 16-bit formats
+/// or between RGBA and BGRA byte order.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Conversion {
+    source: Source,
+    /// Channel index and bit count of packed fields from most significant.
+    fields: &'static [(usize, u32)],
+    /// Swap red and blue bytes instead of packing.
+    swap_red_blue: bool,
+}
+
+impl Conversion {
+    /// Find conversion between formats if supported.
+    pub(crate) fn new(src: Format, dst: Format) -> Option<Self> {
+        let source = match src.base_format() {
+            (SurfaceType::R8_G8_B8_A8, ChannelType::Unorm)
+            | (SurfaceType::R8_G8_B8_A8, ChannelType::Srgb) => Source::Rgba8,
+            (SurfaceType::B8_G8_R8_A8, ChannelType::Unorm)
+            | (SurfaceType::B8_G8_R8_A8, ChannelType::Srgb) => Source::Bgra8,
+            (SurfaceType::R32_G32_B32_A32, ChannelType::Float) => Source::Rgba32Float,
+            _ => return None,
+        };
+        let (dst_surface, dst_channel) = dst.base_format();
+        let swap_red_blue = match (source, dst_surface) {
+            (Source::Rgba8, SurfaceType::B8_G8_R8_A8)
+            | (Source::Bgra8, SurfaceType::R8_G8_B8_A8) => {
+                // Channels are moved as is, so they must be encoded the same way.
+                if src.base_format().1 != dst_channel {
+                    return None;
+                }
+                true
+            }
+            _ => false,
+        };
+        if swap_red_blue {
+            return Some(Conversion {
+                source,
+                fields: &[],
+                swap_red_blue,
+            });
+        }
+        let fields: &'static [(usize, u32)] = match dst.base_format() {
+            (SurfaceType::R5_G6_B5, ChannelType::Unorm) => &[(0, 5), (1, 6), (2, 5)],
+            (SurfaceType::B5_G6_R5, ChannelType::Unorm) => &[(2, 5), (1, 6), (0, 5)],
+            (SurfaceType::R5_G5_B5_A1, ChannelType::Unorm) => &[(0, 5), (1, 5), (2, 5), (3, 1)],
+            (SurfaceType::B5_G5_R5_A1, ChannelType::Unorm) => &[(2, 5), (1, 5), (0, 5), (3, 1)],
+            (SurfaceType::A1_R5_G5_B5, ChannelType::Unorm) => &[(3, 1), (0, 5), (1, 5), (2, 5)],
+            (SurfaceType::R4_G4_B4_A4, ChannelType::Unorm) => &[(0, 4), (1, 4), (2, 4), (3, 4)],
+            (SurfaceType::B4_G4_R4_A4, ChannelType::Unorm) => &[(2, 4), (1, 4), (0, 4), (3, 4)],
+            _ => return None,
+        };
+        Some(Conversion {
+            source,
+            fields,
+            swap_red_blue,
+        })
+    }
+
+    fn source_bits(&self) -> u32 {
+        match self.source {
+            Source::Rgba8 | Source::Bgra8 => 8,
+            Source::Rgba32Float => 32,
+        }
+    }
+
+    fn source_size(&self) -> usize {
+        match self.source {
+            Source::Rgba8 | Source::Bgra8 => 4,
+            Source::Rgba32Float => 16,
+        }
+    }
+
+    fn read(&self, texel: &[u8]) -> [f32; 4] {
+        match self.source {
+            Source::Rgba8 => unorm8([texel[0], texel[1], texel[2], texel[3]]),
+            Source::Bgra8 => unorm8([texel[2], texel[1], texel[0], texel[3]]),
+            Source::Rgba32Float => {
+                let mut rgba = [0.0; 4];
+                for (value, bytes) in rgba.iter_mut().zip(texel.chunks_exact(4)) {
+                    let mut raw = [0u8; 4];
+                    raw.copy_from_slice(bytes);
+                    *value = f32::from_bits(u32::from_ne_bytes(raw)).clamp(0.0, 1.0);
+                }
+                rgba
+            }
+        }
+    }
+
+    /// Convert slices of `height` rows with `width` texels.
+    pub(crate) fn convert(
+        &self,
+        data: &[u8],
+        width: usize,
+        height: usize,
+        dither: DitherMode,
+    ) -> Vec<u8> {
+        if self.swap_red_blue {
+            let mut result = data.to_vec();
+            for texel in result.chunks_exact_mut(4) {
+                texel.swap(0, 2);
+            }
+            return result;
+        }
+        let texels = data.len() / self.source_size();
+        let mut result = Vec::with_capacity(texels * 2);
+        let mut errors = vec![[0.0f32; 4]; (width + 2) * 2];
+        for (index, texel) in data.chunks_exact(self.source_size()).enumerate() {
+            let x = index % width;
+            let y = index / width % height;
+            if x == 0 {
+                // Move to the next row of error accumulators.
+                let (current, next) = errors.split_at_mut(width + 2);
+                if y == 0 {
+                    for error in next.iter_mut() {
+                        *error = [0.0; 4];
+                    }
+                }
+                current.copy_from_slice(next);
+                for error in next.iter_mut() {
+                    *error = [0.0; 4];
+                }
+            }
+
+            let rgba = self.read(texel);
+            let mut packed = 0u16;
+            for &(channel, bits) in self.fields {
+                let max = ((1u32 << bits) - 1) as f32;
+                let value = rgba[channel] * max;
+                let dither = if bits < self.source_bits() {
+                    dither
+                } else {
+                    DitherMode::None
+                };
+                let quantized = match dither {
+                    DitherMode::None => (value + 0.5).floor(),
+                    DitherMode::Bayer4 => (value + bayer_threshold(x, y, 4)).floor(),
+                    DitherMode::Bayer8 => (value + bayer_threshold(x, y, 8)).floor(),
+                    DitherMode::FloydSteinberg => {
+                        let target = value + errors[x + 1][channel];
+                        let quantized = (target + 0.5).floor().max(0.0).min(max);
+                        let error = target - quantized;
+                        let row = width + 2;
+                        errors[x + 2][channel] += error * 7.0 / 16.0;
+                        errors[row + x][channel] += error * 3.0 / 16.0;
+                        errors[row + x + 1][channel] += error * 5.0 / 16.0;
+                        errors[row + x + 2][channel] += error / 16.0;
+                        quantized
+                    }
+                };
+                packed = packed << bits | quantized.max(0.0).min(max) as u16;
+            }
+            result.extend_from_slice(&packed.to_ne_bytes());
+        }
+        result
+    }
+}
+
+fn unorm8(rgba: [u8; 4]) -> [f32; 4] {
+    [
+        rgba[0] as f32 / 255.0,
+        rgba[1] as f32 / 255.0,
+        rgba[2] as f32 / 255.0,
+        rgba[3] as f32 / 255.0,
+    ]
+}
+
+/// Threshold in `0 .. 1` from `size`x`size` Bayer matrix.
+fn bayer_threshold(x: usize, y: usize, size: usize) -> f32 {
+    (bayer_index(x % size, y % size, size) as f32 + 0.5) / (size * size) as f32
+}
+
+fn bayer_index(x: usize, y: usize, size: usize) -> usize {
+    if size == 1 {
+        return 0;
+    }
+    let half = size / 2;
+    let quadrant = [0, 2, 3, 1][(y / half) * 2 + x / half];
+    4 * bayer_index(x % half, y % half, half) + quadrant
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bitfield_rows_are_padded_to_bytes() {
+        // 13 texels of 1 bit take 2 bytes per row, last 3 bits are padding.
+        let data = [0b1010_0000, 0b0001_1111, 0b0000_0000, 0b0000_1000];
+        let texels = expand_bitfield(&data, 13, 2, 1, BitOrder::MsbFirst, true).unwrap();
+        let mut expected = vec![0; 26];
+        for &x in &[0, 2, 11, 12, 13 + 12] {
+            expected[x] = 255;
+        }
+        assert_eq!(texels, expected);
+
+        // 7 texels of 4 bits take 4 bytes per row, last nibble is padding.
+        let data = [0x21, 0x43, 0x65, 0xf7];
+        let texels = expand_bitfield(&data, 7, 1, 4, BitOrder::LsbFirst, false).unwrap();
+        assert_eq!(texels, vec![1, 2, 3, 4, 5, 6, 7]);
+        let texels = expand_bitfield(&data, 7, 1, 4, BitOrder::MsbFirst, true).unwrap();
+        assert_eq!(texels, vec![34, 17, 68, 51, 102, 85, 255]);
+
+        match expand_bitfield(&data[..3], 7, 1, 4, BitOrder::MsbFirst, false) {
+            Err(TextureError::LevelSize {
+                expected: 4,
+                actual: 3,
+                ..
+            }) => {}
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn chroma_key_matches_within_tolerance() {
+        let mut data = vec![
+            255, 0, 255, 255, // exact key
+            250, 4, 251, 255, // within tolerance
+            240, 0, 255, 255, // outside tolerance
+        ];
+        chroma_key(&mut data, 3, 1, [255, 0, 255], 5, false);
+        assert_eq!(
+            data.chunks(4).map(|texel| texel[3]).collect::<Vec<_>>(),
+            [0, 0, 255]
+        );
+
+        let mut exact = vec![250, 4, 251, 255];
+        chroma_key(&mut exact, 1, 1, [255, 0, 255], 0, false);
+        assert_eq!(exact[3], 255);
+    }
+
+    #[test]
+    fn chroma_bleed_fills_keyed_border() {
+        // 3x3 sprite with keyed border around opaque center.
+        let key = [255, 0, 255, 255];
+        let mut data = (0..9)
+            .flat_map(|i| {
+                if i == 4 {
+                    vec![10, 20, 30, 255]
+                } else {
+                    key.to_vec()
+                }
+            })
+            .collect::<Vec<_>>();
+        chroma_key(&mut data, 3, 3, [255, 0, 255], 0, true);
+        for (i, texel) in data.chunks(4).enumerate() {
+            if i == 4 {
+                assert_eq!(texel, [10, 20, 30, 255]);
+            } else {
+                assert_eq!(texel, [10, 20, 30, 0], "texel {}", i);
+            }
+        }
+    }
+
+    /// Mean absolute error of red channel averaged over 4x4 blocks
+    /// of 16x4 gradient converted to `R5g6b5Unorm`.
+    fn block_error(source: &[u8], dither: DitherMode) -> f32 {
+        let conversion = Conversion::new(Format::Rgba8Unorm, Format::R5g6b5Unorm).unwrap();
+        let packed = conversion.convert(source, 16, 4, dither);
+        let red = packed
+            .chunks(2)
+            .map(|texel| f32::from(u16::from_ne_bytes([texel[0], texel[1]]) >> 11) / 31.0)
+            .collect::<Vec<_>>();
+        let mut error = 0.0;
+        for block in 0..4 {
+            let mean = |value: &dyn Fn(usize) -> f32| {
+                (0..16)
+                    .map(|i| value((i / 4) * 16 + block * 4 + i % 4))
+                    .sum::<f32>()
+                    / 16.0
+            };
+            let expected = mean(&|index| f32::from(source[index * 4]) / 255.0);
+            error += (mean(&|index| red[index]) - expected).abs();
+        }
+        error / 4.0
+    }
+
+    #[test]
+    fn dithering_reduces_gradient_error() {
+        // Shallow gradient over less than two steps of 5-bit channel.
+        let source = (0..64)
+            .flat_map(|i| {
+                let value = 100 + (i % 16) as u8;
+                vec![value, 0, 0, 255]
+            })
+            .collect::<Vec<_>>();
+        let truncated = block_error(&source, DitherMode::None);
+        for &dither in &[DitherMode::Bayer4, DitherMode::FloydSteinberg] {
+            let dithered = block_error(&source, dither);
+            assert!(
+                dithered < truncated,
+                "{:?} error {} is not less than {}",
+                dither,
+                dithered,
+                truncated
+            );
+        }
+
+        let conversion = Conversion::new(Format::Rgba8Unorm, Format::R5g6b5Unorm).unwrap();
+        for &dither in &[DitherMode::Bayer4, DitherMode::Bayer8] {
+            assert_eq!(
+                conversion.convert(&source, 16, 4, dither),
+                conversion.convert(&source, 16, 4, dither)
+            );
+        }
+    }
+}
@@ -0,0 +1,220 @@
+//! Import of DDS images.
+
+use ddsfile::{Caps2, D3D10ResourceDimension, D3DFormat, Dds, DxgiFormat, MiscFlag};
+
+use hal::format::Format;
+use hal::image::{Kind, Layer, Level};
+
+use error::TextureError;
+use format;
+use source::{DecodedTexture, TextureSource};
+use TextureBuilder;
+
+impl TextureBuilder<'static> {
+    /// Create builder from DDS file with all its mip levels, array layers and cube faces.
+    pub fn from_dds(data: &[u8]) -> Result<Self, TextureError> {
+        TextureBuilder::from_source(&DdsImage { data })
+    }
+}
+
+/// DDS file as `TextureSource`.
+/// Block compressed and common uncompressed formats are supported.
+#[derive(Clone, Copy, Debug)]
+pub struct DdsImage<'a> {
+    /// Content of the file.
+    pub data: &'a [u8],
+}
+
+impl<'a> TextureSource for DdsImage<'a> {
+    fn decode(&self) -> Result<DecodedTexture, TextureError> {
+        let dds = Dds::read(self.data).map_err(TextureError::decode)?;
+        let format = dds_format(&dds)?;
+        let kind = dds_kind(&dds);
+        let levels = dds.get_num_mipmap_levels().clamp(1, Level::MAX as u32) as Level;
+
+        // File stores all levels of one layer before the next layer,
+        // while decoded texture stores all layers of one level together.
+        let layers = kind.num_layers();
+        let mut decoded = vec![Vec::new(); levels as usize];
+        let mut data = &dds.data[..];
+        for _ in 0..layers {
+            for (level, decoded) in decoded.iter_mut().enumerate() {
+                let size = format::level_size(format, kind.extent().at_level(level as Level), 1);
+                // Truncated levels are reported by size check of `from_source`.
+                let (layer, rest) = data.split_at(size.min(data.len()));
+                decoded.extend_from_slice(layer);
+                data = rest;
+            }
+        }
+
+        Ok(DecodedTexture {
+            kind,
+            format,
+            levels: decoded,
+        })
+    }
+}
+
+fn dds_kind(dds: &Dds) -> Kind {
+    let (width, height, depth) = (dds.get_width(), dds.get_height(), dds.get_depth());
+    match dds.header10 {
+        Some(ref header) => {
+            let cube = header.misc_flag.contains(MiscFlag::TEXTURECUBE);
+            let layers = header
+                .array_size
+                .max(1)
+                .saturating_mul(if cube { 6 } else { 1 });
+            // Layers past the limit are left in the data and fail size check.
+            let layers = layers.min(Layer::MAX as u32) as Layer;
+            match header.resource_dimension {
+                D3D10ResourceDimension::Texture1D => Kind::D1(width, layers),
+                D3D10ResourceDimension::Texture3D => Kind::D3(width, height, depth),
+                _ => Kind::D2(width, height, layers, 1),
+            }
+        }
+        None if dds.header.caps2.contains(Caps2::VOLUME) => Kind::D3(width, height, depth),
+        None if dds.header.caps2.contains(Caps2::CUBEMAP) => Kind::D2(width, height, 6, 1),
+        None => Kind::D2(width, height, 1, 1),
+    }
+}
+
+fn dds_format(dds: &Dds) -> Result<Format, TextureError> {
+    let unsupported = |format: String| TextureError::SourceFormatUnsupported {
+        container: "DDS",
+        format,
+    };
+    if let Some(format) = dds.get_dxgi_format() {
+        return dxgi_format(format).ok_or_else(|| unsupported(format!("{:?}", format)));
+    }
+    match dds.get_d3d_format() {
+        Some(format) => d3d_format(format).ok_or_else(|| unsupported(format!("{:?}", format))),
+        None => Err(unsupported(format!("{:?}", dds.header.spf))),
+    }
+}
+
+fn dxgi_format(format: DxgiFormat) -> Option<Format> {
+    Some(match format {
+        DxgiFormat::R8_UNorm => Format::R8Unorm,
+        DxgiFormat::R8G8_UNorm => Format::Rg8Unorm,
+        DxgiFormat::R8G8B8A8_UNorm => Format::Rgba8Unorm,
+        DxgiFormat::R8G8B8A8_UNorm_sRGB => Format::Rgba8Srgb,
+        DxgiFormat::B8G8R8A8_UNorm => Format::Bgra8Unorm,
+        DxgiFormat::B8G8R8A8_UNorm_sRGB => Format::Bgra8Srgb,
+        DxgiFormat::R16_Float => Format::R16Float,
+        DxgiFormat::R16G16B16A16_Float => Format::Rgba16Float,
+        DxgiFormat::R32_Float => Format::R32Float,
+        DxgiFormat::R32G32B32A32_Float => Format::Rgba32Float,
+        DxgiFormat::BC1_UNorm => Format::Bc1RgbaUnorm,
+        DxgiFormat::BC1_UNorm_sRGB => Format::Bc1RgbaSrgb,
+        DxgiFormat::BC2_UNorm => Format::Bc2Unorm,
+        DxgiFormat::BC2_UNorm_sRGB => Format::Bc2Srgb,
+        DxgiFormat::BC3_UNorm => Format::Bc3Unorm,
+        DxgiFormat::BC3_UNorm_sRGB => Format::Bc3Srgb,
+        DxgiFormat::BC4_UNorm => Format::Bc4Unorm,
+        DxgiFormat::BC4_SNorm => Format::Bc4Inorm,
+        DxgiFormat::BC5_UNorm => Format::Bc5Unorm,
+        DxgiFormat::BC5_SNorm => Format::Bc5Inorm,
+        DxgiFormat::BC6H_UF16 => Format::Bc6hUfloat,
+        DxgiFormat::BC6H_SF16 => Format::Bc6hFloat,
+        DxgiFormat::BC7_UNorm => Format::Bc7Unorm,
+        DxgiFormat::BC7_UNorm_sRGB => Format::Bc7Srgb,
+        _ => return None,
+    })
+}
+
+fn d3d_format(format: D3DFormat) -> Option<Format> {
+    Some(match format {
+        D3DFormat::L8 => Format::R8Unorm,
+        D3DFormat::A8B8G8R8 => Format::Rgba8Unorm,
+        D3DFormat::A8R8G8B8 => Format::Bgra8Unorm,
+        D3DFormat::R16F => Format::R16Float,
+        D3DFormat::A16B16G16R16F => Format::Rgba16Float,
+        D3DFormat::R32F => Format::R32Float,
+        D3DFormat::A32B32G32R32F => Format::Rgba32Float,
+        D3DFormat::DXT1 => Format::Bc1RgbaUnorm,
+        D3DFormat::DXT3 => Format::Bc2Unorm,
+        D3DFormat::DXT5 => Format::Bc3Unorm,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ddsfile::{AlphaMode, NewDxgiParams};
+
+    fn dds_file(format: DxgiFormat, width: u32, height: u32, levels: u32, layers: u32) -> Vec<u8> {
+        let mut dds = Dds::new_dxgi(NewDxgiParams {
+            height,
+            width,
+            depth: None,
+            format,
+            mipmap_levels: Some(levels),
+            array_layers: Some(layers),
+            caps2: None,
+            is_cubemap: false,
+            resource_dimension: D3D10ResourceDimension::Texture2D,
+            alpha_mode: AlphaMode::Unknown,
+        })
+        .unwrap();
+        for (index, byte) in dds.data.iter_mut().enumerate() {
+            *byte = index as u8;
+        }
+        let mut file = Vec::new();
+        dds.write(&mut file).unwrap();
+        file
+    }
+
+    #[test]
+    fn layers_are_grouped_by_level() {
+        let file = dds_file(DxgiFormat::R8G8B8A8_UNorm, 4, 2, 2, 2);
+        let decoded = DdsImage { data: &file }.decode().unwrap();
+        assert_eq!(decoded.kind, Kind::D2(4, 2, 2, 1));
+        assert_eq!(decoded.format, Format::Rgba8Unorm);
+        // Each layer stores 32 bytes of base level followed by 8 bytes of second level.
+        let expected_base = (0..32).chain(40..72).collect::<Vec<u8>>();
+        let expected_mip = (32..40).chain(72..80).collect::<Vec<u8>>();
+        assert_eq!(decoded.levels, [expected_base, expected_mip]);
+
+        let builder = TextureBuilder::from_dds(&file).unwrap();
+        assert_eq!(builder.levels(), 2);
+        builder.validate().unwrap();
+    }
+
+    #[test]
+    fn block_compressed_levels_round_up() {
+        let file = dds_file(DxgiFormat::BC1_UNorm, 6, 6, 3, 1);
+        let decoded = DdsImage { data: &file }.decode().unwrap();
+        assert_eq!(decoded.format, Format::Bc1RgbaUnorm);
+        let sizes = decoded.levels.iter().map(Vec::len).collect::<Vec<_>>();
+        assert_eq!(sizes, [32, 8, 8]);
+    }
+
+    #[test]
+    fn unknown_formats_and_truncated_files_are_rejected() {
+        let file = dds_file(DxgiFormat::R10G10B10A2_UNorm, 4, 4, 1, 1);
+        match TextureBuilder::from_dds(&file) {
+            Err(TextureError::SourceFormatUnsupported {
+                container: "DDS",
+                format,
+            }) => {
+                assert_eq!(format, "R10G10B10A2_UNorm");
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+
+        let file = dds_file(DxgiFormat::R8G8B8A8_UNorm, 4, 4, 1, 1);
+        match TextureBuilder::from_dds(&file[..file.len() - 1]) {
+            Err(TextureError::LevelSize {
+                level: 0,
+                expected: 64,
+                actual: 63,
+            }) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+        match TextureBuilder::from_dds(&file[..8]) {
+            Err(TextureError::Decode { .. }) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+}
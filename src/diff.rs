@@ -0,0 +1,227 @@
+//! Structural comparison of texture builders.
+
+use TextureBuilder;
+
+/// Aspects that differ between two builders.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BuilderDiff {
+    /// Image kind differs, including extent grown by border.
+    pub kind: bool,
+    /// Texture format differs.
+    pub format: bool,
+    /// Number of mip levels differs.
+    pub levels: bool,
+    /// Bytes of base level or explicit mip levels differ.
+    pub data: bool,
+    /// Row or slice stride of the base level or handling of trailing bytes differs.
+    pub stride: bool,
+    /// Mip generation settings differ.
+    pub mip_settings: bool,
+    /// Image creation or view parameters differ:
+    /// usage, storage flags, memory preference, swizzle, resident levels, ASTC profile,
+    /// final layout, transient attachment mode or per-aspect upload.
+    pub image: bool,
+    /// Transforms applied to the data before upload differ.
+    pub transforms: bool,
+    /// Metadata not affecting image content differs:
+    /// sampling, level of detail clamp, source retention, wrap check, name,
+    /// out of memory policy, pre-transform and budget.
+    pub metadata: bool,
+}
+
+impl BuilderDiff {
+    pub(crate) fn new(a: &TextureBuilder, b: &TextureBuilder) -> Self {
+        let (resolved_a, resolved_b) = (a.resolved(), b.resolved());
+        BuilderDiff {
+            kind: resolved_a.kind != resolved_b.kind,
+            format: a.format != b.format,
+            levels: resolved_a.levels != resolved_b.levels,
+            data: a.data != b.data || a.mip_data != b.mip_data,
+            stride: a.data_width() != b.data_width()
+                || a.data_height() != b.data_height()
+                || a.remaining_bytes_ignored != b.remaining_bytes_ignored,
+            mip_settings: a.generate_mips != b.generate_mips
+                || a.max_levels != b.max_levels
+                || a.min_mip_extent != b.min_mip_extent
+                || a.mip_filter != b.mip_filter
+                || a.mip_renormalize != b.mip_renormalize
+                || a.mip_gamma_correct != b.mip_gamma_correct
+                || a.alpha_coverage != b.alpha_coverage,
+            image: a.usage != b.usage
+                || a.storage_flags != b.storage_flags
+                || a.prefer_unified_memory != b.prefer_unified_memory
+                || a.swizzle != b.swizzle
+                || a.min_resident_level != b.min_resident_level
+                || a.astc_profile != b.astc_profile
+                || resolved_a.final_layout != resolved_b.final_layout
+                || a.transient != b.transient
+                || a.per_aspect_upload != b.per_aspect_upload,
+            transforms: a.source_endianness != b.source_endianness
+                || a.sanitize_non_finite != b.sanitize_non_finite
+                || a.chroma_key != b.chroma_key
+                || a.chroma_bleed != b.chroma_bleed
+                || a.source_format != b.source_format
+                || a.dither != b.dither
+                || a.clear_unspecified_levels != b.clear_unspecified_levels
+                || a.tileable_blend != b.tileable_blend
+                || a.border != b.border
+                || compress_differs(a, b),
+            metadata: a.sampling != b.sampling
+                || a.lod_clamp != b.lod_clamp
+                || a.retain_source != b.retain_source
+                || a.pot_wrap_required != b.pot_wrap_required
+                || a.name != b.name
+                || a.oom_policy != b.oom_policy
+                || a.pre_transform != b.pre_transform
+                || a.budget != b.budget,
+        }
+    }
+
+    /// Check if builders are equal.
+    pub fn is_empty(&self) -> bool {
+        *self == BuilderDiff::default()
+    }
+
+    /// Check if existing texture can't be reused and must be rebuilt from scratch.
+    pub fn requires_rebuild(&self) -> bool {
+        self.kind || self.format || self.levels || self.image
+    }
+
+    /// Check if content of existing texture must be uploaded again.
+    pub fn requires_upload(&self) -> bool {
+        self.data || self.stride || self.mip_settings || self.transforms
+    }
+}
+
+#[cfg(feature = "compress-bc")]
+fn compress_differs(a: &TextureBuilder, b: &TextureBuilder) -> bool {
+    a.compress != b.compress || a.auto_compression != b.auto_compression
+}
+
+#[cfg(not(feature = "compress-bc"))]
+fn compress_differs(_: &TextureBuilder, _: &TextureBuilder) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use hal::format::{Component, Format, Swizzle};
+    use hal::image::{Kind, Usage, WrapMode};
+
+    use super::*;
+    use {DitherMode, TextureSampling};
+
+    fn builder() -> TextureBuilder<'static> {
+        TextureBuilder::new(Kind::D2(4, 4, 1, 1))
+            .with_format(Format::Rgba8Unorm)
+            .with_data_bytes(vec![0; 64])
+    }
+
+    #[test]
+    fn equal_builders() {
+        let diff = builder().diff(&builder());
+        assert!(diff.is_empty());
+        assert!(!diff.requires_rebuild() && !diff.requires_upload());
+        assert_eq!(builder(), builder());
+    }
+
+    #[test]
+    fn each_aspect_is_reported() {
+        let base = builder();
+        let changed = [
+            (
+                base.clone().with_kind(Kind::D2(4, 2, 1, 1)),
+                BuilderDiff {
+                    kind: true,
+                    stride: true,
+                    ..Default::default()
+                },
+            ),
+            (
+                base.clone().with_format(Format::Rgba8Srgb),
+                BuilderDiff {
+                    format: true,
+                    ..Default::default()
+                },
+            ),
+            (
+                base.clone().with_data_bytes(vec![1; 64]),
+                BuilderDiff {
+                    data: true,
+                    ..Default::default()
+                },
+            ),
+            (
+                base.clone().with_data_bytes(vec![0; 80]),
+                BuilderDiff {
+                    data: true,
+                    ..Default::default()
+                },
+            ),
+            (
+                base.clone().with_data_width(5),
+                BuilderDiff {
+                    stride: true,
+                    ..Default::default()
+                },
+            ),
+            (
+                base.clone().with_mip_renormalize(true),
+                BuilderDiff {
+                    mip_settings: true,
+                    ..Default::default()
+                },
+            ),
+            (
+                base.clone().with_usage(Usage::STORAGE),
+                BuilderDiff {
+                    image: true,
+                    ..Default::default()
+                },
+            ),
+            (
+                base.clone().with_swizzle(Swizzle(
+                    Component::B,
+                    Component::G,
+                    Component::R,
+                    Component::A,
+                )),
+                BuilderDiff {
+                    image: true,
+                    ..Default::default()
+                },
+            ),
+            (
+                base.clone().with_dither(DitherMode::Bayer4),
+                BuilderDiff {
+                    transforms: true,
+                    ..Default::default()
+                },
+            ),
+            (
+                base.clone()
+                    .with_sampling(TextureSampling::new(WrapMode::Clamp))
+                    .with_name("changed"),
+                BuilderDiff {
+                    metadata: true,
+                    ..Default::default()
+                },
+            ),
+        ];
+        for (other, expected) in &changed {
+            assert_eq!(base.diff(other), *expected, "{:?}", other);
+            assert_ne!(base, *other);
+        }
+    }
+
+    #[test]
+    fn rebuild_and_upload_decisions() {
+        let base = builder();
+        let reupload = base.diff(&base.clone().with_data_bytes(vec![1; 64]));
+        assert!(reupload.requires_upload() && !reupload.requires_rebuild());
+        let rebuild = base.diff(&base.clone().with_format(Format::Bgra8Unorm));
+        assert!(rebuild.requires_rebuild());
+        let metadata = base.diff(&base.clone().with_name("renamed"));
+        assert!(!metadata.requires_upload() && !metadata.requires_rebuild());
+    }
+}
@@ -0,0 +1,857 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::sync::Arc;
+
+use failure;
+
+use hal::device::mapping;
+use hal::format::Format;
+use hal::image::{
+    self, Extent, Kind, Layer, Level, NumSamples, Offset, StorageFlags, SubresourceLayers, Usage,
+};
+
+use format::{AstcProfile, Endianness};
+
+/// Errors of texture creation and use.
+/// Implements `std::error::Error`, so it converts into `failure::Error` as well.
+#[derive(Clone, Debug)]
+pub enum TextureError {
+    /// CPU mip generation can't decode texels of the format.
+    MipGenUnsupportedFormat {
+        /// Format of the texture.
+        format: Format,
+    },
+
+    /// Device can't decode ASTC data with requested profile.
+    AstcUnsupported {
+        /// Format of the texture.
+        format: Format,
+        /// Requested profile.
+        profile: AstcProfile,
+    },
+
+    /// Operation requires format with alpha channel.
+    NoAlphaChannel {
+        /// Format of the texture.
+        format: Format,
+    },
+
+    /// Data provided for mip level has wrong size.
+    LevelSize {
+        /// Mip level.
+        level: Level,
+        /// Expected size in bytes.
+        expected: usize,
+        /// Size of data provided.
+        actual: usize,
+    },
+
+    /// Data provided for mip level has wrong number of layers.
+    LayerCount {
+        /// Mip level.
+        level: Level,
+        /// Layers in the texture.
+        expected: Layer,
+        /// Layers in the data provided.
+        actual: Layer,
+    },
+
+    /// More mip levels than extent allows.
+    TooManyLevels {
+        /// Levels requested.
+        levels: Level,
+        /// Levels in full mip chain.
+        max: Level,
+    },
+
+    /// Mip levels data is provided while mip generation requested.
+    ConflictingMipData,
+
+    /// Texture was built without retaining its builder.
+    SourceNotRetained,
+
+    /// Image lacks usage required for the operation.
+    MissingUsage {
+        /// Required usage.
+        usage: Usage,
+    },
+
+    /// Image lacks storage flags required for the operation.
+    MissingStorageFlags {
+        /// Required flags.
+        flags: StorageFlags,
+    },
+
+    /// Wrong number of descriptor sets for levels processed.
+    DescriptorSetCount {
+        /// One set for each processed level.
+        expected: usize,
+        /// Number of sets provided.
+        actual: usize,
+    },
+
+    /// Formats have different texel or block size.
+    IncompatibleFormats {
+        /// Format of the source image.
+        src: Format,
+        /// Format of the destination image.
+        dst: Format,
+    },
+
+    /// Region doesn't fit into the image.
+    RegionOutOfBounds {
+        /// Subresource of the region.
+        layers: SubresourceLayers,
+        /// Offset of the region.
+        offset: Offset,
+        /// Extent of the region.
+        extent: Extent,
+    },
+
+    /// Resolve requires multisampled source and single-sampled destination.
+    ResolveSamples {
+        /// Samples of the source image.
+        src: NumSamples,
+        /// Samples of the destination image.
+        dst: NumSamples,
+    },
+
+    /// Backend can't resolve depth or stencil.
+    DepthResolveUnsupported {
+        /// Format of the texture.
+        format: Format,
+    },
+
+    /// Images must have same extent and layer count.
+    ExtentMismatch {
+        /// Extent of the source image.
+        src: Extent,
+        /// Extent of the destination image.
+        dst: Extent,
+    },
+
+    /// Device can't blit images of the format.
+    BlitUnsupported {
+        /// Format of the texture.
+        format: Format,
+    },
+
+    /// Texture can't be reloaded in place from the builder.
+    Incompatible {
+        /// What differs between texture and builder.
+        reason: &'static str,
+    },
+
+    /// Byte order of the format's data can't be converted.
+    EndiannessUnsupported {
+        /// Format of the texture.
+        format: Format,
+        /// Byte order of the source data.
+        endianness: Endianness,
+    },
+
+    /// Palette index is outside of the palette.
+    IndexOutOfRange {
+        /// Offending index.
+        index: u8,
+        /// Number of entries in the palette.
+        palette: usize,
+    },
+
+    /// Packed data must have 1, 2 or 4 bits per texel.
+    UnsupportedBitDepth {
+        /// Requested bits per texel.
+        bits: u8,
+    },
+
+    /// Wrapping sampling requires power-of-two extent.
+    NonPowerOfTwoWrap {
+        /// Extent of the texture.
+        extent: Extent,
+    },
+
+    /// Chroma key requires 8-bit RGBA format.
+    ChromaKeyUnsupportedFormat {
+        /// Format of the texture.
+        format: Format,
+    },
+
+    /// Operation is valid only for 3D textures.
+    NotVolume {
+        /// Kind of the texture.
+        kind: Kind,
+    },
+
+    /// Operation is valid only for 2D textures, including arrays and cubemaps.
+    NotTwoDimensional {
+        /// Kind of the texture.
+        kind: Kind,
+    },
+
+    /// Depth slice has wrong size.
+    SliceSize {
+        /// Expected size in bytes.
+        expected: usize,
+        /// Provided size in bytes.
+        actual: usize,
+    },
+
+    /// All depth slices are already provided.
+    TooManySlices {
+        /// Depth of the texture.
+        depth: u32,
+    },
+
+    /// Data can't be converted between formats.
+    ConversionUnsupported {
+        /// Format of the data.
+        src: Format,
+        /// Format of the texture.
+        dst: Format,
+    },
+
+    /// Texels of the format can't be decoded on CPU.
+    DecodeUnsupportedFormat {
+        /// Format of the data.
+        format: Format,
+    },
+
+    /// Device can't filter mip levels of the format.
+    MipGenUnsupported {
+        /// Format of the texture.
+        format: Format,
+    },
+
+    /// Region of compressed texture isn't aligned to blocks.
+    UnalignedRegion {
+        /// Offset of the region.
+        offset: Offset,
+        /// Extent of the region.
+        extent: Extent,
+        /// Width and height of the format's block.
+        block: (u8, u8),
+    },
+
+    /// Levels of the format can't be filled with clear color.
+    ClearUnsupportedFormat {
+        /// Format of the texture.
+        format: Format,
+    },
+
+    /// Border can't be added to data of the format.
+    BorderUnsupportedFormat {
+        /// Format of the data.
+        format: Format,
+    },
+
+    /// Texels of the format can't be encoded on CPU.
+    EncodeUnsupportedFormat {
+        /// Format of the texture.
+        format: Format,
+    },
+
+    /// Gradient stops must be sorted and lie in `0 ..= 1` range.
+    InvalidGradientStop {
+        /// Index of the offending stop.
+        index: usize,
+        /// Offset of the stop.
+        offset: f32,
+    },
+
+    /// Gradient requires at least one stop.
+    EmptyGradient,
+
+    /// Checkerboard cell has zero size.
+    EmptyCheckerCell,
+
+    /// Border is added to base level only.
+    BorderWithMipData,
+
+    /// Device ran out of memory creating the image.
+    OutOfMemory {
+        /// Estimated size of the image in bytes.
+        size: u64,
+        /// Kind of the image.
+        kind: Kind,
+        /// Format of the image.
+        format: Format,
+        /// Name of the texture if set.
+        name: Option<String>,
+    },
+
+    /// Transient attachment can't be filled with data.
+    TransientWithData,
+
+    /// Device has no lazily allocated memory for transient attachments.
+    LazyMemoryUnsupported,
+
+    /// Texture doesn't fit into hard limit of memory budget.
+    BudgetExceeded {
+        /// Estimated size of the texture in bytes.
+        requested: u64,
+        /// Bytes already used by textures of the budget.
+        used: u64,
+        /// Hard limit of the budget.
+        limit: u64,
+    },
+
+    /// Backend or factory operation failed.
+    Backend {
+        /// Error reported by backend or factory.
+        source: Arc<dyn Error + Send + Sync>,
+    },
+
+    /// Decoding texture from image container failed.
+    Decode {
+        /// Error reported by decoder.
+        source: Arc<dyn Error + Send + Sync>,
+    },
+
+    /// Image container stores texels in format without matching texture format.
+    SourceFormatUnsupported {
+        /// Name of the container.
+        container: &'static str,
+        /// Format of the texels in the container.
+        format: String,
+    },
+
+    /// Reading or writing file failed.
+    Io {
+        /// Underlying I/O error.
+        source: Arc<io::Error>,
+    },
+
+    /// Minimal level of detail is greater than maximal.
+    InvalidLodClamp {
+        /// Minimal level of detail.
+        min_lod: f32,
+        /// Maximal level of detail.
+        max_lod: f32,
+    },
+
+    /// Size of the data doesn't fit address space.
+    DimensionsTooLarge {
+        /// Extent of the image.
+        extent: Extent,
+        /// Number of array layers.
+        layers: Layer,
+    },
+
+    /// Data of the format can't be block compressed that way.
+    CompressUnsupportedFormat {
+        /// Format of the texture.
+        format: Format,
+    },
+
+    /// Data can't be uploaded into depth and stencil aspects together.
+    CombinedAspectUploadUnsupported {
+        /// Format of the texture.
+        format: Format,
+    },
+
+    /// Level or layer is out of the texture.
+    SubresourceOutOfRange {
+        /// Requested level.
+        level: Level,
+        /// Requested layer.
+        layer: Layer,
+        /// Number of levels in the texture.
+        levels: Level,
+        /// Number of layers in the texture.
+        layers: Layer,
+    },
+
+    /// Texture memory is not mapped as linear host-visible image.
+    NotHostVisible,
+
+    /// Base level data is shorter than the image requires
+    /// or longer than rows and slices of the image with trailing bytes not allowed.
+    DataSize {
+        /// Format of the data.
+        format: Format,
+        /// Extent of base level.
+        extent: Extent,
+        /// Number of layers.
+        layers: Layer,
+        /// Minimal size in bytes.
+        required: usize,
+        /// Maximal size in bytes without trailing bytes.
+        allowed: usize,
+        /// Size of data provided.
+        actual: usize,
+    },
+
+    /// Layer of array texture has extent different from the first layer.
+    LayerExtentMismatch {
+        /// Index of the layer.
+        layer: Layer,
+        /// Extent of the layer.
+        extent: Extent,
+        /// Extent of the first layer.
+        expected: Extent,
+    },
+
+    /// Array texture has no layers or more than `Layer` can count.
+    ArrayLayerCount {
+        /// Number of layers provided.
+        count: usize,
+    },
+
+    /// Storage flags can't be used with image kind.
+    StorageFlagsConflict {
+        /// Requested flags.
+        flags: StorageFlags,
+        /// Kind of the image.
+        kind: Kind,
+    },
+
+    /// Row stride is shorter than a row or not a multiple of texel size.
+    InvalidStride {
+        /// Stride in bytes.
+        stride: u32,
+        /// Size of a row in bytes.
+        row_size: u32,
+        /// Size of a texel in bytes.
+        texel_size: u32,
+    },
+
+    /// Streaming texture is created without staging buffers.
+    NoStagingSlots,
+
+    /// Device was lost while waiting for submitted commands.
+    DeviceLost,
+
+    /// Rows of data are shorter than rows of the level.
+    RowTooShort {
+        /// Row length of the data in texels.
+        data_width: u32,
+        /// Width of the level in texels.
+        width: u32,
+    },
+}
+
+impl fmt::Display for TextureError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TextureError::MipGenUnsupportedFormat { ref format } => write!(
+                fmt,
+                "Mip generation is not supported for format {:?}",
+                format
+            ),
+            TextureError::AstcUnsupported {
+                ref format,
+                ref profile,
+            } => write!(
+                fmt,
+                "ASTC {:?} profile is not supported for format {:?}",
+                profile, format
+            ),
+            TextureError::NoAlphaChannel { ref format } => {
+                write!(fmt, "Format {:?} has no alpha channel", format)
+            }
+            TextureError::LevelSize {
+                ref level,
+                ref expected,
+                ref actual,
+            } => write!(
+                fmt,
+                "Data for level {} is {} bytes while {} bytes expected",
+                level, actual, expected
+            ),
+            TextureError::LayerCount {
+                ref level,
+                ref expected,
+                ref actual,
+            } => write!(
+                fmt,
+                "Data for level {} has {} layers while {} expected",
+                level, actual, expected
+            ),
+            TextureError::TooManyLevels {
+                ref levels,
+                ref max,
+            } => write!(
+                fmt,
+                "Texture can't have {} levels, maximum is {}",
+                levels, max
+            ),
+            TextureError::ConflictingMipData => write!(
+                fmt,
+                "Mip levels data can't be provided when mip generation is enabled"
+            ),
+            TextureError::SourceNotRetained => write!(fmt, "Texture source is not retained"),
+            TextureError::MissingUsage { ref usage } => {
+                write!(fmt, "Image must be created with {:?} usage", usage)
+            }
+            TextureError::MissingStorageFlags { ref flags } => {
+                write!(fmt, "Image must be created with {:?} storage flags", flags)
+            }
+            TextureError::DescriptorSetCount { expected, actual } => write!(
+                fmt,
+                "{} descriptor sets provided while {} expected",
+                actual, expected
+            ),
+            TextureError::IncompatibleFormats { ref src, ref dst } => {
+                write!(fmt, "Formats {:?} and {:?} are not compatible", src, dst)
+            }
+            TextureError::RegionOutOfBounds {
+                ref layers,
+                ref offset,
+                ref extent,
+            } => write!(
+                fmt,
+                "Region {:?} at {:?} of {:?} is out of image bounds",
+                extent, offset, layers
+            ),
+            TextureError::ResolveSamples { ref src, ref dst } => {
+                write!(fmt, "Can't resolve {} samples into {} samples", src, dst)
+            }
+            TextureError::DepthResolveUnsupported { ref format } => write!(
+                fmt,
+                "Resolve of depth-stencil format {:?} is not supported",
+                format
+            ),
+            TextureError::ExtentMismatch { ref src, ref dst } => write!(
+                fmt,
+                "Source extent {:?} doesn't match destination extent {:?}",
+                src, dst
+            ),
+            TextureError::BlitUnsupported { ref format } => {
+                write!(fmt, "Blit is not supported for format {:?}", format)
+            }
+            TextureError::Incompatible { ref reason } => {
+                write!(fmt, "Texture is incompatible with new content: {}", reason)
+            }
+            TextureError::EndiannessUnsupported {
+                ref format,
+                ref endianness,
+            } => write!(
+                fmt,
+                "Can't convert {:?} data of format {:?} to native byte order",
+                endianness, format
+            ),
+            TextureError::IndexOutOfRange {
+                ref index,
+                ref palette,
+            } => write!(
+                fmt,
+                "Index {} is out of palette with {} entries",
+                index, palette
+            ),
+            TextureError::UnsupportedBitDepth { ref bits } => {
+                write!(fmt, "Unsupported bit depth {}", bits)
+            }
+            TextureError::NonPowerOfTwoWrap { ref extent } => write!(
+                fmt,
+                "Wrapping sampling of non-power-of-two texture {:?}",
+                extent
+            ),
+            TextureError::ChromaKeyUnsupportedFormat { ref format } => {
+                write!(fmt, "Chroma key is not supported for format {:?}", format)
+            }
+            TextureError::NotVolume { ref kind } => {
+                write!(fmt, "Texture of kind {:?} has no depth slices", kind)
+            }
+            TextureError::NotTwoDimensional { ref kind } => {
+                write!(fmt, "Texture of kind {:?} is not two-dimensional", kind)
+            }
+            TextureError::SliceSize {
+                ref expected,
+                ref actual,
+            } => write!(
+                fmt,
+                "Slice is {} bytes while {} bytes expected",
+                actual, expected
+            ),
+            TextureError::TooManySlices { ref depth } => {
+                write!(fmt, "Texture has only {} depth slices", depth)
+            }
+            TextureError::ConversionUnsupported { ref src, ref dst } => write!(
+                fmt,
+                "Conversion from {:?} to {:?} is not supported",
+                src, dst
+            ),
+            TextureError::DecodeUnsupportedFormat { ref format } => {
+                write!(fmt, "Decoding is not supported for format {:?}", format)
+            }
+            TextureError::MipGenUnsupported { ref format } => write!(
+                fmt,
+                "Mip filtering is not supported for format {:?}",
+                format
+            ),
+            TextureError::UnalignedRegion {
+                ref offset,
+                ref extent,
+                ref block,
+            } => write!(
+                fmt,
+                "Region {:?} at {:?} is not aligned to {:?} blocks",
+                extent, offset, block
+            ),
+            TextureError::ClearUnsupportedFormat { ref format } => write!(
+                fmt,
+                "Clearing levels is not supported for format {:?}",
+                format
+            ),
+            TextureError::BorderUnsupportedFormat { ref format } => {
+                write!(fmt, "Border is not supported for format {:?}", format)
+            }
+            TextureError::EncodeUnsupportedFormat { ref format } => {
+                write!(fmt, "Encoding is not supported for format {:?}", format)
+            }
+            TextureError::InvalidGradientStop { index, offset } => write!(
+                fmt,
+                "Gradient stop {} at {} is out of order or outside of 0..=1 range",
+                index, offset
+            ),
+            TextureError::EmptyGradient => write!(fmt, "Gradient has no stops"),
+            TextureError::EmptyCheckerCell => write!(fmt, "Checkerboard cell has zero size"),
+            TextureError::BorderWithMipData => write!(
+                fmt,
+                "Mip levels data can't be provided when border is added"
+            ),
+            TextureError::OutOfMemory {
+                size,
+                kind,
+                format,
+                ref name,
+            } => {
+                write!(
+                    fmt,
+                    "Out of memory allocating {} bytes for {:?} image of format {:?}",
+                    size, kind, format
+                )?;
+                if let Some(ref name) = *name {
+                    write!(fmt, " of texture {:?}", name)?;
+                }
+                Ok(())
+            }
+            TextureError::TransientWithData => {
+                write!(fmt, "Transient attachment can't be created with data")
+            }
+            TextureError::LazyMemoryUnsupported => {
+                write!(fmt, "Lazily allocated memory is not supported by device")
+            }
+            TextureError::BudgetExceeded {
+                requested,
+                used,
+                limit,
+            } => write!(
+                fmt,
+                "Texture of {} bytes exceeds budget limit of {} bytes with {} bytes used",
+                requested, limit, used
+            ),
+            TextureError::Backend { ref source } => write!(fmt, "Backend error: {}", source),
+            TextureError::Decode { ref source } => write!(fmt, "Decoding error: {}", source),
+            TextureError::SourceFormatUnsupported {
+                container,
+                ref format,
+            } => write!(
+                fmt,
+                "{} format {} has no matching texture format",
+                container, format
+            ),
+            TextureError::Io { ref source } => write!(fmt, "I/O error: {}", source),
+            TextureError::InvalidLodClamp { min_lod, max_lod } => write!(
+                fmt,
+                "Minimal level of detail {} is greater than maximal {}",
+                min_lod, max_lod
+            ),
+            TextureError::DimensionsTooLarge { extent, layers } => write!(
+                fmt,
+                "Size of {} layers of {}x{}x{} texels doesn't fit address space",
+                layers, extent.width, extent.height, extent.depth
+            ),
+            TextureError::CompressUnsupportedFormat { ref format } => write!(
+                fmt,
+                "Data of {:?} format can't be block compressed that way",
+                format
+            ),
+            TextureError::CombinedAspectUploadUnsupported { ref format } => write!(
+                fmt,
+                "Data of {:?} format can't be uploaded into depth and stencil aspects together",
+                format
+            ),
+            TextureError::SubresourceOutOfRange {
+                ref level,
+                ref layer,
+                ref levels,
+                ref layers,
+            } => write!(
+                fmt,
+                "Level {} and layer {} are out of texture with levels 0..{} and layers 0..{}",
+                level, layer, levels, layers
+            ),
+            TextureError::NotHostVisible => {
+                write!(fmt, "Texture is not a linear image in host-visible memory")
+            }
+            TextureError::DataSize {
+                ref format,
+                ref extent,
+                ref layers,
+                ref required,
+                ref allowed,
+                ref actual,
+            } => {
+                write!(
+                    fmt,
+                    "Data is {} bytes but {}x{}x{} {:?} texture with {} layers ",
+                    grouped(*actual),
+                    extent.width,
+                    extent.height,
+                    extent.depth,
+                    format,
+                    layers
+                )?;
+                if actual < required {
+                    write!(fmt, "requires {}", grouped(*required))
+                } else {
+                    write!(
+                        fmt,
+                        "allows at most {} unless remaining bytes are ignored",
+                        grouped(*allowed)
+                    )
+                }
+            }
+            TextureError::LayerExtentMismatch {
+                ref layer,
+                ref extent,
+                ref expected,
+            } => write!(
+                fmt,
+                "Layer {} is {}x{} while first layer is {}x{}",
+                layer, extent.width, extent.height, expected.width, expected.height
+            ),
+            TextureError::ArrayLayerCount { ref count } => write!(
+                fmt,
+                "Array texture can't have {} layers, must be 1 to {}",
+                count,
+                Layer::MAX
+            ),
+            TextureError::StorageFlagsConflict {
+                ref flags,
+                ref kind,
+            } => write!(
+                fmt,
+                "Storage flags {:?} are not compatible with {:?}",
+                flags, kind
+            ),
+            TextureError::InvalidStride {
+                ref stride,
+                ref row_size,
+                ref texel_size,
+            } => write!(
+                fmt,
+                "Row stride of {} bytes must be at least {} bytes and multiple of texel size {}",
+                stride, row_size, texel_size
+            ),
+            TextureError::NoStagingSlots => {
+                write!(fmt, "Streaming texture needs at least one staging buffer")
+            }
+            TextureError::DeviceLost => {
+                write!(fmt, "Device was lost before submitted commands completed")
+            }
+            TextureError::RowTooShort {
+                ref data_width,
+                ref width,
+            } => write!(
+                fmt,
+                "Data rows of {} texels are shorter than level width {}",
+                data_width, width
+            ),
+        }
+    }
+}
+
+impl Error for TextureError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            TextureError::Backend { ref source } => Some(&**source),
+            TextureError::Decode { ref source } => Some(&**source),
+            TextureError::Io { ref source } => Some(&**source),
+            _ => None,
+        }
+    }
+}
+
+impl TextureError {
+    /// Wrap error reported by backend or factory.
+    pub fn backend<E>(error: E) -> Self
+    where
+        E: Error + Send + Sync + 'static,
+    {
+        TextureError::Backend {
+            source: Arc::new(error),
+        }
+    }
+
+    /// Wrap error reported by texture decoder.
+    pub fn decode<E>(error: E) -> Self
+    where
+        E: Error + Send + Sync + 'static,
+    {
+        TextureError::Decode {
+            source: Arc::new(error),
+        }
+    }
+}
+
+/// Format number with thousands separated by commas.
+fn grouped(value: usize) -> String {
+    let digits = value.to_string();
+    let mut result = String::with_capacity(digits.len() * 4 / 3);
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index).is_multiple_of(3) {
+            result.push(',');
+        }
+        result.push(digit);
+    }
+    result
+}
+
+impl From<failure::Error> for TextureError {
+    fn from(error: failure::Error) -> Self {
+        TextureError::backend(error.compat())
+    }
+}
+
+impl From<image::ViewError> for TextureError {
+    fn from(error: image::ViewError) -> Self {
+        failure::Error::from(error).into()
+    }
+}
+
+impl From<mapping::Error> for TextureError {
+    fn from(error: mapping::Error) -> Self {
+        failure::Error::from(error).into()
+    }
+}
+
+impl From<io::Error> for TextureError {
+    fn from(error: io::Error) -> Self {
+        TextureError::Io {
+            source: Arc::new(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sources_are_chained() {
+        let io = TextureError::from(io::Error::new(io::ErrorKind::NotFound, "missing file"));
+        assert_eq!(io.source().unwrap().to_string(), "missing file");
+
+        let decode = TextureError::decode(io::Error::new(io::ErrorKind::InvalidData, "bad header"));
+        assert_eq!(decode.source().unwrap().to_string(), "bad header");
+        assert_eq!(decode.to_string(), "Decoding error: bad header");
+
+        let backend = TextureError::backend(TextureError::ConflictingMipData);
+        let source = backend.source().unwrap();
+        assert!(source.downcast_ref::<TextureError>().is_some());
+        assert!(source.source().is_none());
+    }
+}
@@ -0,0 +1,464 @@
+//! Format helpers.
+
+use std::borrow::Cow;
+
+use hal::format::{ChannelType, Format, ImageFeature, SurfaceType};
+use hal::image::{Extent, Layer};
+use hal::{Backend, Features, PhysicalDevice};
+
+use error::TextureError;
+
+/// Decoding profile of ASTC compressed data.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum AstcProfile {
+    /// Low dynamic range. Decoded by `Astc*Unorm` and `Astc*Srgb` formats.
+    #[default]
+    Ldr,
+    /// High dynamic range. Decoded into floating point values.
+    Hdr,
+}
+
+/// Byte order of multi-byte channels.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Endianness {
+    /// Byte order of the host.
+    #[default]
+    Native,
+    /// Least significant byte first.
+    Little,
+    /// Most significant byte first.
+    Big,
+}
+
+impl Endianness {
+    fn is_native(&self) -> bool {
+        match *self {
+            Endianness::Native => true,
+            Endianness::Little => cfg!(target_endian = "little"),
+            Endianness::Big => cfg!(target_endian = "big"),
+        }
+    }
+}
+
+/// Size in bytes of the unit affected by byte order.
+/// That is channel size for regular formats and texel size for packed ones.
+/// Returns `1` for formats with 8-bit channels
+/// and `None` for formats which byte order can't be converted,
+/// such as compressed formats and mixed depth-stencil formats.
+pub(crate) fn swap_unit_size(format: Format) -> Option<usize> {
+    let size = match format.base_format().0 {
+        SurfaceType::R4_G4
+        | SurfaceType::R8
+        | SurfaceType::R8_G8
+        | SurfaceType::R8_G8_B8
+        | SurfaceType::B8_G8_R8
+        | SurfaceType::R8_G8_B8_A8
+        | SurfaceType::B8_G8_R8_A8
+        | SurfaceType::S8 => 1,
+        SurfaceType::R4_G4_B4_A4
+        | SurfaceType::B4_G4_R4_A4
+        | SurfaceType::R5_G6_B5
+        | SurfaceType::B5_G6_R5
+        | SurfaceType::R5_G5_B5_A1
+        | SurfaceType::B5_G5_R5_A1
+        | SurfaceType::A1_R5_G5_B5
+        | SurfaceType::R16
+        | SurfaceType::R16_G16
+        | SurfaceType::R16_G16_B16
+        | SurfaceType::R16_G16_B16_A16
+        | SurfaceType::D16 => 2,
+        SurfaceType::A8_B8_G8_R8
+        | SurfaceType::A2_R10_G10_B10
+        | SurfaceType::A2_B10_G10_R10
+        | SurfaceType::B10_G11_R11
+        | SurfaceType::E5_B9_G9_R9
+        | SurfaceType::R32
+        | SurfaceType::R32_G32
+        | SurfaceType::R32_G32_B32
+        | SurfaceType::R32_G32_B32_A32
+        | SurfaceType::X8D24
+        | SurfaceType::D24_S8
+        | SurfaceType::D32 => 4,
+        SurfaceType::R64
+        | SurfaceType::R64_G64
+        | SurfaceType::R64_G64_B64
+        | SurfaceType::R64_G64_B64_A64 => 8,
+        _ => return None,
+    };
+    Some(size)
+}
+
+/// Check that data of `format` in `endianness` can be converted to native byte order.
+pub(crate) fn check_endianness(format: Format, endianness: Endianness) -> Result<(), TextureError> {
+    if !endianness.is_native() && swap_unit_size(format).is_none() {
+        Err(TextureError::EndiannessUnsupported { format, endianness })?;
+    }
+    Ok(())
+}
+
+/// Convert data of `format` from `endianness` to native byte order.
+/// Borrows data when no conversion is required or possible.
+pub(crate) fn to_native(format: Format, endianness: Endianness, data: &[u8]) -> Cow<'_, [u8]> {
+    let unit = match swap_unit_size(format) {
+        Some(unit) if unit > 1 && !endianness.is_native() => unit,
+        _ => return Cow::Borrowed(data),
+    };
+    let mut data = data.to_vec();
+    for chunk in data.chunks_mut(unit) {
+        chunk.reverse();
+    }
+    Cow::Owned(data)
+}
+
+/// Replace NaN with zero and infinities with largest finite values
+/// in data of floating point `format`.
+/// Borrows data when there is nothing to replace.
+pub(crate) fn sanitize_non_finite(format: Format, data: Cow<[u8]>) -> Cow<[u8]> {
+    if format.base_format().1 != ChannelType::Float {
+        return data;
+    }
+    let (unit, sanitize): (usize, fn(&mut [u8]) -> bool) = match swap_unit_size(format) {
+        Some(2) => (2, sanitize_f16),
+        Some(4) => (4, sanitize_f32),
+        Some(8) => (8, sanitize_f64),
+        _ => return data,
+    };
+    let mut scratch = [0u8; 8];
+    let non_finite = data.chunks_exact(unit).any(|chunk| {
+        scratch[..unit].copy_from_slice(chunk);
+        sanitize(&mut scratch[..unit])
+    });
+    if !non_finite {
+        return data;
+    }
+    let mut data = data.into_owned();
+    for chunk in data.chunks_exact_mut(unit) {
+        sanitize(chunk);
+    }
+    Cow::Owned(data)
+}
+
+fn sanitize_f16(bytes: &mut [u8]) -> bool {
+    let bits = u16::from_ne_bytes([bytes[0], bytes[1]]);
+    let sanitized = if bits & 0x7c00 != 0x7c00 {
+        return false;
+    } else if bits & 0x03ff != 0 {
+        0
+    } else {
+        (bits & 0x8000) | 0x7bff
+    };
+    bytes.copy_from_slice(&sanitized.to_ne_bytes());
+    true
+}
+
+/// Decode IEEE 754 half-precision value.
+pub(crate) fn f16_to_f32(bits: u16) -> f32 {
+    let sign = ((bits & 0x8000) as u32) << 16;
+    let exponent = ((bits >> 10) & 0x1f) as u32;
+    let mantissa = (bits & 0x03ff) as u32;
+    let bits = match exponent {
+        0 if mantissa == 0 => sign,
+        0 => {
+            // Subnormal half is normal single.
+            let shift = mantissa.leading_zeros() - 21;
+            sign | (113 - shift) << 23 | (mantissa << shift & 0x03ff) << 13
+        }
+        0x1f => sign | 0x7f80_0000 | mantissa << 13,
+        _ => sign | (exponent + 112) << 23 | mantissa << 13,
+    };
+    f32::from_bits(bits)
+}
+
+/// Encode IEEE 754 half-precision value rounding to nearest.
+pub(crate) fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x007f_ffff;
+    if exponent == 0xff {
+        let nan = if mantissa != 0 { 0x0200 } else { 0 };
+        return sign | 0x7c00 | nan;
+    }
+    let exponent = exponent - 112;
+    if exponent >= 0x1f {
+        sign | 0x7c00
+    } else if exponent <= 0 {
+        if exponent < -10 {
+            return sign;
+        }
+        let mantissa = mantissa | 0x0080_0000;
+        let shift = (14 - exponent) as u32;
+        let half = mantissa >> shift;
+        let rest = mantissa & ((1 << shift) - 1);
+        let round = (rest > 1 << (shift - 1) || (rest == 1 << (shift - 1) && half & 1 != 0)) as u32;
+        sign | (half + round) as u16
+    } else {
+        let half = (exponent as u32) << 10 | mantissa >> 13;
+        let rest = mantissa & 0x1fff;
+        let round = (rest > 0x1000 || (rest == 0x1000 && half & 1 != 0)) as u32;
+        sign | (half + round) as u16
+    }
+}
+
+fn sanitize_f32(bytes: &mut [u8]) -> bool {
+    let mut value = [0u8; 4];
+    value.copy_from_slice(bytes);
+    let value = f32::from_bits(u32::from_ne_bytes(value));
+    let sanitized = if value.is_nan() {
+        0.0
+    } else if value.is_infinite() {
+        value.signum() * f32::MAX
+    } else {
+        return false;
+    };
+    bytes.copy_from_slice(&sanitized.to_bits().to_ne_bytes());
+    true
+}
+
+fn sanitize_f64(bytes: &mut [u8]) -> bool {
+    let mut value = [0u8; 8];
+    value.copy_from_slice(bytes);
+    let value = f64::from_bits(u64::from_ne_bytes(value));
+    let sanitized = if value.is_nan() {
+        0.0
+    } else if value.is_infinite() {
+        value.signum() * f64::MAX
+    } else {
+        return false;
+    };
+    bytes.copy_from_slice(&sanitized.to_bits().to_ne_bytes());
+    true
+}
+
+/// Size in bytes of tightly packed data for `layers` of `extent`.
+/// Partial blocks at the edges are counted as whole.
+/// Saturates at `usize::MAX` on overflow.
+pub(crate) fn level_size(format: Format, extent: Extent, layers: Layer) -> usize {
+    let desc = format.surface_desc();
+    let blocks_x = extent.width.div_ceil(desc.dim.0 as u32) as usize;
+    let blocks_y = extent.height.div_ceil(desc.dim.1 as u32) as usize;
+    blocks_x
+        .checked_mul(blocks_y)
+        .and_then(|blocks| blocks.checked_mul(extent.depth as usize))
+        .and_then(|blocks| blocks.checked_mul(layers as usize))
+        .and_then(|blocks| blocks.checked_mul(desc.bits as usize / 8))
+        .unwrap_or(usize::MAX)
+}
+
+/// Minimal size in bytes of data for `layers` of `extent` laid out with
+/// `data_width` texels between rows and `data_height` rows between slices.
+/// Saturates at `usize::MAX` on overflow.
+pub(crate) fn strided_size(
+    format: Format,
+    extent: Extent,
+    data_width: u32,
+    data_height: u32,
+    layers: Layer,
+) -> usize {
+    checked_strided_size(format, extent, data_width, data_height, layers).unwrap_or(usize::MAX)
+}
+
+/// Minimal size in bytes of strided data or `None` if it doesn't fit `usize`.
+pub(crate) fn checked_strided_size(
+    format: Format,
+    extent: Extent,
+    data_width: u32,
+    data_height: u32,
+    layers: Layer,
+) -> Option<usize> {
+    let desc = format.surface_desc();
+    let block_size = desc.bits as usize / 8;
+    let row_size = (data_width.div_ceil(desc.dim.0 as u32) as usize).checked_mul(block_size)?;
+    let slice_rows = data_height.div_ceil(desc.dim.1 as u32) as usize;
+    let rows = extent.height.div_ceil(desc.dim.1 as u32) as usize;
+    let slices = (extent.depth as usize).checked_mul(layers as usize)?;
+    slice_rows
+        .checked_mul(slices.saturating_sub(1))?
+        .checked_add(rows)?
+        .checked_mul(row_size)
+}
+
+/// Copy data laid out with `data_width` texels between rows and `data_height` rows
+/// between slices into tightly packed data for `layers` of `extent`.
+#[cfg(feature = "test-support")]
+pub(crate) fn pack_tight(
+    format: Format,
+    extent: Extent,
+    data_width: u32,
+    data_height: u32,
+    layers: Layer,
+    data: &[u8],
+) -> Vec<u8> {
+    let desc = format.surface_desc();
+    let block_size = desc.bits as usize / 8;
+    let row_size = extent.width.div_ceil(desc.dim.0 as u32) as usize * block_size;
+    let row_pitch = data_width.div_ceil(desc.dim.0 as u32) as usize * block_size;
+    let rows = extent.height.div_ceil(desc.dim.1 as u32) as usize;
+    let slice_pitch = data_height.div_ceil(desc.dim.1 as u32) as usize * row_pitch;
+    let slices = extent.depth as usize * layers as usize;
+
+    let mut packed = Vec::with_capacity(row_size * rows * slices);
+    for slice in 0..slices {
+        for row in 0..rows {
+            let offset = slice * slice_pitch + row * row_pitch;
+            packed.extend_from_slice(&data[offset..offset + row_size]);
+        }
+    }
+    packed
+}
+
+/// Size in bytes of one depth slice with `data_width` texels
+/// between rows and `data_height` rows between slices.
+pub(crate) fn slice_size(format: Format, data_width: u32, data_height: u32) -> usize {
+    let desc = format.surface_desc();
+    let row_size = data_width.div_ceil(desc.dim.0 as u32) as usize * desc.bits as usize / 8;
+    data_height.div_ceil(desc.dim.1 as u32) as usize * row_size
+}
+
+/// Check if format is one of ASTC compressed formats.
+pub(crate) fn is_astc(format: Format) -> bool {
+    matches!(
+        format.base_format().0,
+        SurfaceType::ASTC_4x4
+            | SurfaceType::ASTC_5x4
+            | SurfaceType::ASTC_5x5
+            | SurfaceType::ASTC_6x5
+            | SurfaceType::ASTC_6x6
+            | SurfaceType::ASTC_8x5
+            | SurfaceType::ASTC_8x6
+            | SurfaceType::ASTC_8x8
+            | SurfaceType::ASTC_10x5
+            | SurfaceType::ASTC_10x6
+            | SurfaceType::ASTC_10x8
+            | SurfaceType::ASTC_10x10
+            | SurfaceType::ASTC_12x10
+            | SurfaceType::ASTC_12x12
+    )
+}
+
+/// Check that device can decode ASTC data of `format` with `profile`.
+/// Non-ASTC formats are accepted as is.
+pub(crate) fn check_astc_profile<B>(
+    physical: &B::PhysicalDevice,
+    format: Format,
+    profile: AstcProfile,
+) -> Result<(), TextureError>
+where
+    B: Backend,
+{
+    check_astc_features(physical.features(), format, profile)
+}
+
+/// Check that device with `features` can decode ASTC data of `format` with `profile`.
+fn check_astc_features(
+    features: Features,
+    format: Format,
+    profile: AstcProfile,
+) -> Result<(), TextureError> {
+    if !is_astc(format) {
+        return Ok(());
+    }
+
+    match profile {
+        AstcProfile::Ldr => {
+            if !features.contains(Features::FORMAT_ASTC_LDR) {
+                Err(TextureError::AstcUnsupported { format, profile })?;
+            }
+        }
+        AstcProfile::Hdr => {
+            // There are no formats nor device features for HDR profile yet.
+            Err(TextureError::AstcUnsupported { format, profile })?;
+        }
+    }
+    Ok(())
+}
+
+/// Check that device can sample mip chain of `format` with linear filtering in optimal tiling.
+pub(crate) fn check_mip_filtering<B>(
+    physical: &B::PhysicalDevice,
+    format: Format,
+) -> Result<(), TextureError>
+where
+    B: Backend,
+{
+    check_mip_features(
+        physical.format_properties(Some(format)).optimal_tiling,
+        format,
+    )
+}
+
+/// Check that optimal tiling `features` of `format` allow sampling mip chain with linear filtering.
+fn check_mip_features(features: ImageFeature, format: Format) -> Result<(), TextureError> {
+    let required = ImageFeature::SAMPLED | ImageFeature::SAMPLED_LINEAR;
+    if !features.contains(required) {
+        Err(TextureError::MipGenUnsupported { format })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn astc_profiles() {
+        let ldr = Features::FORMAT_ASTC_LDR;
+        for &format in &[Format::Astc6x6Unorm, Format::Astc6x6Srgb] {
+            check_astc_features(ldr, format, AstcProfile::Ldr).unwrap();
+            match check_astc_features(Features::empty(), format, AstcProfile::Ldr) {
+                Err(TextureError::AstcUnsupported {
+                    profile: AstcProfile::Ldr,
+                    ..
+                }) => {}
+                other => panic!("{:?}", other),
+            }
+            match check_astc_features(ldr, format, AstcProfile::Hdr) {
+                Err(TextureError::AstcUnsupported {
+                    profile: AstcProfile::Hdr,
+                    ..
+                }) => {}
+                other => panic!("{:?}", other),
+            }
+        }
+        check_astc_features(Features::empty(), Format::Rgba8Unorm, AstcProfile::Hdr).unwrap();
+    }
+
+    #[test]
+    fn swapped_gradient_matches_native() {
+        let gradient = (0..64u16).map(|i| i * 1024 + i).collect::<Vec<_>>();
+        let bytes = |encode: fn(u16) -> [u8; 2]| {
+            gradient
+                .iter()
+                .flat_map(|&value| encode(value).to_vec())
+                .collect::<Vec<_>>()
+        };
+        let native = bytes(u16::to_ne_bytes);
+        for &(endianness, ref data) in &[
+            (Endianness::Native, bytes(u16::to_ne_bytes)),
+            (Endianness::Little, bytes(u16::to_le_bytes)),
+            (Endianness::Big, bytes(u16::to_be_bytes)),
+        ] {
+            check_endianness(Format::Rgba16Unorm, endianness).unwrap();
+            assert_eq!(
+                &to_native(Format::Rgba16Unorm, endianness, data)[..],
+                &native[..]
+            );
+        }
+        match to_native(Format::Rgba16Unorm, Endianness::Native, &native) {
+            Cow::Borrowed(_) => {}
+            Cow::Owned(_) => panic!("native data is copied"),
+        }
+    }
+
+    #[test]
+    fn mip_generation_requires_linear_filtering() {
+        let format = Format::Rgba32Float;
+        match check_mip_features(ImageFeature::SAMPLED | ImageFeature::BLIT_SRC, format) {
+            Err(TextureError::MipGenUnsupported { format: error }) => assert_eq!(error, format),
+            other => panic!("{:?}", other),
+        }
+        check_mip_features(ImageFeature::SAMPLED | ImageFeature::SAMPLED_LINEAR, format).unwrap();
+    }
+}
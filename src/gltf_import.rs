@@ -0,0 +1,187 @@
+//! Import of glTF images and samplers.
+
+use gltf::image::{Data, Format as GltfFormat};
+use gltf::texture::{MagFilter, MinFilter, Sampler, WrappingMode};
+
+use hal::format::Format;
+use hal::image::{Filter, Kind, WrapMode};
+
+use error::TextureError;
+use sampling::TextureSampling;
+use source::{expand_alpha, DecodedTexture, TextureSource};
+use TextureBuilder;
+
+impl TextureBuilder<'static> {
+    /// Create builder from image decoded by `gltf` crate.
+    /// RGB data is expanded to RGBA.
+    /// `srgb` selects sRGB formats for 8-bit data and should be set for color textures.
+    /// 16-bit data is always treated as linear as there are no 16-bit sRGB formats.
+    pub fn from_gltf_image(image: &Data, srgb: bool) -> Self {
+        GltfImage { image, srgb }.decoded().into_builder()
+    }
+}
+
+/// Image decoded by `gltf` crate as `TextureSource`.
+/// See `TextureBuilder::from_gltf_image` for the conversion.
+#[derive(Clone, Copy, Debug)]
+pub struct GltfImage<'a> {
+    /// Decoded image.
+    pub image: &'a Data,
+    /// Whether 8-bit data uses sRGB formats.
+    pub srgb: bool,
+}
+
+impl<'a> TextureSource for GltfImage<'a> {
+    fn decode(&self) -> Result<DecodedTexture, TextureError> {
+        Ok(self.decoded())
+    }
+}
+
+impl<'a> GltfImage<'a> {
+    fn decoded(&self) -> DecodedTexture {
+        let (image, srgb) = (self.image, self.srgb);
+        let format = |unorm, srgb_format| if srgb { srgb_format } else { unorm };
+        let (format, data) = match image.format {
+            GltfFormat::R8 => (
+                format(Format::R8Unorm, Format::R8Srgb),
+                image.pixels.clone(),
+            ),
+            GltfFormat::R8G8 => (
+                format(Format::Rg8Unorm, Format::Rg8Srgb),
+                image.pixels.clone(),
+            ),
+            GltfFormat::R8G8B8 => (
+                format(Format::Rgba8Unorm, Format::Rgba8Srgb),
+                expand_alpha(&image.pixels, 1),
+            ),
+            GltfFormat::R8G8B8A8 => (
+                format(Format::Rgba8Unorm, Format::Rgba8Srgb),
+                image.pixels.clone(),
+            ),
+            GltfFormat::B8G8R8 => (
+                format(Format::Bgra8Unorm, Format::Bgra8Srgb),
+                expand_alpha(&image.pixels, 1),
+            ),
+            GltfFormat::B8G8R8A8 => (
+                format(Format::Bgra8Unorm, Format::Bgra8Srgb),
+                image.pixels.clone(),
+            ),
+            GltfFormat::R16 => (Format::R16Unorm, image.pixels.clone()),
+            GltfFormat::R16G16 => (Format::Rg16Unorm, image.pixels.clone()),
+            GltfFormat::R16G16B16 => (Format::Rgba16Unorm, expand_alpha(&image.pixels, 2)),
+            GltfFormat::R16G16B16A16 => (Format::Rgba16Unorm, image.pixels.clone()),
+        };
+
+        DecodedTexture {
+            kind: Kind::D2(image.width, image.height, 1, 1),
+            format,
+            levels: vec![data],
+        }
+    }
+}
+
+impl<'a> From<Sampler<'a>> for TextureSampling {
+    /// Filters not specified by the sampler default to linear.
+    /// Minification without mipmapping maps to nearest mip filter.
+    fn from(sampler: Sampler<'a>) -> Self {
+        let wrap = |mode| match mode {
+            WrappingMode::ClampToEdge => WrapMode::Clamp,
+            WrappingMode::MirroredRepeat => WrapMode::Mirror,
+            WrappingMode::Repeat => WrapMode::Tile,
+        };
+        let (min_filter, mip_filter) = match sampler.min_filter() {
+            Some(MinFilter::Nearest) => (Filter::Nearest, Filter::Nearest),
+            Some(MinFilter::Linear) => (Filter::Linear, Filter::Nearest),
+            Some(MinFilter::NearestMipmapNearest) => (Filter::Nearest, Filter::Nearest),
+            Some(MinFilter::LinearMipmapNearest) => (Filter::Linear, Filter::Nearest),
+            Some(MinFilter::NearestMipmapLinear) => (Filter::Nearest, Filter::Linear),
+            Some(MinFilter::LinearMipmapLinear) | None => (Filter::Linear, Filter::Linear),
+        };
+        let mag_filter = match sampler.mag_filter() {
+            Some(MagFilter::Nearest) => Filter::Nearest,
+            Some(MagFilter::Linear) | None => Filter::Linear,
+        };
+
+        TextureSampling {
+            wrap_u: wrap(sampler.wrap_s()),
+            wrap_v: wrap(sampler.wrap_t()),
+            min_filter,
+            mag_filter,
+            mip_filter,
+            ..TextureSampling::new(WrapMode::Tile)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(format: GltfFormat, pixels: Vec<u8>) -> Data {
+        Data {
+            pixels,
+            format,
+            width: 2,
+            height: 1,
+        }
+    }
+
+    #[test]
+    fn rgb_is_expanded_to_rgba() {
+        let rgb8 = image(GltfFormat::R8G8B8, vec![1, 2, 3, 4, 5, 6]);
+        let decoded = GltfImage {
+            image: &rgb8,
+            srgb: true,
+        }
+        .decode()
+        .unwrap();
+        assert_eq!(decoded.kind, Kind::D2(2, 1, 1, 1));
+        assert_eq!(decoded.format, Format::Rgba8Srgb);
+        assert_eq!(decoded.levels, vec![vec![1, 2, 3, 0xFF, 4, 5, 6, 0xFF]]);
+
+        let rgb16 = image(GltfFormat::R16G16B16, (0..12).collect());
+        let decoded = GltfImage {
+            image: &rgb16,
+            srgb: true,
+        }
+        .decode()
+        .unwrap();
+        assert_eq!(decoded.format, Format::Rgba16Unorm);
+        assert_eq!(
+            decoded.levels,
+            vec![vec![
+                0, 1, 2, 3, 4, 5, 0xFF, 0xFF, 6, 7, 8, 9, 10, 11, 0xFF, 0xFF
+            ]]
+        );
+    }
+
+    #[test]
+    fn srgb_selects_8_bit_formats() {
+        let formats = [
+            (GltfFormat::R8, Format::R8Unorm, Format::R8Srgb),
+            (GltfFormat::R8G8, Format::Rg8Unorm, Format::Rg8Srgb),
+            (GltfFormat::R8G8B8A8, Format::Rgba8Unorm, Format::Rgba8Srgb),
+            (GltfFormat::B8G8R8A8, Format::Bgra8Unorm, Format::Bgra8Srgb),
+            (GltfFormat::R16, Format::R16Unorm, Format::R16Unorm),
+            (GltfFormat::R16G16, Format::Rg16Unorm, Format::Rg16Unorm),
+        ];
+        for &(gltf, unorm, srgb) in &formats {
+            let data = image(gltf, vec![0; 16]);
+            let linear = GltfImage {
+                image: &data,
+                srgb: false,
+            }
+            .decode()
+            .unwrap();
+            assert_eq!(linear.format, unorm);
+            assert_eq!(linear.levels, vec![data.pixels.clone()]);
+            let color = GltfImage {
+                image: &data,
+                srgb: true,
+            }
+            .decode()
+            .unwrap();
+            assert_eq!(color.format, srgb);
+        }
+    }
+}
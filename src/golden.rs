@@ -0,0 +1,108 @@
+//! Helpers for testing texture data against golden files.
+
+use error::TextureError;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use compare::{CompareResult, CompareTolerance};
+use format;
+use TextureBuilder;
+
+/// Environment variable that makes `match_golden` write golden files when set to `1`.
+pub const UPDATE_GOLDEN: &str = "UPDATE_GOLDEN";
+
+/// Compare base level data of the builder with golden file.
+/// Golden file contains tightly packed data of all layers in native byte order.
+/// When `UPDATE_GOLDEN=1` is set the file is written from the builder instead.
+pub fn match_golden<P>(
+    builder: &TextureBuilder,
+    path: P,
+    tolerance: CompareTolerance,
+) -> Result<CompareResult, TextureError>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    builder.check_base_size()?;
+    let format = builder.data_format();
+    let extent = builder.kind.extent();
+    let data = builder.base_source();
+    let data = format::pack_tight(
+        format,
+        extent,
+        builder.data_width(),
+        builder.data_height(),
+        builder.kind.num_layers(),
+        &data,
+    );
+
+    if env::var(UPDATE_GOLDEN).ok().as_deref() == Some("1") {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, &data)?;
+    }
+
+    let golden = TextureBuilder::new(builder.kind)
+        .with_format(format)
+        .with_data_width(extent.width)
+        .with_data_height(extent.height)
+        .with_data(fs::read(path)?);
+    let current = TextureBuilder::new(builder.kind)
+        .with_format(format)
+        .with_data_width(extent.width)
+        .with_data_height(extent.height)
+        .with_data(data);
+    current.compare(&golden, tolerance)
+}
+
+/// Assert that base level data of `TextureBuilder` matches golden file within tolerance.
+/// Tolerance defaults to exact match.
+/// Set `UPDATE_GOLDEN=1` to write golden files from current data.
+#[macro_export]
+macro_rules! assert_texture_matches {
+    ($builder:expr, $path:expr) => {
+        assert_texture_matches!($builder, $path, $crate::CompareTolerance::exact())
+    };
+    ($builder:expr, $path:expr, $tolerance:expr) => {{
+        let path = $path;
+        match $crate::match_golden(&$builder, &path, $tolerance) {
+            Ok(ref result) if result.matches() => {}
+            Ok(result) => panic!(
+                "Texture doesn't match golden file {:?}: {} texels differ, max difference {} at {:?}",
+                path, result.differing_texels, result.max_difference, result.worst_texel,
+            ),
+            Err(error) => panic!("Can't match texture with golden file {:?}: {}", path, error),
+        }
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process;
+
+    use hal::format::Format;
+    use hal::image::Kind;
+
+    use super::*;
+
+    #[test]
+    fn golden_file_ignores_row_padding() {
+        let path = env::temp_dir().join(format!("gfx-texture-golden-{}.bin", process::id()));
+        fs::write(&path, [1u8, 2, 4, 5]).unwrap();
+
+        let builder = TextureBuilder::new(Kind::D2(2, 2, 1, 1))
+            .with_format(Format::R8Unorm)
+            .with_data_bytes(vec![1u8, 2, 3, 4, 5, 6])
+            .with_data_width(3);
+        assert_texture_matches!(builder, &path);
+
+        let changed = builder.clone().with_data_bytes(vec![1u8, 2, 3, 4, 9, 6]);
+        let result = match_golden(&changed, &path, CompareTolerance::exact()).unwrap();
+        assert_eq!(result.worst_texel, Some((1, 1, 0)));
+        assert!(!result.matches());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
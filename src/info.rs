@@ -0,0 +1,200 @@
+//! Description of texture separate from its data.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::io::Read;
+
+use hal::format::{Format, Swizzle};
+use hal::image::{Kind, Level, StorageFlags, Usage};
+use hal::queue::QueueFamilyId;
+use hal::Backend;
+
+use render::Factory;
+
+use error::TextureError;
+#[cfg(feature = "serde")]
+use kind;
+use {Texture, TextureBuilder};
+
+/// What the image is, regardless of where its data comes from.
+/// Suitable as cache key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TextureInfo {
+    /// Kind of the image.
+    #[cfg_attr(feature = "serde", serde(with = "kind::serde_kind"))]
+    pub kind: Kind,
+    /// Format of the image.
+    pub format: Format,
+    /// Limit of mip levels including base level.
+    pub max_levels: Option<Level>,
+    /// Whether mip levels are generated on CPU from base level.
+    pub generate_mips: bool,
+    /// Additional usage flags of the image.
+    pub usage: Usage,
+    /// Additional storage flags of the image.
+    pub storage_flags: StorageFlags,
+    /// Swizzle of the view.
+    pub swizzle: Swizzle,
+}
+
+impl TextureInfo {
+    /// Description of `Rgba8Srgb` image of the kind with defaults of `TextureBuilder`.
+    pub fn new(kind: Kind) -> Self {
+        TextureInfo {
+            kind,
+            format: Format::Rgba8Srgb,
+            max_levels: None,
+            generate_mips: false,
+            usage: Usage::empty(),
+            storage_flags: StorageFlags::empty(),
+            swizzle: Swizzle::NO,
+        }
+    }
+}
+
+/// Where data of the image comes from.
+pub enum DataSource<'a> {
+    /// Image without data, e.g. render target.
+    None,
+    /// Base level with rows `data_width` texels apart and slices `data_height` rows apart.
+    Bytes {
+        /// Data of all layers.
+        data: Cow<'a, [u8]>,
+        /// Number of texels between rows.
+        data_width: u32,
+        /// Number of rows between slices.
+        data_height: u32,
+    },
+    /// Tightly packed data of each level starting from base,
+    /// with all layers of the level following each other.
+    Levels(Vec<Cow<'a, [u8]>>),
+    /// Tightly packed base level data of each layer.
+    Layers(Vec<Cow<'a, [u8]>>),
+    /// Tightly packed base level data read to the end of the stream.
+    Stream(Box<dyn Read + 'a>),
+}
+
+impl<'a> fmt::Debug for DataSource<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DataSource::None => f.write_str("None"),
+            DataSource::Bytes {
+                ref data,
+                data_width,
+                data_height,
+            } => f
+                .debug_struct("Bytes")
+                .field("len", &data.len())
+                .field("data_width", &data_width)
+                .field("data_height", &data_height)
+                .finish(),
+            DataSource::Levels(ref levels) => f.debug_tuple("Levels").field(&levels.len()).finish(),
+            DataSource::Layers(ref layers) => f.debug_tuple("Layers").field(&layers.len()).finish(),
+            DataSource::Stream(_) => f.write_str("Stream"),
+        }
+    }
+}
+
+impl<'a> TextureBuilder<'a> {
+    /// Create builder from image description and data source.
+    /// Stream sources are read to the end here.
+    pub fn from_parts(info: TextureInfo, source: DataSource<'a>) -> Result<Self, TextureError> {
+        let mut builder = TextureBuilder::new(info.kind);
+        builder
+            .set_format(info.format)
+            .set_generate_mips(info.generate_mips)
+            .set_usage(info.usage)
+            .set_storage_flags(info.storage_flags)
+            .set_swizzle(info.swizzle);
+        if let Some(max_levels) = info.max_levels {
+            builder.set_max_levels(max_levels);
+        }
+
+        match source {
+            DataSource::None => {}
+            DataSource::Bytes {
+                data,
+                data_width,
+                data_height,
+            } => {
+                builder
+                    .set_data(data)
+                    .set_data_width(data_width)
+                    .set_data_height(data_height);
+            }
+            DataSource::Levels(levels) => {
+                let mut levels = levels.into_iter();
+                builder.set_data(levels.next().unwrap_or_default());
+                for (level, data) in levels.enumerate() {
+                    builder.set_mip_data(level as u8 + 1, data);
+                }
+            }
+            DataSource::Layers(layers) => {
+                let chunks = layers.iter().map(|layer| &**layer).collect::<Vec<_>>();
+                builder.set_data_chunks(&chunks);
+            }
+            DataSource::Stream(mut stream) => {
+                let mut data = Vec::new();
+                stream.read_to_end(&mut data)?;
+                builder.set_data(data);
+            }
+        }
+        Ok(builder)
+    }
+
+    /// Description of the image regardless of its data.
+    pub fn info(&self) -> TextureInfo {
+        TextureInfo {
+            kind: self.kind,
+            format: self.format,
+            max_levels: self.max_levels,
+            generate_mips: self.generate_mips,
+            usage: self.usage,
+            storage_flags: self.storage_flags,
+            swizzle: self.swizzle,
+        }
+    }
+}
+
+/// Build texture from image description and data source
+/// with other parameters of `TextureBuilder` left default.
+pub fn build<B>(
+    info: TextureInfo,
+    source: DataSource,
+    family: QueueFamilyId,
+    factory: &mut Factory<B>,
+) -> Result<Texture<B>, TextureError>
+where
+    B: Backend,
+{
+    TextureBuilder::from_parts(info, source)?.build(family, factory)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_target_needs_no_data() {
+        let mut info = TextureInfo::new(Kind::D2(64, 64, 1, 1));
+        info.format = Format::Rgba16Float;
+        info.usage = Usage::COLOR_ATTACHMENT;
+        let builder = TextureBuilder::from_parts(info, DataSource::None).unwrap();
+        builder.validate().unwrap();
+        assert_eq!(builder.info(), info);
+    }
+
+    #[test]
+    fn sources_fill_builder_data() {
+        let info = TextureInfo::new(Kind::D2(2, 2, 1, 1));
+        let stream = DataSource::Stream(Box::new(&[7u8; 16][..]));
+        let builder = TextureBuilder::from_parts(info, stream).unwrap();
+        builder.validate().unwrap();
+
+        let levels = DataSource::Levels(vec![vec![0u8; 16].into(), vec![0u8; 4].into()]);
+        let builder = TextureBuilder::from_parts(info, levels).unwrap();
+        assert_eq!(builder.levels(), 2);
+        builder.validate().unwrap();
+    }
+}
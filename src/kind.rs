@@ -0,0 +1,125 @@
+//! Serializable description of image kind.
+
+use hal::image::{Kind, Layer, NumSamples};
+
+/// Local mirror of `Kind` with stable serialized representation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum KindDesc {
+    /// One-dimensional image.
+    D1 {
+        /// Width in texels.
+        width: u32,
+        /// Number of array layers.
+        layers: Layer,
+    },
+    /// Two-dimensional image.
+    D2 {
+        /// Width in texels.
+        width: u32,
+        /// Height in texels.
+        height: u32,
+        /// Number of array layers.
+        layers: Layer,
+        /// Number of samples per texel.
+        samples: NumSamples,
+    },
+    /// Volume image.
+    D3 {
+        /// Width in texels.
+        width: u32,
+        /// Height in texels.
+        height: u32,
+        /// Depth in texels.
+        depth: u32,
+    },
+}
+
+impl From<Kind> for KindDesc {
+    fn from(kind: Kind) -> Self {
+        match kind {
+            Kind::D1(width, layers) => KindDesc::D1 { width, layers },
+            Kind::D2(width, height, layers, samples) => KindDesc::D2 {
+                width,
+                height,
+                layers,
+                samples,
+            },
+            Kind::D3(width, height, depth) => KindDesc::D3 {
+                width,
+                height,
+                depth,
+            },
+        }
+    }
+}
+
+impl From<KindDesc> for Kind {
+    fn from(desc: KindDesc) -> Self {
+        match desc {
+            KindDesc::D1 { width, layers } => Kind::D1(width, layers),
+            KindDesc::D2 {
+                width,
+                height,
+                layers,
+                samples,
+            } => Kind::D2(width, height, layers, samples),
+            KindDesc::D3 {
+                width,
+                height,
+                depth,
+            } => Kind::D3(width, height, depth),
+        }
+    }
+}
+
+/// Serialize `Kind` fields as `KindDesc` with `#[serde(with = "kind::serde_kind")]`.
+#[cfg(feature = "serde")]
+pub(crate) mod serde_kind {
+    use hal::image::Kind;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::KindDesc;
+
+    pub fn serialize<S>(kind: &Kind, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        KindDesc::from(*kind).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Kind, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        KindDesc::deserialize(deserializer).map(Kind::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KINDS: [Kind; 3] = [Kind::D1(64, 2), Kind::D2(32, 16, 6, 4), Kind::D3(8, 4, 2)];
+
+    #[test]
+    fn round_trip() {
+        for &kind in &KINDS {
+            assert_eq!(Kind::from(KindDesc::from(kind)), kind);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        extern crate serde_json;
+
+        for &kind in &KINDS {
+            let json = serde_json::to_string(&KindDesc::from(kind)).unwrap();
+            let desc: KindDesc = serde_json::from_str(&json).unwrap();
+            assert_eq!(Kind::from(desc), kind);
+        }
+        let json = serde_json::to_string(&KindDesc::from(KINDS[2])).unwrap();
+        assert_eq!(json, r#"{"D3":{"width":8,"height":4,"depth":2}}"#);
+    }
+}
@@ -2,94 +2,494 @@ extern crate failure;
 extern crate gfx_hal as hal;
 extern crate gfx_render as render;
 
+#[cfg(feature = "load-dds")]
+extern crate ddsfile;
+#[cfg(feature = "gltf")]
+extern crate gltf;
+#[cfg(feature = "logging")]
+#[macro_use]
+extern crate log;
+#[cfg(feature = "mmap")]
+extern crate memmap;
+#[cfg(feature = "load-png")]
+extern crate png;
+
 #[cfg(feature = "serde")]
 #[macro_use]
 extern crate serde;
 
 use std::borrow::{Borrow, Cow};
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
 
-use failure::Error;
-
-use hal::format::{Aspects, Format, Swizzle};
+use hal::adapter::MemoryProperties;
+use hal::command::{
+    BufferImageCopy, ClearColor, ClearDepthStencil, ImageBlit, ImageCopy, ImageResolve,
+    RawCommandBuffer,
+};
+use hal::format::{Aspects, ChannelType, Format, ImageFeature, SurfaceType, Swizzle};
 use hal::image::{
-    Access, Kind, Layout, Offset, StorageFlags, SubresourceLayers, SubresourceRange, Tiling, Usage,
-    ViewKind,
+    Access, Extent, Filter, Kind, Layer, Layout, Level, NumSamples, Offset, SamplerInfo,
+    StorageFlags, Subresource, SubresourceLayers, SubresourceRange, Tiling, Usage, ViewKind,
 };
 use hal::memory::Properties;
+use hal::pso::PipelineStage;
 use hal::queue::QueueFamilyId;
-use hal::{Backend, Device};
+use hal::{Backend, Device, PhysicalDevice};
 
 use render::{Factory, Image};
 
+mod budget;
+mod color;
+mod command;
+mod compare;
+#[cfg(feature = "compress-bc")]
+mod compress;
+mod convert;
+#[cfg(feature = "load-dds")]
+mod dds_import;
+mod diff;
+mod error;
+mod format;
+#[cfg(feature = "gltf")]
+mod gltf_import;
+#[cfg(feature = "test-support")]
+mod golden;
+mod info;
+mod kind;
+#[doc(hidden)]
+#[macro_use]
+pub mod macros;
+#[cfg(feature = "mmap")]
+mod mapped;
+mod mip;
+#[cfg(feature = "load-png")]
+mod png_import;
+mod pool;
+mod preset;
+mod readback;
+mod resolved;
+mod sampling;
+mod source;
+mod stats;
+mod streaming;
+mod swizzle;
+mod texture_set;
+mod view;
+mod yuv;
+
+pub use budget::TextureBudget;
+pub use command::PendingUpload;
+pub use compare::{compare, CompareResult, CompareTolerance};
+#[cfg(feature = "compress-bc")]
+pub use compress::{BlockCompression, CompressionHint, CompressionQuality};
+pub use convert::{BitOrder, BorderMode, DitherMode};
+#[cfg(feature = "load-dds")]
+pub use dds_import::DdsImage;
+pub use diff::BuilderDiff;
+pub use error::TextureError;
+pub use format::{AstcProfile, Endianness};
+#[cfg(feature = "gltf")]
+pub use gltf_import::GltfImage;
+#[cfg(feature = "test-support")]
+pub use golden::{match_golden, UPDATE_GOLDEN};
+pub use info::{build, DataSource, TextureInfo};
+pub use kind::KindDesc;
+#[cfg(feature = "mmap")]
+pub use mapped::MappedFile;
+pub use mip::MipFilter;
+#[cfg(feature = "load-png")]
+pub use png_import::PngImage;
+pub use pool::{TexturePin, TexturePool};
+pub use preset::TexturePreset;
+pub use readback::MappedReader;
+pub use resolved::ResolvedTextureParams;
+pub use sampling::{clamp_anisotropy, LodClamp, TextureSampling};
+pub use source::{DecodedTexture, LayerSource, TextureSource};
+pub use stats::{ChannelStats, TextureStats, UploadStats};
+pub use streaming::StreamingTexture;
+pub use swizzle::SwizzlePresets;
+pub use texture_set::TextureSet;
+pub use view::OwnedView;
+pub use yuv::{Nv12Builder, Nv12Texture};
+
+use budget::Charge;
+use mip::MipOptions;
+
 /// Texture builder allow user to build texture
 /// specifying image kind, format and data properties.
-#[derive(Clone, Debug)]
+/// Builder is `Send` and `Sync`, so it can be filled on loader threads.
+#[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TextureBuilder<'a> {
+    #[cfg_attr(feature = "serde", serde(with = "kind::serde_kind"))]
     kind: Kind,
     format: Format,
-    data_width: u32,
-    data_height: u32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    data_width: Option<u32>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    data_height: Option<u32>,
     data: Cow<'a, [u8]>,
+    mip_data: Vec<Cow<'a, [u8]>>,
+    prefer_unified_memory: bool,
+    generate_mips: bool,
+    max_levels: Option<u8>,
+    min_mip_extent: Option<u32>,
+    mip_filter: MipFilter,
+    mip_renormalize: bool,
+    mip_gamma_correct: Option<bool>,
+    astc_profile: AstcProfile,
+    alpha_coverage: Option<f32>,
+    min_resident_level: Level,
+    retain_source: bool,
+    usage: Usage,
+    storage_flags: StorageFlags,
+    source_endianness: Endianness,
+    swizzle: Swizzle,
+    sanitize_non_finite: bool,
+    sampling: Option<TextureSampling>,
+    lod_clamp: Option<LodClamp>,
+    pot_wrap_required: bool,
+    chroma_key: Option<([u8; 3], u8)>,
+    chroma_bleed: bool,
+    source_format: Option<Format>,
+    dither: DitherMode,
+    clear_unspecified_levels: Option<[f32; 4]>,
+    tileable_blend: Option<u32>,
+    border: Option<(u32, BorderMode)>,
+    #[cfg(feature = "compress-bc")]
+    compress: Option<(BlockCompression, CompressionQuality)>,
+    #[cfg(feature = "compress-bc")]
+    auto_compression: Option<CompressionHint>,
+    name: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    oom_policy: OomPolicy,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pre_transform: PreTransform,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    budget: Option<Arc<TextureBudget>>,
+    final_layout: Option<Layout>,
+    transient: bool,
+    per_aspect_upload: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    remaining_bytes_ignored: bool,
 }
 
 impl<'a> TextureBuilder<'a> {
     /// Crate builder with specified kind.
     pub fn new(kind: Kind) -> Self {
-        let extent = kind.extent();
         TextureBuilder {
-            kind: kind,
+            kind,
             format: Format::Rgba8Srgb,
-            data_width: extent.width,
-            data_height: extent.height,
+            data_width: None,
+            data_height: None,
             data: Vec::new().into(),
+            mip_data: Vec::new(),
+            prefer_unified_memory: true,
+            generate_mips: false,
+            max_levels: None,
+            min_mip_extent: None,
+            mip_filter: MipFilter::Box,
+            mip_renormalize: false,
+            mip_gamma_correct: None,
+            astc_profile: AstcProfile::Ldr,
+            alpha_coverage: None,
+            min_resident_level: 0,
+            retain_source: false,
+            usage: Usage::empty(),
+            storage_flags: StorageFlags::empty(),
+            source_endianness: Endianness::Native,
+            swizzle: Swizzle::NO,
+            sanitize_non_finite: false,
+            sampling: None,
+            lod_clamp: None,
+            pot_wrap_required: false,
+            chroma_key: None,
+            chroma_bleed: false,
+            source_format: None,
+            dither: DitherMode::None,
+            clear_unspecified_levels: None,
+            tileable_blend: None,
+            border: None,
+            #[cfg(feature = "compress-bc")]
+            compress: None,
+            #[cfg(feature = "compress-bc")]
+            auto_compression: None,
+            name: None,
+            oom_policy: OomPolicy::Fail,
+            pre_transform: PreTransform::Identity,
+            budget: None,
+            final_layout: None,
+            transient: false,
+            per_aspect_upload: false,
+            remaining_bytes_ignored: false,
+        }
+    }
+
+    /// Create builder for 2D `Rgba8Srgb` texture from palette-indexed texels.
+    /// Palette may have less than 256 entries,
+    /// indices outside of it produce `TextureError::IndexOutOfRange`.
+    /// Texels with `transparent` index are expanded with zero alpha.
+    pub fn expand_indexed(
+        indices: &[u8],
+        palette: &[[u8; 4]],
+        width: u32,
+        height: u32,
+        transparent: Option<u8>,
+    ) -> Result<Self, TextureError> {
+        let count = width as usize * height as usize;
+        if indices.len() < count {
+            Err(TextureError::LevelSize {
+                level: 0,
+                expected: count,
+                actual: indices.len(),
+            })?;
+        }
+        let texels = convert::expand_indexed(&indices[..count], palette, transparent)?;
+        Ok(TextureBuilder::new(Kind::D2(width, height, 1, 1))
+            .with_format(Format::Rgba8Srgb)
+            .with_data(cast_vec(texels)))
+    }
+
+    /// Create builder for 2D `R8Unorm` texture from 1, 2 or 4 bits per texel data.
+    /// Each row is padded to byte boundary.
+    /// With `full_range` values are scaled to cover whole `0 ..= 255` range,
+    /// e.g. 1-bit values become `0` or `255` and 4-bit values become `n * 17`.
+    pub fn from_bitfield(
+        data: &[u8],
+        width: u32,
+        height: u32,
+        bits_per_pixel: u8,
+        order: BitOrder,
+        full_range: bool,
+    ) -> Result<Self, TextureError> {
+        let texels =
+            convert::expand_bitfield(data, width, height, bits_per_pixel, order, full_range)?;
+        Ok(TextureBuilder::new(Kind::D2(width, height, 1, 1))
+            .with_format(Format::R8Unorm)
+            .with_data(texels))
+    }
+
+    /// Create builder with kind, format and tightly packed base level data.
+    /// Length of `bytes` must match size of all layers exactly.
+    pub fn from_bytes<D>(kind: Kind, format: Format, bytes: D) -> Result<Self, TextureError>
+    where
+        D: Into<Cow<'a, [u8]>>,
+    {
+        let bytes = bytes.into();
+        let extent = kind.extent();
+        let expected = format::strided_size(
+            format,
+            extent,
+            extent.width,
+            extent.height,
+            kind.num_layers(),
+        );
+        if bytes.len() != expected {
+            Err(TextureError::LevelSize {
+                level: 0,
+                expected,
+                actual: bytes.len(),
+            })?;
+        }
+        Ok(TextureBuilder::new(kind)
+            .with_format(format)
+            .with_data(bytes))
+    }
+
+    /// Create builder filled with RGBA `color` encoded for format with 8-bit channels,
+    /// e.g. red and blue bytes are swapped for BGRA formats.
+    pub fn solid_color(kind: Kind, format: Format, color: [u8; 4]) -> Result<Self, TextureError> {
+        let texel = convert::solid_texel(format, color)
+            .ok_or(TextureError::EncodeUnsupportedFormat { format })?;
+        let extent = kind.extent();
+        let count = extent.width as usize
+            * extent.height as usize
+            * extent.depth as usize
+            * kind.num_layers() as usize;
+        Ok(TextureBuilder::new(kind)
+            .with_format(format)
+            .with_data(texel.repeat(count)))
+    }
+
+    /// Create builder for 2D texture of `size` texels square
+    /// with checkers of `cell` texels alternating RGBA colors `a` and `b`.
+    /// Colors are encoded for format with 8-bit channels, same as in `solid_color`.
+    pub fn checkerboard(
+        size: u32,
+        cell: u32,
+        a: [u8; 4],
+        b: [u8; 4],
+        format: Format,
+    ) -> Result<Self, TextureError> {
+        if cell == 0 {
+            Err(TextureError::EmptyCheckerCell)?;
+        }
+        let unsupported = TextureError::EncodeUnsupportedFormat { format };
+        let a = convert::solid_texel(format, a).ok_or_else(|| unsupported.clone())?;
+        let b = convert::solid_texel(format, b).ok_or(unsupported)?;
+        let mut data = Vec::with_capacity(size as usize * size as usize * a.len());
+        for y in 0..size {
+            for x in 0..size {
+                let texel = if (x / cell + y / cell).is_multiple_of(2) {
+                    &a
+                } else {
+                    &b
+                };
+                data.extend_from_slice(texel);
+            }
+        }
+        Ok(TextureBuilder::new(Kind::D2(size, size, 1, 1))
+            .with_format(format)
+            .with_data(data))
+    }
+
+    /// Create builder for 1D texture of `width` texels interpolating gradient `stops`.
+    /// Each stop is offset in `0 ..= 1` range with linear RGBA color.
+    /// Stops must be sorted by offset, texels sample gradient at their centers.
+    pub fn gradient_1d(
+        width: u32,
+        stops: &[(f32, [f32; 4])],
+        format: Format,
+    ) -> Result<Self, TextureError> {
+        let mut previous = 0.0;
+        for (index, &(offset, _)) in stops.iter().enumerate() {
+            if !(offset >= previous && offset <= 1.0) {
+                Err(TextureError::InvalidGradientStop { index, offset })?;
+            }
+            previous = offset;
+        }
+        if stops.is_empty() {
+            Err(TextureError::EmptyGradient)?;
         }
+        let data = mip::gradient(format, width, stops)
+            .ok_or(TextureError::EncodeUnsupportedFormat { format })?;
+        Ok(TextureBuilder::new(Kind::D1(width, 1))
+            .with_format(format)
+            .with_data(data))
+    }
+
+    /// Create builder for attachment that lives only during render pass
+    /// in lazily allocated memory of tile-based GPUs.
+    /// Image gets color or depth-stencil attachment usage along with
+    /// `Usage::TRANSIENT_ATTACHMENT` and `Usage::INPUT_ATTACHMENT`.
+    /// Texture is not uploaded, it is left in `Layout::Undefined`.
+    /// Building fails with `TextureError::LazyMemoryUnsupported`
+    /// if device has no lazily allocated memory.
+    pub fn transient_attachment(kind: Kind, format: Format) -> Self {
+        let mut builder = TextureBuilder::new(kind).with_format(format);
+        builder.transient = true;
+        builder
+    }
+
+    /// Create builder for 2D texture with `Rgba32Float` format from floating point texels.
+    pub fn hdr2d(width: u32, height: u32, data: Vec<[f32; 4]>) -> Self {
+        TextureBuilder::new(Kind::D2(width, height, 1, 1))
+            .with_format(Format::Rgba32Float)
+            .with_data(cast_slice(&data).to_vec())
+    }
+
+    /// Create builder for 1x1 `Rgba8Unorm` flat normal map.
+    /// Texel is `(0.5, 0.5, 1.0, 1.0)` encoded as `[128, 128, 255, 255]`,
+    /// i.e. unperturbed tangent space normal `(0, 0, 1)`.
+    pub fn flat_normal() -> Self {
+        TextureBuilder::new(Kind::D2(1, 1, 1, 1))
+            .with_format(Format::Rgba8Unorm)
+            .with_data(vec![128u8, 128, 255, 255])
+    }
+
+    /// Set kind of the texture to create.
+    /// Data width and height are reset to tight rows of the kind.
+    #[must_use]
+    pub fn with_kind(mut self, kind: Kind) -> Self {
+        self.set_kind(kind);
+        self
+    }
+
+    /// Set kind of the texture to create.
+    /// Data width and height are reset to tight rows of the kind.
+    pub fn set_kind(&mut self, kind: Kind) -> &mut Self {
+        self.kind = kind;
+        self.set_tight_rows()
     }
 
     /// Set image format of the texture to create.
+    #[must_use]
     pub fn with_format(mut self, format: Format) -> Self {
         self.set_format(format);
         self
     }
 
     /// Set image format of the texture to create.
+    /// Format must be color or depth format.
+    /// Data of depth-stencil formats is uploaded into depth aspect.
     pub fn set_format(&mut self, format: Format) -> &mut Self {
-        assert_eq!(format.surface_desc().aspects, Aspects::COLOR);
+        let aspects = format.surface_desc().aspects;
+        assert!(aspects == Aspects::COLOR || aspects.contains(Aspects::DEPTH));
         self.format = format;
         self
     }
 
     /// Set data width of the raw image bytes (also known as stride).
-    /// The number of bytes between lines of the image.
+    /// The number of texels between lines of the image, not bytes.
+    #[must_use]
     pub fn with_data_width(mut self, data_width: u32) -> Self {
         self.set_data_width(data_width);
         self
     }
 
     /// Set data width of the raw image bytes (also known as stride).
-    /// The number of bytes between lines of the image.
+    /// The number of texels between lines of the image, not bytes.
     pub fn set_data_width(&mut self, data_width: u32) -> &mut Self {
         assert!(data_width >= self.kind.extent().width);
-        self.data_width = data_width;
+        self.data_width = Some(data_width);
         self
     }
 
     /// Set data height of the raw image bytes.
-    /// The number of bytes between layers of the 3d image.
+    /// The number of lines between layers of the 3d image.
+    #[must_use]
     pub fn with_data_height(mut self, data_height: u32) -> Self {
         self.set_data_height(data_height);
         self
     }
 
     /// Set data height of the raw image bytes.
-    /// The number of bytes between layers of the 3d image.
+    /// The number of lines between layers of the 3d image.
     pub fn set_data_height(&mut self, data_height: u32) -> &mut Self {
         assert!(data_height >= self.kind.extent().height);
-        self.data_height = data_height;
+        self.data_height = Some(data_height);
+        self
+    }
+
+    /// Reset data width and height to tightly packed rows of the extent
+    /// rounded up to whole blocks of the format.
+    /// This is the default until stride is set explicitly,
+    /// and follows later changes of the format.
+    #[must_use]
+    pub fn with_tight_rows(mut self) -> Self {
+        self.set_tight_rows();
+        self
+    }
+
+    /// Reset data width and height to tightly packed rows of the extent
+    /// rounded up to whole blocks of the format.
+    /// This is the default until stride is set explicitly,
+    /// and follows later changes of the format.
+    pub fn set_tight_rows(&mut self) -> &mut Self {
+        self.data_width = None;
+        self.data_height = None;
         self
     }
 
     /// Set raw data for the image.
+    /// Data can be slice of bytes or typed texels such as `u16` for 16-bit formats.
+    /// Borrowed data is never copied by the builder.
+    /// `build` reads it straight into staging memory, so it can point into
+    /// memory-mapped file as long as the mapping outlives `build` call.
+    #[must_use]
     pub fn with_data<D, P>(mut self, data: D) -> Self
     where
         D: Into<Cow<'a, [P]>>,
@@ -100,6 +500,10 @@ impl<'a> TextureBuilder<'a> {
     }
 
     /// Set raw data for the image.
+    /// Data can be slice of bytes or typed texels such as `u16` for 16-bit formats.
+    /// Borrowed data is never copied by the builder.
+    /// `build` reads it straight into staging memory, so it can point into
+    /// memory-mapped file as long as the mapping outlives `build` call.
     pub fn set_data<D, P>(&mut self, data: D) -> &mut Self
     where
         D: Into<Cow<'a, [P]>>,
@@ -109,141 +513,5420 @@ impl<'a> TextureBuilder<'a> {
         self
     }
 
-    /// Build texture and filling it with data provided.
-    pub fn build<B>(
-        &self,
-        family: QueueFamilyId,
-        factory: &mut Factory<B>,
-    ) -> Result<Texture<B>, Error>
+    /// Set raw bytes for the image.
+    /// Same as `with_data` without inferring texel type.
+    #[must_use]
+    pub fn with_data_bytes<D>(mut self, data: D) -> Self
     where
-        B: Backend,
+        D: Into<Cow<'a, [u8]>>,
     {
-        let extent = self.kind.extent();
-        assert!(self.data_width >= extent.width);
-        assert!(
-            self.data.len() * 8
-                >= (self.data_width
-                    * extent.height
-                    * extent.depth
-                    * self.format.base_format().0.desc().bits as u32) as usize
-        );
+        self.set_data_bytes(data);
+        self
+    }
 
-        let mut image = factory.create_image(
-            self.kind,
-            1,
-            self.format,
-            Tiling::Optimal,
-            StorageFlags::empty(),
-            Usage::TRANSFER_DST | Usage::SAMPLED,
-            Properties::DEVICE_LOCAL,
-        )?;
+    /// Set raw bytes for the image.
+    /// Same as `set_data` without inferring texel type.
+    pub fn set_data_bytes<D>(&mut self, data: D) -> &mut Self
+    where
+        D: Into<Cow<'a, [u8]>>,
+    {
+        self.data = data.into();
+        self
+    }
 
-        let view = factory.create_image_view(
-            image.borrow(),
-            match self.kind {
-                Kind::D1(_, _) => ViewKind::D1,
-                Kind::D2(_, _, _, _) => ViewKind::D2,
-                Kind::D3(_, _, _) => ViewKind::D3,
-            },
-            self.format,
-            Swizzle::NO,
-            SubresourceRange {
-                aspects: Aspects::COLOR,
-                levels: 0..1,
-                layers: 0..1,
-            },
-        )?;
+    /// Borrow raw bytes for the image from any byte container,
+    /// e.g. shared buffer or memory-mapped file, without copying.
+    #[must_use]
+    pub fn with_data_ref<D>(mut self, data: &'a D) -> Self
+    where
+        D: AsRef<[u8]> + ?Sized,
+    {
+        self.set_data_ref(data);
+        self
+    }
 
-        factory.upload_image(
-            &mut image,
-            family,
-            Layout::ShaderReadOnlyOptimal,
-            Access::SHADER_READ,
-            SubresourceLayers {
-                aspects: Aspects::COLOR,
-                level: 0,
-                layers: 0..1,
-            },
-            Offset::ZERO,
-            self.kind.extent(),
-            self.data_width,
-            self.data_height,
-            &self.data,
-        )?;
+    /// Borrow raw bytes for the image from any byte container,
+    /// e.g. shared buffer or memory-mapped file, without copying.
+    pub fn set_data_ref<D>(&mut self, data: &'a D) -> &mut Self
+    where
+        D: AsRef<[u8]> + ?Sized,
+    {
+        self.data = Cow::Borrowed(data.as_ref());
+        self
+    }
 
-        Ok(Texture {
-            kind: self.kind,
-            format: self.format,
-            image,
-            view,
-        })
+    /// Set raw data for the image from chunks following each other,
+    /// e.g. tiles or row bands produced by decoder.
+    /// Chunks are copied into single owned buffer allocated once.
+    /// Total length is checked on `validate` same as for `with_data`.
+    #[must_use]
+    pub fn with_data_chunks(mut self, chunks: &[&[u8]]) -> Self {
+        self.set_data_chunks(chunks);
+        self
     }
-}
 
-/// Texture is persistent image accessible by GPU as sampled.
-#[derive(Debug)]
-pub struct Texture<B: Backend> {
-    kind: Kind,
-    format: Format,
-    image: Image<B>,
-    view: B::ImageView,
-}
+    /// Set raw data for the image from chunks following each other,
+    /// e.g. tiles or row bands produced by decoder.
+    /// Chunks are copied into single owned buffer allocated once.
+    /// Total length is checked on `validate` same as for `with_data`.
+    pub fn set_data_chunks(&mut self, chunks: &[&[u8]]) -> &mut Self {
+        let mut data = Vec::with_capacity(chunks.iter().map(|chunk| chunk.len()).sum());
+        for chunk in chunks {
+            data.extend_from_slice(chunk);
+        }
+        self.data = Cow::Owned(data);
+        self
+    }
 
-impl<B> Texture<B>
-where
-    B: Backend,
-{
-    pub fn new<'a>(kind: Kind) -> TextureBuilder<'a> {
-        TextureBuilder::new(kind)
+    /// Append one depth slice of base level data of 3D texture.
+    /// Slice must contain `data_height` rows of `data_width` texels.
+    /// Data becomes owned by the builder.
+    pub fn push_slice(&mut self, slice: &[u8]) -> Result<&mut Self, TextureError> {
+        let depth = match self.kind {
+            Kind::D3(_, _, depth) => depth,
+            kind => Err(TextureError::NotVolume { kind })?,
+        };
+        let slice_size = format::slice_size(self.format, self.data_width(), self.data_height());
+        if slice.len() != slice_size {
+            Err(TextureError::SliceSize {
+                expected: slice_size,
+                actual: slice.len(),
+            })?;
+        }
+        if self.data.len() / slice_size >= depth as usize {
+            Err(TextureError::TooManySlices { depth })?;
+        }
+        self.data.to_mut().extend_from_slice(slice);
+        Ok(self)
     }
 
-    pub fn image(&self) -> &Image<B> {
-        &self.image
+    /// Set raw data for mip level.
+    /// Unlike base level data, mip levels are tightly packed and
+    /// must contain all array layers.
+    /// Skipped levels are left empty.
+    #[must_use]
+    pub fn with_mip_data<D, P>(mut self, level: u8, data: D) -> Self
+    where
+        D: Into<Cow<'a, [P]>>,
+        P: Clone + 'a,
+    {
+        self.set_mip_data(level, data);
+        self
     }
 
-    pub fn view(&self) -> &B::ImageView {
-        &self.view
+    /// Set raw data for mip level.
+    /// Unlike base level data, mip levels are tightly packed and
+    /// must contain all array layers.
+    /// Skipped levels are left empty.
+    pub fn set_mip_data<D, P>(&mut self, level: u8, data: D) -> &mut Self
+    where
+        D: Into<Cow<'a, [P]>>,
+        P: Clone + 'a,
+    {
+        assert!(level > 0);
+        let data = cast_cow(data.into());
+        if level as usize > self.mip_data.len() {
+            self.mip_data.resize(level as usize, Cow::Borrowed(&[]));
+        }
+        self.mip_data[level as usize - 1] = data;
+        self
     }
 
-    pub fn format(&self) -> Format {
-        self.format
+    /// Allow `build_with_queue` to write data directly into memory of integrated GPUs.
+    /// Enabled by default. Discrete GPUs always use staging upload,
+    /// as do other build methods, which can't prepare the image for host writes.
+    #[must_use]
+    pub fn with_prefer_unified_memory(mut self, prefer: bool) -> Self {
+        self.set_prefer_unified_memory(prefer);
+        self
     }
 
-    pub fn kind(&self) -> Kind {
-        self.kind
+    /// Allow `build_with_queue` to write data directly into memory of integrated GPUs.
+    /// Enabled by default. Discrete GPUs always use staging upload,
+    /// as do other build methods, which can't prepare the image for host writes.
+    pub fn set_prefer_unified_memory(&mut self, prefer: bool) -> &mut Self {
+        self.prefer_unified_memory = prefer;
+        self
     }
-}
 
-fn cast_vec<T>(mut vec: Vec<T>) -> Vec<u8> {
-    use std::mem;
+    /// Generate full mip chain on CPU from the data provided.
+    #[must_use]
+    pub fn with_generate_mips(mut self, generate_mips: bool) -> Self {
+        self.set_generate_mips(generate_mips);
+        self
+    }
 
-    let raw_len = mem::size_of::<T>() * vec.len();
-    let len = raw_len;
+    /// Generate full mip chain on CPU from the data provided.
+    pub fn set_generate_mips(&mut self, generate_mips: bool) -> &mut Self {
+        self.generate_mips = generate_mips;
+        self
+    }
 
-    let cap = mem::size_of::<T>() * vec.capacity();
+    /// Limit number of mip levels including base level.
+    #[must_use]
+    pub fn with_max_levels(mut self, max_levels: u8) -> Self {
+        self.set_max_levels(max_levels);
+        self
+    }
 
-    let ptr = vec.as_mut_ptr();
-    mem::forget(vec);
-    unsafe { Vec::from_raw_parts(ptr as _, len, cap) }
-}
+    /// Limit number of mip levels including base level.
+    pub fn set_max_levels(&mut self, max_levels: u8) -> &mut Self {
+        assert!(max_levels > 0);
+        self.max_levels = Some(max_levels);
+        self
+    }
 
-fn cast_slice<T>(slice: &[T]) -> &[u8] {
-    use std::{mem, slice::from_raw_parts};
+    /// Stop mip chain before width or height gets smaller than `min_extent`.
+    /// Useful for block-compressed data authored down to block size only.
+    #[must_use]
+    pub fn with_min_mip_extent(mut self, min_extent: u32) -> Self {
+        self.set_min_mip_extent(min_extent);
+        self
+    }
+
+    /// Stop mip chain before width or height gets smaller than `min_extent`.
+    /// Useful for block-compressed data authored down to block size only.
+    pub fn set_min_mip_extent(&mut self, min_extent: u32) -> &mut Self {
+        assert!(min_extent > 0);
+        self.min_mip_extent = Some(min_extent);
+        self
+    }
 
-    let raw_len = mem::size_of::<T>() * slice.len();
-    let len = raw_len;
+    /// Set filter used to downsample data for generated mip levels.
+    #[must_use]
+    pub fn with_mip_filter(mut self, filter: MipFilter) -> Self {
+        self.set_mip_filter(filter);
+        self
+    }
 
-    let ptr = slice.as_ptr();
-    mem::forget(slice);
-    unsafe { from_raw_parts(ptr as _, len) }
-}
+    /// Set filter used to downsample data for generated mip levels.
+    pub fn set_mip_filter(&mut self, filter: MipFilter) -> &mut Self {
+        self.mip_filter = filter;
+        self
+    }
 
-fn cast_cow<T>(cow: Cow<[T]>) -> Cow<[u8]>
-where
-    T: Clone,
-{
-    match cow {
-        Cow::Borrowed(slice) => Cow::Borrowed(cast_slice(slice)),
-        Cow::Owned(vec) => Cow::Owned(cast_vec(vec)),
+    /// Renormalize vectors in generated mip levels.
+    /// Use for normal maps encoded as `n * 0.5 + 0.5` in RGB channels.
+    #[must_use]
+    pub fn with_mip_renormalize(mut self, renormalize: bool) -> Self {
+        self.set_mip_renormalize(renormalize);
+        self
+    }
+
+    /// Renormalize vectors in generated mip levels.
+    /// Use for normal maps encoded as `n * 0.5 + 0.5` in RGB channels.
+    pub fn set_mip_renormalize(&mut self, renormalize: bool) -> &mut Self {
+        self.mip_renormalize = renormalize;
+        self
+    }
+
+    /// Filter color channels of generated mip levels in linear space.
+    /// Defaults to `true` for sRGB formats and `false` otherwise.
+    #[must_use]
+    pub fn with_mip_gamma_correct(mut self, gamma_correct: bool) -> Self {
+        self.set_mip_gamma_correct(gamma_correct);
+        self
+    }
+
+    /// Filter color channels of generated mip levels in linear space.
+    /// Defaults to `true` for sRGB formats and `false` otherwise.
+    pub fn set_mip_gamma_correct(&mut self, gamma_correct: bool) -> &mut Self {
+        self.mip_gamma_correct = Some(gamma_correct);
+        self
+    }
+
+    /// Preserve alpha-test coverage in generated mip levels.
+    /// Alpha of each level is scaled so that fraction of texels passing `threshold`
+    /// matches base level. Format must have alpha channel.
+    #[must_use]
+    pub fn with_alpha_coverage(mut self, threshold: f32) -> Self {
+        self.set_alpha_coverage(threshold);
+        self
+    }
+
+    /// Preserve alpha-test coverage in generated mip levels.
+    /// Alpha of each level is scaled so that fraction of texels passing `threshold`
+    /// matches base level. Format must have alpha channel.
+    pub fn set_alpha_coverage(&mut self, threshold: f32) -> &mut Self {
+        assert!(threshold > 0.0 && threshold <= 1.0);
+        self.alpha_coverage = Some(threshold);
+        self
+    }
+
+    /// Set profile used to decode ASTC compressed data.
+    /// Ignored for other formats.
+    #[must_use]
+    pub fn with_astc_profile(mut self, profile: AstcProfile) -> Self {
+        self.set_astc_profile(profile);
+        self
+    }
+
+    /// Set profile used to decode ASTC compressed data.
+    /// Ignored for other formats.
+    pub fn set_astc_profile(&mut self, profile: AstcProfile) -> &mut Self {
+        self.astc_profile = profile;
+        self
+    }
+
+    /// Upload only levels starting from `level`.
+    /// Data for levels below it is ignored and may be left empty.
+    /// Those levels can be streamed later with `Texture::upload_level`.
+    #[must_use]
+    pub fn with_min_resident_level(mut self, level: Level) -> Self {
+        self.set_min_resident_level(level);
+        self
+    }
+
+    /// Upload only levels starting from `level`.
+    /// Data for levels below it is ignored and may be left empty.
+    /// Those levels can be streamed later with `Texture::upload_level`.
+    pub fn set_min_resident_level(&mut self, level: Level) -> &mut Self {
+        self.min_resident_level = level;
+        self
+    }
+
+    /// Keep copy of this builder in built texture to allow `Texture::recreate`.
+    /// Retained builder owns copy of all level data,
+    /// so texture keeps as much host memory as the source data occupies.
+    #[must_use]
+    pub fn with_retain_source(mut self, retain: bool) -> Self {
+        self.set_retain_source(retain);
+        self
+    }
+
+    /// Keep copy of this builder in built texture to allow `Texture::recreate`.
+    /// Retained builder owns copy of all level data,
+    /// so texture keeps as much host memory as the source data occupies.
+    pub fn set_retain_source(&mut self, retain: bool) -> &mut Self {
+        self.retain_source = retain;
+        self
+    }
+
+    /// Additional usage flags the image is created with.
+    /// `TRANSFER_SRC` allows texture to be source of `Texture::copy_from`.
+    #[must_use]
+    pub fn with_usage(mut self, usage: Usage) -> Self {
+        self.set_usage(usage);
+        self
+    }
+
+    /// Additional usage flags the image is created with.
+    /// `TRANSFER_SRC` allows texture to be source of `Texture::copy_from`.
+    pub fn set_usage(&mut self, usage: Usage) -> &mut Self {
+        self.usage = usage;
+        self
+    }
+
+    /// Additional flags the image is created with.
+    /// They are combined with flags the builder derives from texture parameters.
+    #[must_use]
+    pub fn with_storage_flags(mut self, flags: StorageFlags) -> Self {
+        self.set_storage_flags(flags);
+        self
+    }
+
+    /// Additional flags the image is created with.
+    /// They are combined with flags the builder derives from texture parameters.
+    pub fn set_storage_flags(&mut self, flags: StorageFlags) -> &mut Self {
+        self.storage_flags = flags;
+        self
+    }
+
+    /// Byte order of multi-byte channels in provided data.
+    /// Data is converted to native order on upload when they differ.
+    /// 8-bit channels are never swapped.
+    /// Formats which byte order can't be converted, such as compressed ones,
+    /// fail validation unless data is in native order.
+    #[must_use]
+    pub fn with_source_endianness(mut self, endianness: Endianness) -> Self {
+        self.set_source_endianness(endianness);
+        self
+    }
+
+    /// Byte order of multi-byte channels in provided data.
+    /// Data is converted to native order on upload when they differ.
+    /// 8-bit channels are never swapped.
+    /// Formats which byte order can't be converted, such as compressed ones,
+    /// fail validation unless data is in native order.
+    pub fn set_source_endianness(&mut self, endianness: Endianness) -> &mut Self {
+        self.source_endianness = endianness;
+        self
+    }
+
+    /// Swizzle of the texture view.
+    /// See `SwizzlePresets` for common ones.
+    #[must_use]
+    pub fn with_swizzle(mut self, swizzle: Swizzle) -> Self {
+        self.set_swizzle(swizzle);
+        self
+    }
+
+    /// Swizzle of the texture view.
+    /// See `SwizzlePresets` for common ones.
+    pub fn set_swizzle(&mut self, swizzle: Swizzle) -> &mut Self {
+        self.swizzle = swizzle;
+        self
+    }
+
+    /// Set layout texture is left in after upload.
+    /// Defaults to `Layout::DepthStencilReadOnlyOptimal` for depth formats
+    /// and `Layout::ShaderReadOnlyOptimal` otherwise.
+    #[must_use]
+    pub fn with_final_layout(mut self, layout: Layout) -> Self {
+        self.set_final_layout(layout);
+        self
+    }
+
+    /// Set layout texture is left in after upload.
+    /// Defaults to `Layout::DepthStencilReadOnlyOptimal` for depth formats
+    /// and `Layout::ShaderReadOnlyOptimal` otherwise.
+    pub fn set_final_layout(&mut self, layout: Layout) -> &mut Self {
+        self.final_layout = Some(layout);
+        self
+    }
+
+    /// Replace NaN with zero and infinities with largest finite values
+    /// in data of floating point formats.
+    /// Disabled by default, non-finite values are uploaded untouched.
+    #[must_use]
+    pub fn with_sanitize_non_finite(mut self, sanitize: bool) -> Self {
+        self.set_sanitize_non_finite(sanitize);
+        self
+    }
+
+    /// Replace NaN with zero and infinities with largest finite values
+    /// in data of floating point formats.
+    /// Disabled by default, non-finite values are uploaded untouched.
+    pub fn set_sanitize_non_finite(&mut self, sanitize: bool) -> &mut Self {
+        self.sanitize_non_finite = sanitize;
+        self
+    }
+
+    /// Sampler state the texture is intended to be used with.
+    /// Builder only stores it, `Texture::sampling` returns it back.
+    #[must_use]
+    pub fn with_sampling(mut self, sampling: TextureSampling) -> Self {
+        self.set_sampling(sampling);
+        self
+    }
+
+    /// Sampler state the texture is intended to be used with.
+    /// Builder only stores it, `Texture::sampling` returns it back.
+    pub fn set_sampling(&mut self, sampling: TextureSampling) -> &mut Self {
+        self.sampling = Some(sampling);
+        self
+    }
+
+    /// Level of detail clamp and bias the texture is intended to be sampled with.
+    /// Builder only stores it, `Texture::lod_clamp` returns it back.
+    #[must_use]
+    pub fn with_lod_clamp(mut self, lod_clamp: LodClamp) -> Self {
+        self.set_lod_clamp(lod_clamp);
+        self
+    }
+
+    /// Level of detail clamp and bias the texture is intended to be sampled with.
+    /// Builder only stores it, `Texture::lod_clamp` returns it back.
+    pub fn set_lod_clamp(&mut self, lod_clamp: LodClamp) -> &mut Self {
+        self.lod_clamp = Some(lod_clamp);
+        self
+    }
+
+    /// Reject wrapping sampling of non-power-of-two textures.
+    /// Backend can't report this limitation so it must be set by the user
+    /// when targeting such backends.
+    #[must_use]
+    pub fn with_pot_wrap_required(mut self, required: bool) -> Self {
+        self.set_pot_wrap_required(required);
+        self
+    }
+
+    /// Reject wrapping sampling of non-power-of-two textures.
+    /// Backend can't report this limitation so it must be set by the user
+    /// when targeting such backends.
+    pub fn set_pot_wrap_required(&mut self, required: bool) -> &mut Self {
+        self.pot_wrap_required = required;
+        self
+    }
+
+    /// Make texels which color is within `tolerance` of `color` transparent.
+    /// Distance is checked per channel. Only 8-bit RGBA and BGRA formats are supported.
+    #[must_use]
+    pub fn with_chroma_key(mut self, color: [u8; 3], tolerance: u8) -> Self {
+        self.set_chroma_key(color, tolerance);
+        self
+    }
+
+    /// Make texels which color is within `tolerance` of `color` transparent.
+    /// Distance is checked per channel. Only 8-bit RGBA and BGRA formats are supported.
+    pub fn set_chroma_key(&mut self, color: [u8; 3], tolerance: u8) -> &mut Self {
+        self.chroma_key = Some((color, tolerance));
+        self
+    }
+
+    /// Replace color of chroma keyed texels with average of opaque neighbours
+    /// to avoid halos under bilinear filtering.
+    #[must_use]
+    pub fn with_chroma_bleed(mut self, bleed: bool) -> Self {
+        self.set_chroma_bleed(bleed);
+        self
+    }
+
+    /// Replace color of chroma keyed texels with average of opaque neighbours
+    /// to avoid halos under bilinear filtering.
+    pub fn set_chroma_bleed(&mut self, bleed: bool) -> &mut Self {
+        self.chroma_bleed = bleed;
+        self
+    }
+
+    /// Format of provided data when it differs from texture format.
+    /// Data is converted on build. Supported conversions are from 8-bit RGBA, BGRA
+    /// and 32-bit float RGBA formats into 16-bit packed 565, 5551, 1555 and 4444 formats,
+    /// and between 8-bit RGBA and BGRA formats with same channel type,
+    /// e.g. `Rgba8Srgb` data for swapchain-compatible `Bgra8Srgb` texture.
+    #[must_use]
+    pub fn with_source_format(mut self, format: Format) -> Self {
+        self.set_source_format(format);
+        self
+    }
+
+    /// Format of provided data when it differs from texture format.
+    /// Data is converted on build. Supported conversions are from 8-bit RGBA, BGRA
+    /// and 32-bit float RGBA formats into 16-bit packed 565, 5551, 1555 and 4444 formats,
+    /// and between 8-bit RGBA and BGRA formats with same channel type,
+    /// e.g. `Rgba8Srgb` data for swapchain-compatible `Bgra8Srgb` texture.
+    pub fn set_source_format(&mut self, format: Format) -> &mut Self {
+        self.source_format = Some(format);
+        self
+    }
+
+    /// Dithering applied when data is converted into format with less precision.
+    #[must_use]
+    pub fn with_dither(mut self, dither: DitherMode) -> Self {
+        self.set_dither(dither);
+        self
+    }
+
+    /// Dithering applied when data is converted into format with less precision.
+    pub fn set_dither(&mut self, dither: DitherMode) -> &mut Self {
+        self.dither = dither;
+        self
+    }
+
+    /// Fill levels without provided data with color instead of leaving them undefined.
+    /// This covers levels below `min_resident_level` and, when `max_levels` is set,
+    /// levels past provided mip data.
+    /// Color is in linear space and encoded the same way device clears images.
+    #[must_use]
+    pub fn with_clear_unspecified_levels(mut self, color: [f32; 4]) -> Self {
+        self.set_clear_unspecified_levels(color);
+        self
+    }
+
+    /// Fill levels without provided data with color instead of leaving them undefined.
+    /// This covers levels below `min_resident_level` and, when `max_levels` is set,
+    /// levels past provided mip data.
+    /// Color is in linear space and encoded the same way device clears images.
+    pub fn set_clear_unspecified_levels(&mut self, color: [f32; 4]) -> &mut Self {
+        self.clear_unspecified_levels = Some(color);
+        self
+    }
+
+    /// Cross-fade each edge of base level with the opposite one over `blend_width` texels
+    /// so that the texture tiles without seams. Opposite border texels become equal.
+    /// Blending is done in linear space for sRGB formats.
+    #[must_use]
+    pub fn with_make_tileable(mut self, blend_width: u32) -> Self {
+        self.set_make_tileable(blend_width);
+        self
+    }
+
+    /// Cross-fade each edge of base level with the opposite one over `blend_width` texels
+    /// so that the texture tiles without seams. Opposite border texels become equal.
+    /// Blending is done in linear space for sRGB formats.
+    pub fn set_make_tileable(&mut self, blend_width: u32) -> &mut Self {
+        self.tileable_blend = Some(blend_width);
+        self
+    }
+
+    /// Surround base level with border `width` texels wide, growing the extent accordingly.
+    /// Border is added after data is converted to native byte order, sanitized, chroma keyed
+    /// and made tileable, and before conversion to texture format and mip generation.
+    /// 1D textures get border only at the ends. Explicit mip data can't be used with border.
+    /// Use `content_rect` to find where provided data ends up.
+    #[must_use]
+    pub fn with_border(mut self, width: u32, mode: BorderMode) -> Self {
+        self.set_border(width, mode);
+        self
+    }
+
+    /// Surround base level with border `width` texels wide, growing the extent accordingly.
+    /// Border is added after data is converted to native byte order, sanitized, chroma keyed
+    /// and made tileable, and before conversion to texture format and mip generation.
+    /// 1D textures get border only at the ends. Explicit mip data can't be used with border.
+    /// Use `content_rect` to find where provided data ends up.
+    pub fn set_border(&mut self, width: u32, mode: BorderMode) -> &mut Self {
+        self.border = Some((width, mode));
+        self
+    }
+
+    /// Compress data into block compressed format on build.
+    /// Compression runs on final data of each level, after conversion and mip generation,
+    /// and texture gets format reported by `BlockCompression::format`.
+    #[cfg(feature = "compress-bc")]
+    #[must_use]
+    pub fn with_compress(
+        mut self,
+        compression: BlockCompression,
+        quality: CompressionQuality,
+    ) -> Self {
+        self.set_compress(compression, quality);
+        self
+    }
+
+    /// Compress data into block compressed format on build.
+    /// Compression runs on final data of each level, after conversion and mip generation,
+    /// and texture gets format reported by `BlockCompression::format`.
+    #[cfg(feature = "compress-bc")]
+    pub fn set_compress(
+        &mut self,
+        compression: BlockCompression,
+        quality: CompressionQuality,
+    ) -> &mut Self {
+        self.compress = Some((compression, quality));
+        self
+    }
+
+    /// Compress data into best block compressed format for the hint
+    /// device supports on build, or leave it uncompressed if there is none.
+    /// Chosen format is logged and reported by `Texture::format`.
+    /// Explicit `with_compress` takes precedence.
+    #[cfg(feature = "compress-bc")]
+    #[must_use]
+    pub fn with_auto_compression(mut self, hint: CompressionHint) -> Self {
+        self.set_auto_compression(hint);
+        self
+    }
+
+    /// Compress data into best block compressed format for the hint
+    /// device supports on build, or leave it uncompressed if there is none.
+    /// Chosen format is logged and reported by `Texture::format`.
+    /// Explicit `set_compress` takes precedence.
+    #[cfg(feature = "compress-bc")]
+    pub fn set_auto_compression(&mut self, hint: CompressionHint) -> &mut Self {
+        self.auto_compression = Some(hint);
+        self
+    }
+
+    /// Allow data for combined depth-stencil formats to be uploaded into depth aspect only.
+    /// Stencil aspect is left undefined.
+    /// Without it providing data for such formats fails with
+    /// `TextureError::CombinedAspectUploadUnsupported`.
+    #[must_use]
+    pub fn with_per_aspect_upload(mut self, per_aspect: bool) -> Self {
+        self.set_per_aspect_upload(per_aspect);
+        self
+    }
+
+    /// Allow data for combined depth-stencil formats to be uploaded into depth aspect only.
+    /// Stencil aspect is left undefined.
+    /// Without it providing data for such formats fails with
+    /// `TextureError::CombinedAspectUploadUnsupported`.
+    pub fn set_per_aspect_upload(&mut self, per_aspect: bool) -> &mut Self {
+        self.per_aspect_upload = per_aspect;
+        self
+    }
+
+    /// Ignore base level data past the last row and slice of the image,
+    /// e.g. trailing padding of files.
+    /// Without it such data fails validation with `TextureError::DataSize`.
+    #[must_use]
+    pub fn with_remaining_bytes_ignored(mut self, ignored: bool) -> Self {
+        self.set_remaining_bytes_ignored(ignored);
+        self
+    }
+
+    /// Ignore base level data past the last row and slice of the image,
+    /// e.g. trailing padding of files.
+    /// Without it such data fails validation with `TextureError::DataSize`.
+    pub fn set_remaining_bytes_ignored(&mut self, ignored: bool) -> &mut Self {
+        self.remaining_bytes_ignored = ignored;
+        self
+    }
+
+    /// Set name reported in errors about the texture.
+    #[must_use]
+    pub fn with_name<N: Into<String>>(mut self, name: N) -> Self {
+        self.set_name(name);
+        self
+    }
+
+    /// Set name reported in errors about the texture.
+    pub fn set_name<N: Into<String>>(&mut self, name: N) -> &mut Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Set what `build` does when device runs out of memory for the image.
+    #[must_use]
+    pub fn with_oom_policy(mut self, policy: OomPolicy) -> Self {
+        self.set_oom_policy(policy);
+        self
+    }
+
+    /// Set what `build` does when device runs out of memory for the image.
+    pub fn set_oom_policy(&mut self, policy: OomPolicy) -> &mut Self {
+        self.oom_policy = policy;
+        self
+    }
+
+    /// Set rotation composition code should apply to the texture,
+    /// e.g. to match swapchain pre-transform on mobile.
+    /// Builder only stores it, `Texture::pre_transform` returns it back.
+    #[must_use]
+    pub fn with_pre_transform(mut self, pre_transform: PreTransform) -> Self {
+        self.set_pre_transform(pre_transform);
+        self
+    }
+
+    /// Set rotation composition code should apply to the texture,
+    /// e.g. to match swapchain pre-transform on mobile.
+    /// Builder only stores it, `Texture::pre_transform` returns it back.
+    pub fn set_pre_transform(&mut self, pre_transform: PreTransform) -> &mut Self {
+        self.pre_transform = pre_transform;
+        self
+    }
+
+    /// Charge estimated size of built textures to the budget until they are disposed.
+    #[must_use]
+    pub fn with_budget(mut self, budget: &Arc<TextureBudget>) -> Self {
+        self.set_budget(budget);
+        self
+    }
+
+    /// Charge estimated size of built textures to the budget until they are disposed.
+    pub fn set_budget(&mut self, budget: &Arc<TextureBudget>) -> &mut Self {
+        self.budget = Some(budget.clone());
+        self
+    }
+
+    /// Region of the base level covered by provided data.
+    /// Whole base level unless border is added.
+    pub fn content_rect(&self) -> (Offset, Extent) {
+        let (x, y) = self.border_size();
+        let offset = Offset {
+            x: x as i32,
+            y: y as i32,
+            z: 0,
+        };
+        (offset, self.kind.extent())
+    }
+
+    /// Configure format, mip generation and swizzle for texture semantics.
+    /// Settings can be overridden afterwards.
+    #[must_use]
+    pub fn with_preset(mut self, preset: TexturePreset) -> Self {
+        self.set_preset(preset);
+        self
+    }
+
+    /// Configure format, mip generation and swizzle for texture semantics.
+    /// Settings can be overridden afterwards.
+    pub fn set_preset(&mut self, preset: TexturePreset) -> &mut Self {
+        self.generate_mips = true;
+        match preset {
+            TexturePreset::Albedo => {
+                self.format = Format::Rgba8Srgb;
+                self.mip_renormalize = false;
+                self.mip_gamma_correct = Some(true);
+                self.swizzle = Swizzle::NO;
+            }
+            TexturePreset::NormalMap => {
+                self.format = Format::Rgba8Unorm;
+                self.mip_renormalize = true;
+                self.mip_gamma_correct = Some(false);
+                self.swizzle = Swizzle::NO;
+            }
+            TexturePreset::MetallicRoughness => {
+                self.format = Format::Rgba8Unorm;
+                self.mip_renormalize = false;
+                self.mip_gamma_correct = Some(false);
+                self.swizzle = Swizzle::NO;
+            }
+            TexturePreset::Occlusion => {
+                self.format = Format::Rgba8Unorm;
+                self.mip_renormalize = false;
+                self.mip_gamma_correct = Some(false);
+                self.swizzle = Swizzle::rrr1();
+            }
+        }
+        self
+    }
+
+    /// Check that opposite edges of base level differ by no more than `tolerance`
+    /// in any normalized channel.
+    pub fn is_tileable(&self, tolerance: f32) -> Result<bool, TextureError> {
+        let (channels, texels) = self.decode_base()?;
+        let extent = self.kind.extent();
+        let (width, height) = (extent.width as usize, extent.height as usize);
+        let texel = |slice: usize, x: usize, y: usize| {
+            let index = ((slice * height + y) * width + x) * channels;
+            &texels[index..index + channels]
+        };
+        let matches =
+            |a: &[f32], b: &[f32]| a.iter().zip(b).all(|(a, b)| (a - b).abs() <= tolerance);
+
+        let slices = texels.len() / (width * height * channels);
+        for slice in 0..slices {
+            for y in 0..height {
+                if !matches(texel(slice, 0, y), texel(slice, width - 1, y)) {
+                    return Ok(false);
+                }
+            }
+            for x in 0..width {
+                if !matches(texel(slice, x, 0), texel(slice, x, height - 1)) {
+                    return Ok(false);
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    /// Check that data provided for each level matches its extent and format.
+    /// Base level without data, e.g. of render target or transient attachment,
+    /// is left undefined unless unspecified levels are cleared.
+    pub fn validate(&self) -> Result<(), TextureError> {
+        let extent = self.kind.extent();
+        let levels = self.levels();
+        let max_levels = self.max_levels();
+        if levels > max_levels {
+            Err(TextureError::TooManyLevels {
+                levels,
+                max: max_levels,
+            })?;
+        }
+
+        if self.clear_unspecified_levels.is_some() && !mip::is_supported(self.format) {
+            Err(TextureError::ClearUnsupportedFormat {
+                format: self.format,
+            })?;
+        }
+
+        if self.generate_mips && !self.mip_data.is_empty() {
+            Err(TextureError::ConflictingMipData)?;
+        }
+
+        if self.transient && (!self.data.is_empty() || !self.mip_data.is_empty()) {
+            Err(TextureError::TransientWithData)?;
+        }
+
+        if self.format.surface_desc().aspects == Aspects::DEPTH | Aspects::STENCIL
+            && !self.per_aspect_upload
+            && (!self.data.is_empty() || !self.mip_data.is_empty())
+        {
+            Err(TextureError::CombinedAspectUploadUnsupported {
+                format: self.format,
+            })?;
+        }
+
+        let data_format = self.data_format();
+        if data_format != self.format && self.conversion().is_none() {
+            Err(TextureError::ConversionUnsupported {
+                src: data_format,
+                dst: self.format,
+            })?;
+        }
+
+        format::check_endianness(data_format, self.source_endianness)?;
+
+        if let Some((_, mode)) = self.border {
+            let supported = data_format.surface_desc().dim == (1, 1)
+                && match mode {
+                    BorderMode::Solid(color) => convert::solid_texel(data_format, color).is_some(),
+                    BorderMode::ClampEdge => true,
+                };
+            if !supported {
+                Err(TextureError::BorderUnsupportedFormat {
+                    format: data_format,
+                })?;
+            }
+            if !self.mip_data.is_empty() {
+                Err(TextureError::BorderWithMipData)?;
+            }
+        }
+
+        if self.tileable_blend.is_some() && !mip::is_supported(data_format) {
+            Err(TextureError::DecodeUnsupportedFormat {
+                format: data_format,
+            })?;
+        }
+
+        if self.chroma_key.is_some() {
+            match data_format.base_format() {
+                (SurfaceType::R8_G8_B8_A8, _) | (SurfaceType::B8_G8_R8_A8, _) => {}
+                _ => Err(TextureError::ChromaKeyUnsupportedFormat {
+                    format: data_format,
+                })?,
+            }
+        }
+
+        if self.pot_wrap_required
+            && self.sampling.is_some_and(|sampling| sampling.wraps())
+            && ![extent.width, extent.height, extent.depth]
+                .iter()
+                .all(|size| size.is_power_of_two())
+        {
+            Err(TextureError::NonPowerOfTwoWrap { extent })?;
+        }
+
+        if let Some(lod_clamp) = self.lod_clamp {
+            let ordering = lod_clamp.min_lod.partial_cmp(&lod_clamp.max_lod);
+            if matches!(ordering, None | Some(Ordering::Greater)) {
+                Err(TextureError::InvalidLodClamp {
+                    min_lod: lod_clamp.min_lod,
+                    max_lod: lod_clamp.max_lod,
+                })?;
+            }
+        }
+
+        let flags = self.storage_flags();
+        if !storage_flags_fit(flags, self.kind) {
+            Err(TextureError::StorageFlagsConflict {
+                flags,
+                kind: self.kind,
+            })?;
+        }
+
+        if self.min_resident_level >= levels {
+            Err(TextureError::TooManyLevels {
+                levels: self.min_resident_level + 1,
+                max: levels,
+            })?;
+        }
+
+        if self.generate_mips || (self.min_resident_level == 0 && !self.data.is_empty()) {
+            self.check_base_size()?;
+        }
+
+        for (index, data) in self.mip_data.iter().enumerate() {
+            let level = index as u8 + 1;
+            if level >= self.min_resident_level {
+                check_level_size(data_format, self.kind, level, data)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Compute statistics of base level data.
+    /// Alpha coverage is counted for texels with alpha not less than `alpha_threshold`.
+    pub fn statistics(&self, alpha_threshold: f32) -> Result<TextureStats, TextureError> {
+        let (channels, texels) = self.decode_base()?;
+        Ok(stats::collect(
+            self.data_format(),
+            channels,
+            &texels,
+            alpha_threshold,
+        ))
+    }
+
+    /// Compare base level data with data of another builder.
+    /// Row and slice strides of both builders are taken into account.
+    pub fn compare(
+        &self,
+        other: &TextureBuilder,
+        tolerance: CompareTolerance,
+    ) -> Result<CompareResult, TextureError> {
+        let format = self.data_format();
+        if format != other.data_format() {
+            Err(TextureError::IncompatibleFormats {
+                src: format,
+                dst: other.data_format(),
+            })?;
+        }
+        let extent = self.kind.extent();
+        if extent != other.kind.extent() {
+            Err(TextureError::ExtentMismatch {
+                src: extent,
+                dst: other.kind.extent(),
+            })?;
+        }
+        let layers = self.kind.num_layers();
+        if layers != other.kind.num_layers() {
+            Err(TextureError::LayerCount {
+                level: 0,
+                expected: layers,
+                actual: other.kind.num_layers(),
+            })?;
+        }
+
+        let (channels, a) = self.decode_base()?;
+        let (_, b) = other.decode_base()?;
+        Ok(compare::compare_texels(
+            channels,
+            extent.width,
+            extent.height,
+            &a,
+            &b,
+            tolerance,
+        ))
+    }
+
+    /// Find aspects that differ from `other` builder.
+    /// Lets reload code choose between re-uploading data into existing texture and full rebuild.
+    pub fn diff(&self, other: &TextureBuilder) -> BuilderDiff {
+        BuilderDiff::new(self, other)
+    }
+
+    /// Parameters `build` will use with all defaults resolved.
+    pub fn resolved(&self) -> ResolvedTextureParams {
+        let padded = self.padded_params();
+        let image = padded.as_ref().unwrap_or(self);
+        ResolvedTextureParams {
+            kind: image.kind,
+            format: self.format,
+            tiling: Tiling::Optimal,
+            usage: self.image_usage(),
+            properties: self.image_properties(),
+            storage_flags: self.storage_flags(),
+            prefer_unified_memory: self.prefer_unified_memory,
+            levels: image.levels(),
+            layers: self.kind.num_layers(),
+            data_width: self.data_width(),
+            data_height: self.data_height(),
+            min_resident_level: self.min_resident_level,
+            generate_mips: self.generate_mips,
+            mip_filter: self.mip_filter,
+            mip_renormalize: self.mip_renormalize,
+            mip_gamma_correct: self.mip_gamma_correct(),
+            alpha_coverage: self.alpha_coverage,
+            astc_profile: self.astc_profile,
+            source_endianness: self.source_endianness,
+            swizzle: self.swizzle,
+            sanitize_non_finite: self.sanitize_non_finite,
+            sampling: self.sampling,
+            lod_clamp: self.lod_clamp,
+            pot_wrap_required: self.pot_wrap_required,
+            chroma_key: self.chroma_key,
+            chroma_bleed: self.chroma_bleed,
+            source_format: self.data_format(),
+            dither: self.dither,
+            clear_unspecified_levels: self.clear_unspecified_levels,
+            tileable_blend: self.tileable_blend,
+            border: self.border,
+            #[cfg(feature = "compress-bc")]
+            compress: self.compress,
+            #[cfg(feature = "compress-bc")]
+            auto_compression: self.auto_compression,
+            oom_policy: self.oom_policy,
+            pre_transform: self.pre_transform,
+            final_layout: self.final_layout(),
+            per_aspect_upload: self.per_aspect_upload,
+            remaining_bytes_ignored: self.remaining_bytes_ignored,
+        }
+    }
+
+    /// Build texture and filling it with data provided.
+    /// Data is read directly from the slice set by `set_data` without intermediate copies.
+    /// Factory is borrowed mutably, so builds from several threads must be serialized
+    /// by the caller, e.g. with a mutex around the factory.
+    /// Texture may be sent to other threads once built.
+    /// Image is always created with exclusive sharing as gfx-hal has no concurrent sharing mode,
+    /// so it can't be shared by several queue families to skip ownership transfers.
+    /// Data is always uploaded through staging buffer,
+    /// see `build_with_queue` to write it directly into unified memory.
+    pub fn build<B>(
+        &self,
+        family: QueueFamilyId,
+        factory: &mut Factory<B>,
+    ) -> Result<Texture<B>, TextureError>
+    where
+        B: Backend,
+    {
+        self.build_with_stats(family, factory)
+            .map(|(texture, _)| texture)
+    }
+
+    /// Build texture and report amount of data uploaded.
+    pub fn build_with_stats<B>(
+        &self,
+        family: QueueFamilyId,
+        factory: &mut Factory<B>,
+    ) -> Result<(Texture<B>, UploadStats), TextureError>
+    where
+        B: Backend,
+    {
+        self.build_with_eviction(family, factory, |_, _| {})
+    }
+
+    /// Build texture handling out of memory error according to `OomPolicy`.
+    /// With `OomPolicy::Retry` the `evict` callback is called once with the error
+    /// so caches can release memory before the allocation is retried.
+    pub fn build_with_eviction<B, F>(
+        &self,
+        family: QueueFamilyId,
+        factory: &mut Factory<B>,
+        evict: F,
+    ) -> Result<(Texture<B>, UploadStats), TextureError>
+    where
+        B: Backend,
+        F: FnOnce(&mut Factory<B>, &TextureError),
+    {
+        self.apply_oom_policy(
+            factory,
+            |factory, tail| match tail {
+                None => self.build_once(family, factory),
+                Some(tail) => {
+                    let (mut texture, stats) = tail.build_once(family, factory)?;
+                    texture.source = self.retained_source();
+                    Ok((texture, stats))
+                }
+            },
+            evict,
+        )
+    }
+
+    /// Call `build` for this builder handling out of memory error according to `OomPolicy`.
+    /// `build` receives the mip tail builder when falling back to `OomPolicy::MipTail`.
+    fn apply_oom_policy<C, T, F, E>(
+        &self,
+        context: &mut C,
+        mut build: F,
+        evict: E,
+    ) -> Result<T, TextureError>
+    where
+        F: FnMut(&mut C, Option<&TextureBuilder<'_>>) -> Result<T, TextureError>,
+        E: FnOnce(&mut C, &TextureError),
+    {
+        let error = match build(context, None) {
+            Err(error @ TextureError::OutOfMemory { .. }) => error,
+            result => return result,
+        };
+        match self.oom_policy {
+            OomPolicy::Fail => Err(error),
+            OomPolicy::Retry => {
+                evict(context, &error);
+                build(context, None)
+            }
+            OomPolicy::MipTail(max_extent) => {
+                let padded = self.padded()?;
+                match padded.as_ref().unwrap_or(self).mip_tail(max_extent)? {
+                    Some(tail) => build(context, Some(&tail)),
+                    None => Err(error),
+                }
+            }
+        }
+    }
+
+    /// Build texture without applying `OomPolicy`.
+    fn build_once<B>(
+        &self,
+        family: QueueFamilyId,
+        factory: &mut Factory<B>,
+    ) -> Result<(Texture<B>, UploadStats), TextureError>
+    where
+        B: Backend,
+    {
+        let extent = self.kind.extent();
+        assert!(self.data_width() >= extent.width);
+        self.validate()?;
+
+        if self.transient {
+            return Ok((self.build_transient(factory)?, UploadStats::default()));
+        }
+
+        if let Some(padded) = self.padded()? {
+            let (mut texture, stats) = padded.build_once(family, factory)?;
+            texture.source = self.retained_source();
+            return Ok((texture, stats));
+        }
+
+        if let Some(compressed) = self.compressed::<B>(factory.physical())? {
+            let (mut texture, stats) = compressed.build_once(family, factory)?;
+            texture.source = self.retained_source();
+            return Ok((texture, stats));
+        }
+
+        format::check_astc_profile::<B>(factory.physical(), self.format, self.astc_profile)?;
+        self.check_mip_generation(factory)?;
+        let charge = self.charge(self.levels())?;
+
+        let levels = self.levels();
+
+        let mut image = factory
+            .create_image(
+                self.kind,
+                levels,
+                self.format,
+                Tiling::Optimal,
+                self.storage_flags(),
+                Usage::TRANSFER_DST | Usage::SAMPLED | self.usage,
+                Properties::DEVICE_LOCAL,
+            )
+            .map_err(|error| self.allocation_error(levels, error))?;
+
+        let view = self.create_view(factory, &image)?;
+
+        let stats = self.upload(
+            factory,
+            family,
+            &mut image,
+            self.final_layout(),
+            layout_access(self.final_layout()),
+        )?;
+
+        let texture = Texture::assemble(
+            self.staged_parts(levels),
+            image,
+            view,
+            self.retained_source(),
+            charge,
+        );
+        Ok((texture, stats))
+    }
+
+    /// Upload data into existing image and create view for it.
+    /// Image's parameters can't be queried so `params` must be the ones image was created with.
+    /// They are checked against the builder and `TextureError::Incompatible` is returned
+    /// if the image doesn't match the image builder would create.
+    pub fn build_into<B>(
+        &self,
+        mut image: Image<B>,
+        params: ImageParams,
+        family: QueueFamilyId,
+        factory: &mut Factory<B>,
+    ) -> Result<Texture<B>, TextureError>
+    where
+        B: Backend,
+    {
+        assert!(self.data_width() >= self.kind.extent().width);
+        self.validate()?;
+
+        if let Some(padded) = self.padded()? {
+            let mut texture = padded.build_into(image, params, family, factory)?;
+            texture.source = self.retained_source();
+            return Ok(texture);
+        }
+
+        if let Some(compressed) = self.compressed::<B>(factory.physical())? {
+            let mut texture = compressed.build_into(image, params, family, factory)?;
+            texture.source = self.retained_source();
+            return Ok(texture);
+        }
+
+        format::check_astc_profile::<B>(factory.physical(), self.format, self.astc_profile)?;
+        self.check_mip_generation(factory)?;
+
+        self.check_image_params(params)?;
+        let charge = self.charge(params.levels)?;
+        let view = self.create_view(factory, &image)?;
+
+        self.upload(
+            factory,
+            family,
+            &mut image,
+            self.final_layout(),
+            layout_access(self.final_layout()),
+        )?;
+
+        Ok(Texture::assemble(
+            self.parts(self.levels(), params.usage, Tiling::Optimal),
+            image,
+            view,
+            self.retained_source(),
+            charge,
+        ))
+    }
+
+    /// Build texture consuming the builder.
+    /// Borrowed data only needs to live until this call returns.
+    /// Owned data is moved into retained source instead of being copied.
+    pub fn build_owned<B>(
+        mut self,
+        family: QueueFamilyId,
+        factory: &mut Factory<B>,
+    ) -> Result<Texture<B>, TextureError>
+    where
+        B: Backend,
+    {
+        let retain = self.retain_source;
+        self.retain_source = false;
+        let mut texture = self.build(family, factory)?;
+        if retain {
+            self.retain_source = true;
+            texture.source = Some(Box::new(self.into_static()));
+        }
+        Ok(texture)
+    }
+
+    /// Build texture recording upload into command buffer allocated from `pool`
+    /// and submitting it to `queue`, instead of queues used by the factory.
+    /// `pool` must be created for family of the `queue`.
+    /// Blocks until the upload is complete, command buffer is freed back to `pool`.
+    /// Single level textures are written directly into unified memory of integrated GPUs
+    /// when allowed by `set_prefer_unified_memory`.
+    pub fn build_with_queue<B>(
+        &self,
+        factory: &mut Factory<B>,
+        queue: &mut B::CommandQueue,
+        pool: &mut B::CommandPool,
+    ) -> Result<Texture<B>, TextureError>
+    where
+        B: Backend,
+    {
+        assert!(self.data_width() >= self.kind.extent().width);
+        self.validate()?;
+
+        if let Some(padded) = self.padded()? {
+            let mut texture = padded.build_with_queue(factory, queue, pool)?;
+            texture.source = self.retained_source();
+            return Ok(texture);
+        }
+
+        if let Some(compressed) = self.compressed::<B>(factory.physical())? {
+            let mut texture = compressed.build_with_queue(factory, queue, pool)?;
+            texture.source = self.retained_source();
+            return Ok(texture);
+        }
+
+        if self.can_write_directly(factory) {
+            return self
+                .build_unified(factory, queue, pool)
+                .map(|(texture, _)| texture);
+        }
+
+        let (texture, pending) = command::submit_once(factory, queue, pool, |factory, cmd| {
+            self.record_build(factory, cmd)
+        })?;
+        pending.complete(factory);
+        Ok(texture)
+    }
+
+    /// Record texture upload into `cmd` instead of submitting it.
+    /// Texture can be used only after the caller submits `cmd`.
+    /// Staging buffer is returned as `PendingUpload` that must be released
+    /// with `PendingUpload::complete` once the commands are complete.
+    /// All levels are left in layout set by `set_final_layout`,
+    /// levels without data have undefined content.
+    pub fn record_build<B>(
+        &self,
+        factory: &mut Factory<B>,
+        cmd: &mut B::CommandBuffer,
+    ) -> Result<(Texture<B>, PendingUpload<B>), TextureError>
+    where
+        B: Backend,
+    {
+        let extent = self.kind.extent();
+        assert!(self.data_width() >= extent.width);
+        self.validate()?;
+
+        if let Some(padded) = self.padded()? {
+            let (mut texture, pending) = padded.record_build(factory, cmd)?;
+            texture.source = self.retained_source();
+            return Ok((texture, pending));
+        }
+
+        if let Some(compressed) = self.compressed::<B>(factory.physical())? {
+            let (mut texture, pending) = compressed.record_build(factory, cmd)?;
+            texture.source = self.retained_source();
+            return Ok((texture, pending));
+        }
+
+        format::check_astc_profile::<B>(factory.physical(), self.format, self.astc_profile)?;
+        self.check_mip_generation(factory)?;
+
+        let levels = self.levels();
+        let layers = self.kind.num_layers();
+        let uploads = self.upload_levels()?;
+        let charge = self.charge(levels)?;
+
+        let image = factory
+            .create_image(
+                self.kind,
+                levels,
+                self.format,
+                Tiling::Optimal,
+                self.storage_flags(),
+                Usage::TRANSFER_DST | Usage::SAMPLED | self.usage,
+                Properties::DEVICE_LOCAL,
+            )
+            .map_err(|error| self.allocation_error(levels, error))?;
+
+        let view = self.create_view(factory, &image)?;
+
+        let range = SubresourceRange {
+            aspects: aspects_for(self.format),
+            levels: 0..levels,
+            layers: 0..layers,
+        };
+
+        let staging = if uploads.is_empty() {
+            None
+        } else {
+            let chunks = uploads.iter().map(|upload| &*upload.1).collect::<Vec<_>>();
+            let desc = self.format.surface_desc();
+            let (staging, offsets) =
+                command::create_staging(factory, &chunks, desc.bits as u64 / 8 * 4)?;
+            Some((staging, self.upload_regions(&uploads, &offsets)))
+        };
+
+        for command in self.build_commands(staging.is_some()) {
+            match command {
+                BuildCommand::Barrier(states, stages) => {
+                    command::image_barrier::<B>(cmd, image.borrow(), range.clone(), states, stages)
+                }
+                BuildCommand::Copy => {
+                    let (ref staging, ref regions) = *staging.as_ref().unwrap();
+                    command::copy_buffer_to_image::<B>(cmd, staging, image.borrow(), regions);
+                }
+            }
+        }
+
+        let texture = Texture::assemble(
+            self.staged_parts(levels),
+            image,
+            view,
+            self.retained_source(),
+            charge,
+        );
+        Ok((
+            texture,
+            PendingUpload::new(staging.map(|(staging, _)| staging)),
+        ))
+    }
+
+    /// Commands `record_build` records for the image,
+    /// copying staged data when `copy` is set.
+    /// Image ends in the final layout, made visible to stages that access it there.
+    fn build_commands(&self, copy: bool) -> Vec<BuildCommand> {
+        let undefined = (Access::empty(), Layout::Undefined);
+        let final_state = (layout_access(self.final_layout()), self.final_layout());
+        let final_stages = layout_stages(self.final_layout());
+        if !copy {
+            return vec![BuildCommand::Barrier(
+                undefined..final_state,
+                PipelineStage::TOP_OF_PIPE..final_stages,
+            )];
+        }
+        let transfer_write = (Access::TRANSFER_WRITE, Layout::TransferDstOptimal);
+        vec![
+            BuildCommand::Barrier(
+                undefined..transfer_write,
+                PipelineStage::TOP_OF_PIPE..PipelineStage::TRANSFER,
+            ),
+            BuildCommand::Copy,
+            BuildCommand::Barrier(
+                transfer_write..final_state,
+                PipelineStage::TRANSFER..final_stages,
+            ),
+        ]
+    }
+
+    /// Regions copying staged `uploads` at `offsets` into image levels.
+    fn upload_regions(&self, uploads: &[LevelUpload<'_>], offsets: &[u64]) -> Vec<BufferImageCopy> {
+        let extent = self.kind.extent();
+        uploads
+            .iter()
+            .zip(offsets)
+            .map(
+                |(&(level, _, buffer_width, buffer_height), &buffer_offset)| BufferImageCopy {
+                    buffer_offset,
+                    buffer_width,
+                    buffer_height,
+                    image_layers: SubresourceLayers {
+                        aspects: data_aspects(self.format),
+                        level,
+                        layers: 0..self.kind.num_layers(),
+                    },
+                    image_offset: Offset::ZERO,
+                    image_extent: extent.at_level(level),
+                },
+            )
+            .collect()
+    }
+
+    /// Amount of data staged and copied for `uploads`.
+    fn upload_stats(&self, uploads: &[LevelUpload<'_>]) -> UploadStats {
+        let mut stats = UploadStats::default();
+        for (_, data, _, _) in uploads {
+            stats.bytes_uploaded += data.len() as u64;
+            stats.staging_buffer_size += data.len() as u64;
+            stats.subresources += self.kind.num_layers() as u32;
+        }
+        stats
+    }
+
+    /// Create transient attachment in lazily allocated memory without uploading anything.
+    fn build_transient<B>(&self, factory: &mut Factory<B>) -> Result<Texture<B>, TextureError>
+    where
+        B: Backend,
+    {
+        let lazy = factory
+            .physical()
+            .memory_properties()
+            .memory_types
+            .iter()
+            .any(|ty| ty.properties.contains(Properties::LAZILY_ALLOCATED));
+        if !lazy {
+            Err(TextureError::LazyMemoryUnsupported)?;
+        }
+
+        let usage = self.image_usage();
+        let image = factory
+            .create_image(
+                self.kind,
+                1,
+                self.format,
+                Tiling::Optimal,
+                self.storage_flags(),
+                usage,
+                self.image_properties(),
+            )
+            .map_err(|error| self.allocation_error(1, error))?;
+        let view = self.create_view(factory, &image)?;
+
+        Ok(Texture::assemble(
+            TextureParts {
+                resident: 0,
+                layout: Layout::Undefined,
+                access: Access::empty(),
+                transient: true,
+                ..self.parts(1, usage, Tiling::Optimal)
+            },
+            image,
+            view,
+            self.retained_source(),
+            None,
+        ))
+    }
+
+    /// Upload resident levels into image that is in `layout`.
+    fn upload<B>(
+        &self,
+        factory: &mut Factory<B>,
+        family: QueueFamilyId,
+        image: &mut Image<B>,
+        layout: Layout,
+        access: Access,
+    ) -> Result<UploadStats, TextureError>
+    where
+        B: Backend,
+    {
+        let layers = self.kind.num_layers();
+        let uploads = self.upload_levels()?;
+        let stats = self.upload_stats(&uploads);
+        for (level, data, data_width, data_height) in uploads {
+            factory.upload_image(
+                image,
+                family,
+                layout,
+                access,
+                SubresourceLayers {
+                    aspects: data_aspects(self.format),
+                    level,
+                    layers: 0..layers,
+                },
+                Offset::ZERO,
+                self.kind.extent().at_level(level),
+                data_width,
+                data_height,
+                &data,
+            )?;
+        }
+        Ok(stats)
+    }
+
+    /// Base level data in native byte order.
+    fn base_data(&self) -> Cow<'_, [u8]> {
+        self.convert(self.base_source(), self.data_width(), self.data_height())
+    }
+
+    /// Base level data in source format with all transforms applied.
+    fn base_source(&self) -> Cow<'_, [u8]> {
+        let data = self.prepare_source(&self.data, self.data_width(), self.data_height());
+        match self.tileable_blend {
+            Some(blend) => mip::make_tileable(
+                self.data_format(),
+                self.kind.extent(),
+                self.data_width(),
+                self.data_height(),
+                &data,
+                self.kind.num_layers(),
+                blend,
+            )
+            .map_or(data, Cow::Owned),
+            None => data,
+        }
+    }
+
+    /// Prepare level data and convert it into texture format.
+    /// Data consists of slices of `height` rows with `width` texels.
+    fn prepare<'b>(&self, data: &'b [u8], width: u32, height: u32) -> Cow<'b, [u8]> {
+        self.convert(self.prepare_source(data, width, height), width, height)
+    }
+
+    /// Convert prepared data from source format into texture format.
+    fn convert<'b>(&self, data: Cow<'b, [u8]>, width: u32, height: u32) -> Cow<'b, [u8]> {
+        match self.conversion() {
+            Some(conversion) => {
+                Cow::Owned(conversion.convert(&data, width as usize, height as usize, self.dither))
+            }
+            None => data,
+        }
+    }
+
+    /// Convert level data to native byte order and apply requested transforms.
+    /// Data consists of slices of `height` rows with `width` texels.
+    fn prepare_source<'b>(&self, data: &'b [u8], width: u32, height: u32) -> Cow<'b, [u8]> {
+        let data_format = self.data_format();
+        let mut data = format::to_native(data_format, self.source_endianness, data);
+        if self.sanitize_non_finite {
+            data = format::sanitize_non_finite(data_format, data);
+        }
+        if let Some((color, tolerance)) = self.chroma_key {
+            let key = match data_format.base_format().0 {
+                SurfaceType::B8_G8_R8_A8 => [color[2], color[1], color[0]],
+                _ => color,
+            };
+            convert::chroma_key(
+                data.to_mut(),
+                width as usize,
+                height as usize,
+                key,
+                tolerance,
+                self.chroma_bleed,
+            );
+        }
+        data
+    }
+
+    /// Data for mip levels after the base one. Either generated or provided explicitly.
+    fn mip_levels(&self) -> Result<Vec<Cow<'_, [u8]>>, TextureError> {
+        let extent = self.kind.extent();
+        let levels = self.levels();
+        let layers = self.kind.num_layers();
+        let mips = if self.generate_mips {
+            mip::generate(
+                self.data_format(),
+                extent,
+                self.data_width(),
+                self.data_height(),
+                &self.base_source(),
+                layers,
+                &MipOptions {
+                    levels,
+                    filter: self.mip_filter,
+                    renormalize: self.mip_renormalize,
+                    gamma_correct: self.mip_gamma_correct(),
+                    alpha_coverage: self.alpha_coverage,
+                },
+            )?
+            .into_iter()
+            .enumerate()
+            .map(|(index, data)| {
+                let extent = extent.at_level(index as u8 + 1);
+                self.convert(Cow::Owned(data), extent.width, extent.height)
+            })
+            .collect()
+        } else {
+            self.mip_data
+                .iter()
+                .enumerate()
+                .map(|(index, data)| {
+                    let extent = extent.at_level(index as u8 + 1);
+                    self.prepare(data, extent.width, extent.height)
+                })
+                .collect()
+        };
+        Ok(mips)
+    }
+
+    /// Data of levels to upload with texels between rows and rows between slices.
+    /// Levels without data are filled when `clear_unspecified_levels` is set and skipped otherwise.
+    fn upload_levels(&self) -> Result<Vec<LevelUpload<'_>>, TextureError> {
+        let extent = self.kind.extent();
+        let layers = self.kind.num_layers();
+        let (block_width, block_height) = self.format.surface_desc().dim;
+        let mut mips = self.mip_levels()?.into_iter();
+        let mut uploads = Vec::new();
+        // gfx-hal can't create sparse images, the only ones with packed mip tails,
+        // so every level is its own region.
+        for level in 0..self.levels() {
+            let data = if level == 0 {
+                Some(self.base_data()).filter(|data| !data.is_empty())
+            } else {
+                mips.next()
+            };
+            let level_extent = extent.at_level(level);
+            let data_width = align(level_extent.width, block_width as u32);
+            let data_height = align(level_extent.height, block_height as u32);
+            match data {
+                Some(data) if level >= self.min_resident_level => {
+                    if level == 0 {
+                        let size = format::strided_size(
+                            self.format,
+                            extent,
+                            self.data_width(),
+                            self.data_height(),
+                            layers,
+                        );
+                        let data = match data {
+                            Cow::Borrowed(data) => Cow::Borrowed(&data[..size]),
+                            Cow::Owned(mut data) => {
+                                data.truncate(size);
+                                Cow::Owned(data)
+                            }
+                        };
+                        uploads.push((level, data, self.data_width(), self.data_height()));
+                    } else {
+                        uploads.push((level, data, data_width, data_height));
+                    }
+                }
+                _ => {
+                    if let Some(color) = self.clear_unspecified_levels {
+                        let data = mip::fill(self.format, level_extent, layers, color).ok_or(
+                            TextureError::ClearUnsupportedFormat {
+                                format: self.format,
+                            },
+                        )?;
+                        uploads.push((level, Cow::Owned(data), data_width, data_height));
+                    }
+                }
+            }
+        }
+        Ok(uploads)
+    }
+
+    /// Number of border columns and rows added around base level.
+    fn border_size(&self) -> (u32, u32) {
+        match (self.border, self.kind) {
+            (Some((width, _)), Kind::D1(..)) => (width, 0),
+            (Some((width, _)), _) => (width, width),
+            (None, _) => (0, 0),
+        }
+    }
+
+    /// Parameters of the builder with border added to the kind.
+    fn padded_params(&self) -> Option<TextureBuilder<'static>> {
+        let (border_x, border_y) = self.border_size();
+        if border_x == 0 && border_y == 0 {
+            return None;
+        }
+        let extent = self.kind.extent();
+        let (width, height) = (extent.width + border_x * 2, extent.height + border_y * 2);
+        let mut padded = self.clone_params();
+        padded.kind = match self.kind {
+            Kind::D1(_, layers) => Kind::D1(width, layers),
+            Kind::D2(_, _, layers, samples) => Kind::D2(width, height, layers, samples),
+            Kind::D3(_, _, depth) => Kind::D3(width, height, depth),
+        };
+        padded.data_width = Some(width);
+        padded.data_height = Some(height);
+        padded.border = None;
+        Some(padded)
+    }
+
+    /// Builder with border added to base level data and source transforms already applied.
+    fn padded(&self) -> Result<Option<TextureBuilder<'static>>, TextureError> {
+        let (mut padded, mode) = match (self.padded_params(), self.border) {
+            (Some(padded), Some((_, mode))) => (padded, mode),
+            _ => return Ok(None),
+        };
+        self.check_base_size()?;
+
+        let format = self.data_format();
+        let solid = match mode {
+            BorderMode::Solid(color) => Some(
+                convert::solid_texel(format, color)
+                    .ok_or(TextureError::BorderUnsupportedFormat { format })?,
+            ),
+            BorderMode::ClampEdge => None,
+        };
+        let extent = self.kind.extent();
+        let (border_x, border_y) = self.border_size();
+        let data = convert::pad(
+            &self.base_source(),
+            format.surface_desc().bits as usize / 8,
+            (extent.width as usize, extent.height as usize),
+            (self.data_width() as usize, self.data_height() as usize),
+            extent.depth as usize * self.kind.num_layers() as usize,
+            (border_x as usize, border_y as usize),
+            solid.as_ref().map(|texel| &texel[..]),
+        );
+
+        padded.data = Cow::Owned(data);
+        padded.source_endianness = Endianness::Native;
+        padded.sanitize_non_finite = false;
+        padded.chroma_key = None;
+        padded.tileable_blend = None;
+        padded.retain_source = false;
+        Ok(Some(padded))
+    }
+
+    /// Builder with block compressed data of all levels and transforms already applied.
+    /// Compression is chosen for device when automatic.
+    #[cfg(feature = "compress-bc")]
+    fn compressed<B>(
+        &self,
+        physical: &B::PhysicalDevice,
+    ) -> Result<Option<TextureBuilder<'static>>, TextureError>
+    where
+        B: Backend,
+    {
+        let (compression, quality) = match (self.compress, self.auto_compression) {
+            (Some(compress), _) => compress,
+            (None, Some(hint)) => match hint.select(self.format, physical.features()) {
+                Some(compression) => {
+                    #[cfg(feature = "logging")]
+                    info!(
+                        "Texture '{}' is compressed to {:?} for {:?} hint",
+                        self.name.as_ref().map_or("unnamed", |name| &name[..]),
+                        compression,
+                        hint
+                    );
+                    (compression, CompressionQuality::High)
+                }
+                None => {
+                    #[cfg(feature = "logging")]
+                    info!(
+                        "Texture '{}' is left uncompressed in {:?} for {:?} hint",
+                        self.name.as_ref().map_or("unnamed", |name| &name[..]),
+                        self.format,
+                        hint
+                    );
+                    return Ok(None);
+                }
+            },
+            (None, None) => return Ok(None),
+        };
+        let format =
+            compression
+                .format(self.format)
+                .ok_or(TextureError::CompressUnsupportedFormat {
+                    format: self.format,
+                })?;
+
+        let extent = self.kind.extent();
+        let layers = self.kind.num_layers();
+        let mut compressed = self.clone_params();
+        compressed.format = format;
+        compressed.set_tight_rows();
+        compressed.generate_mips = false;
+        compressed.source_endianness = Endianness::Native;
+        compressed.sanitize_non_finite = false;
+        compressed.chroma_key = None;
+        compressed.source_format = None;
+        compressed.dither = DitherMode::None;
+        compressed.clear_unspecified_levels = None;
+        compressed.tileable_blend = None;
+        compressed.border = None;
+        compressed.retain_source = false;
+        compressed.compress = None;
+        compressed.auto_compression = None;
+
+        for (level, data, data_width, data_height) in self.upload_levels()? {
+            let level_data = compress::LevelData {
+                format: self.format,
+                extent: extent.at_level(level),
+                data_width,
+                data_height,
+                layers,
+                data: &data,
+            };
+            let blocks = compress::compress(compression, quality, level_data);
+            if level == 0 {
+                compressed.data = Cow::Owned(blocks);
+            } else {
+                compressed.set_mip_data(level, blocks);
+            }
+        }
+        Ok(Some(compressed))
+    }
+
+    /// Builder with block compressed data. Compression is disabled.
+    #[cfg(not(feature = "compress-bc"))]
+    fn compressed<B>(
+        &self,
+        _: &B::PhysicalDevice,
+    ) -> Result<Option<TextureBuilder<'static>>, TextureError>
+    where
+        B: Backend,
+    {
+        Ok(None)
+    }
+
+    /// Owned copy of the builder if it should be retained by the texture.
+    fn retained_source(&self) -> Option<Box<TextureBuilder<'static>>> {
+        if !self.retain_source {
+            return None;
+        }
+        Some(Box::new(TextureBuilder {
+            data: Cow::Owned(self.data.to_vec()),
+            mip_data: self
+                .mip_data
+                .iter()
+                .map(|data| Cow::Owned(data.to_vec()))
+                .collect(),
+            ..self.clone_params()
+        }))
+    }
+
+    /// Check that image created with `params` matches the image this builder would create.
+    fn check_image_params(&self, params: ImageParams) -> Result<(), TextureError> {
+        let required = Usage::TRANSFER_DST | Usage::SAMPLED;
+        let reason = if params.kind != self.kind {
+            Some("image kind differs")
+        } else if params.format != self.format {
+            Some("image format differs")
+        } else if params.levels != self.levels() {
+            Some("image level count differs")
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            Err(TextureError::Incompatible { reason })?;
+        }
+        if !params.usage.contains(required) {
+            Err(TextureError::MissingUsage { usage: required })?;
+        }
+        Ok(())
+    }
+
+    /// Charge estimated size of image with `levels` to the budget.
+    fn charge(&self, levels: Level) -> Result<Option<Charge>, TextureError> {
+        match self.budget {
+            Some(ref budget) => Ok(Some(Charge::new(
+                budget,
+                image_size(self.kind, self.format, levels),
+            )?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Wrap image creation error adding allocation context if device is out of memory.
+    fn allocation_error(&self, levels: Level, error: failure::Error) -> TextureError {
+        allocation_error(error, self.kind, levels, self.format, self.name.clone())
+    }
+
+    /// Builder for levels of the mip chain starting from the first one
+    /// not larger than `max_extent` in any dimension.
+    /// Levels are uploaded as prepared for this builder.
+    fn mip_tail(&self, max_extent: u32) -> Result<Option<TextureBuilder<'_>>, TextureError> {
+        let extent = self.kind.extent();
+        let first = (0..self.levels()).find(|&level| {
+            let extent = extent.at_level(level);
+            extent.width.max(extent.height).max(extent.depth) <= max_extent
+        });
+        let first = match first {
+            Some(first) if first > 0 => first.max(self.min_resident_level),
+            _ => return Ok(None),
+        };
+
+        let mut uploads = self
+            .upload_levels()?
+            .into_iter()
+            .filter(|upload| upload.0 >= first);
+        let (_, data, data_width, data_height) = match uploads.next() {
+            Some(upload) => upload,
+            None => return Ok(None),
+        };
+        let tail = extent.at_level(first);
+        let kind = match self.kind {
+            Kind::D1(_, layers) => Kind::D1(tail.width, layers),
+            Kind::D2(_, _, layers, samples) => Kind::D2(tail.width, tail.height, layers, samples),
+            Kind::D3(..) => Kind::D3(tail.width, tail.height, tail.depth),
+        };
+
+        Ok(Some(TextureBuilder {
+            kind,
+            data_width: Some(data_width),
+            data_height: Some(data_height),
+            data,
+            mip_data: uploads.map(|upload| upload.1).collect(),
+            generate_mips: false,
+            max_levels: None,
+            min_mip_extent: None,
+            min_resident_level: 0,
+            retain_source: false,
+            source_endianness: Endianness::Native,
+            sanitize_non_finite: false,
+            pot_wrap_required: false,
+            chroma_key: None,
+            source_format: None,
+            clear_unspecified_levels: None,
+            tileable_blend: None,
+            border: None,
+            oom_policy: OomPolicy::Fail,
+            ..self.clone_params()
+        }))
+    }
+
+    /// Convert builder into one that owns its data.
+    fn into_static(self) -> TextureBuilder<'static> {
+        let params = self.clone_params();
+        TextureBuilder {
+            data: Cow::Owned(self.data.into_owned()),
+            mip_data: self
+                .mip_data
+                .into_iter()
+                .map(|data| Cow::Owned(data.into_owned()))
+                .collect(),
+            ..params
+        }
+    }
+
+    /// Copy of the builder parameters without level data.
+    fn clone_params(&self) -> TextureBuilder<'static> {
+        TextureBuilder {
+            kind: self.kind,
+            format: self.format,
+            data_width: self.data_width,
+            data_height: self.data_height,
+            data: Cow::Borrowed(&[]),
+            mip_data: Vec::new(),
+            prefer_unified_memory: self.prefer_unified_memory,
+            generate_mips: self.generate_mips,
+            max_levels: self.max_levels,
+            min_mip_extent: self.min_mip_extent,
+            mip_filter: self.mip_filter,
+            mip_renormalize: self.mip_renormalize,
+            mip_gamma_correct: self.mip_gamma_correct,
+            astc_profile: self.astc_profile,
+            alpha_coverage: self.alpha_coverage,
+            min_resident_level: self.min_resident_level,
+            retain_source: self.retain_source,
+            usage: self.usage,
+            storage_flags: self.storage_flags,
+            source_endianness: self.source_endianness,
+            swizzle: self.swizzle,
+            sanitize_non_finite: self.sanitize_non_finite,
+            sampling: self.sampling,
+            lod_clamp: self.lod_clamp,
+            pot_wrap_required: self.pot_wrap_required,
+            chroma_key: self.chroma_key,
+            chroma_bleed: self.chroma_bleed,
+            source_format: self.source_format,
+            dither: self.dither,
+            clear_unspecified_levels: self.clear_unspecified_levels,
+            tileable_blend: self.tileable_blend,
+            border: self.border,
+            #[cfg(feature = "compress-bc")]
+            compress: self.compress,
+            #[cfg(feature = "compress-bc")]
+            auto_compression: self.auto_compression,
+            name: self.name.clone(),
+            oom_policy: self.oom_policy,
+            pre_transform: self.pre_transform,
+            budget: self.budget.clone(),
+            final_layout: self.final_layout,
+            transient: self.transient,
+            per_aspect_upload: self.per_aspect_upload,
+            remaining_bytes_ignored: self.remaining_bytes_ignored,
+        }
+    }
+
+    /// Whether mips are generated in linear space.
+    /// Defaults to `true` for sRGB formats.
+    fn mip_gamma_correct(&self) -> bool {
+        self.mip_gamma_correct
+            .unwrap_or(self.data_format().base_format().1 == ChannelType::Srgb)
+    }
+
+    /// Check that requested mip generation is supported before any resources are allocated.
+    fn check_mip_generation<B>(&self, factory: &Factory<B>) -> Result<(), TextureError>
+    where
+        B: Backend,
+    {
+        if self.generate_mips && self.levels() > 1 {
+            let format = self.data_format();
+            if !mip::is_supported(format) {
+                Err(TextureError::MipGenUnsupportedFormat { format })?;
+            }
+            format::check_mip_filtering::<B>(factory.physical(), self.format)?;
+        }
+        Ok(())
+    }
+
+    /// Decode base level data of all layers into `f32` channels.
+    fn decode_base(&self) -> Result<(usize, Vec<f32>), TextureError> {
+        self.check_base_size()?;
+        let format = self.data_format();
+        let data = self.base_source();
+        let decoded = mip::decode(
+            format,
+            self.kind.extent(),
+            self.data_width(),
+            self.data_height(),
+            &data,
+            self.kind.num_layers(),
+        )
+        .ok_or(TextureError::DecodeUnsupportedFormat { format })?;
+        Ok(decoded)
+    }
+
+    /// Check that base level data covers whole extent
+    /// and, unless remaining bytes are ignored, doesn't go past last row and slice.
+    /// Padding of the last row up to the stride is allowed.
+    fn check_base_size(&self) -> Result<(), TextureError> {
+        let format = self.data_format();
+        let extent = self.kind.extent();
+        let layers = self.kind.num_layers();
+        let too_large = TextureError::DimensionsTooLarge { extent, layers };
+        let required = format::checked_strided_size(
+            format,
+            extent,
+            self.data_width(),
+            self.data_height(),
+            layers,
+        )
+        .ok_or_else(|| too_large.clone())?;
+        let padded = Extent {
+            width: self.data_width(),
+            height: self.data_height(),
+            depth: extent.depth,
+        };
+        let allowed = format::checked_strided_size(
+            format,
+            padded,
+            self.data_width(),
+            self.data_height(),
+            layers,
+        )
+        .ok_or(too_large)?;
+        let actual = self.data.len();
+        if actual < required || (actual > allowed && !self.remaining_bytes_ignored) {
+            Err(TextureError::DataSize {
+                format,
+                extent,
+                layers,
+                required,
+                allowed,
+                actual,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Format of the provided data.
+    fn data_format(&self) -> Format {
+        self.source_format.unwrap_or(self.format)
+    }
+
+    /// Conversion from data format into texture format if they differ.
+    fn conversion(&self) -> Option<convert::Conversion> {
+        match self.source_format {
+            Some(format) if format != self.format => convert::Conversion::new(format, self.format),
+            _ => None,
+        }
+    }
+
+    /// Layout texture is left in after upload.
+    fn final_layout(&self) -> Layout {
+        match self.final_layout {
+            Some(layout) => layout,
+            None if self.format.surface_desc().aspects.contains(Aspects::DEPTH) => {
+                Layout::DepthStencilReadOnlyOptimal
+            }
+            None => Layout::ShaderReadOnlyOptimal,
+        }
+    }
+
+    /// Usage flags the image is created with when uploaded through staging buffer.
+    fn image_usage(&self) -> Usage {
+        if !self.transient {
+            Usage::TRANSFER_DST | Usage::SAMPLED | self.usage
+        } else if self.format.surface_desc().aspects.contains(Aspects::COLOR) {
+            Usage::COLOR_ATTACHMENT
+                | Usage::TRANSIENT_ATTACHMENT
+                | Usage::INPUT_ATTACHMENT
+                | self.usage
+        } else {
+            Usage::DEPTH_STENCIL_ATTACHMENT
+                | Usage::TRANSIENT_ATTACHMENT
+                | Usage::INPUT_ATTACHMENT
+                | self.usage
+        }
+    }
+
+    /// Memory properties of the image when uploaded through staging buffer.
+    fn image_properties(&self) -> Properties {
+        if self.transient {
+            Properties::DEVICE_LOCAL | Properties::LAZILY_ALLOCATED
+        } else {
+            Properties::DEVICE_LOCAL
+        }
+    }
+
+    /// Texels between rows of base level data, tight rows of whole blocks unless set.
+    fn data_width(&self) -> u32 {
+        let (block_width, _) = self.format.surface_desc().dim;
+        self.data_width
+            .unwrap_or_else(|| align(self.kind.extent().width, block_width as u32))
+    }
+
+    /// Rows between slices of base level data, tight slices of whole blocks unless set.
+    fn data_height(&self) -> u32 {
+        let (_, block_height) = self.format.surface_desc().dim;
+        self.data_height
+            .unwrap_or_else(|| align(self.kind.extent().height, block_height as u32))
+    }
+
+    /// Flags the image is created with.
+    fn storage_flags(&self) -> StorageFlags {
+        self.storage_flags
+    }
+
+    /// Parts of texture created from this builder with `levels`,
+    /// left in the final layout with all levels from `min_resident_level` resident.
+    fn parts(&self, levels: Level, usage: Usage, tiling: Tiling) -> TextureParts {
+        TextureParts {
+            kind: self.kind,
+            format: self.format,
+            swizzle: self.swizzle,
+            sampling: self.sampling,
+            lod_clamp: self.lod_clamp,
+            pre_transform: self.pre_transform,
+            levels,
+            min_resident_level: self.min_resident_level,
+            resident: level_mask(self.min_resident_level..levels),
+            layout: self.final_layout(),
+            access: layout_access(self.final_layout()),
+            usage,
+            tiling,
+            storage_flags: self.storage_flags(),
+            transient: false,
+        }
+    }
+
+    /// Number of mip levels the texture will have.
+    fn levels(&self) -> u8 {
+        if self.generate_mips {
+            self.max_levels()
+        } else {
+            // Saturate so that data for every level index is rejected by `validate`.
+            let provided = (self.mip_data.len() + 1).min(Level::MAX as usize) as Level;
+            match (self.clear_unspecified_levels, self.max_levels) {
+                (Some(_), Some(_)) => provided.max(self.max_levels()),
+                _ => provided,
+            }
+        }
+    }
+
+    /// Number of levels in mip chain capped by configured limits.
+    fn max_levels(&self) -> u8 {
+        let extent = self.kind.extent();
+        let mut levels = mip::full_chain_levels(extent);
+        if let Some(min_extent) = self.min_mip_extent {
+            let fits = |size: u32, base: u32| size >= min_extent.min(base);
+            levels = 1
+                + (1..levels)
+                    .take_while(|&level| {
+                        let size = extent.at_level(level);
+                        fits(size.width, extent.width) && fits(size.height, extent.height)
+                    })
+                    .count() as u8;
+        }
+        if let Some(max_levels) = self.max_levels {
+            levels = levels.min(max_levels);
+        }
+        levels
+    }
+
+    /// Check if texture can be created in linear unified memory
+    /// and filled without staging buffer.
+    fn can_write_directly<B>(&self, factory: &Factory<B>) -> bool
+    where
+        B: Backend,
+    {
+        let physical = factory.physical();
+        self.writes_directly(
+            physical
+                .format_properties(Some(self.format))
+                .linear_tiling
+                .contains(ImageFeature::SAMPLED),
+            &physical.memory_properties(),
+        )
+    }
+
+    /// Check if data is written through mapping instead of staging buffer
+    /// given support of sampling linear images of the format and memory of the device.
+    fn writes_directly(&self, linear_sampled: bool, memory: &MemoryProperties) -> bool {
+        let single = matches!(self.kind, Kind::D2(_, _, 1, 1));
+
+        self.prefer_unified_memory
+            && !self.data.is_empty()
+            && single
+            && self.levels() == 1
+            && self.format.surface_desc().dim == (1, 1)
+            && linear_sampled
+            && is_unified_memory(memory)
+    }
+
+    /// Parts of texture with `levels` uploaded through staging buffer.
+    fn staged_parts(&self, levels: Level) -> TextureParts {
+        self.parts(levels, self.image_usage(), Tiling::Optimal)
+    }
+
+    /// Parts of texture built in unified memory.
+    fn unified_parts(&self) -> TextureParts {
+        self.parts(1, Usage::SAMPLED | self.usage, Tiling::Linear)
+    }
+
+    /// Build texture in unified memory writing data through mapping.
+    /// Factory can't create images in `Layout::Preinitialized`,
+    /// so image is moved from `Layout::Undefined` to `Layout::General` on `queue`
+    /// before host writes, as linear images can't be written in optimal layouts,
+    /// and into the final layout afterwards.
+    fn build_unified<B>(
+        &self,
+        factory: &mut Factory<B>,
+        queue: &mut B::CommandQueue,
+        pool: &mut B::CommandPool,
+    ) -> Result<(Texture<B>, UploadStats), TextureError>
+    where
+        B: Backend,
+    {
+        let extent = self.kind.extent();
+        let parts = self.unified_parts();
+        let charge = self.charge(1)?;
+        let image = factory
+            .create_image(
+                self.kind,
+                1,
+                self.format,
+                parts.tiling,
+                parts.storage_flags,
+                parts.usage,
+                Properties::DEVICE_LOCAL | Properties::CPU_VISIBLE,
+            )
+            .map_err(|error| self.allocation_error(1, error))?;
+
+        let range = SubresourceRange {
+            aspects: aspects_for(self.format),
+            levels: 0..1,
+            layers: 0..1,
+        };
+        let host_write = (Access::HOST_WRITE, Layout::General);
+        command::submit_once(factory, queue, pool, |_, cmd| {
+            command::image_barrier::<B>(
+                cmd,
+                image.borrow(),
+                range.clone(),
+                (Access::empty(), Layout::Undefined)..host_write,
+                PipelineStage::TOP_OF_PIPE..PipelineStage::HOST,
+            );
+            Ok(())
+        })?;
+
+        let footprint = factory.get_image_subresource_footprint(
+            image.borrow(),
+            Subresource {
+                aspects: data_aspects(self.format),
+                level: 0,
+                layer: 0,
+            },
+        );
+
+        let texel_size = self.format.surface_desc().bits as usize / 8;
+        let row_size = extent.width as usize * texel_size;
+        let data_row_pitch = self.data_width() as usize * texel_size;
+
+        let data = self.base_data();
+        let block = image.block();
+        let ptr = factory.map_memory(block.memory(), block.range())?;
+        for y in 0..extent.height as usize {
+            let src = &data[y * data_row_pitch..][..row_size];
+            let offset = footprint.slice.start as usize + y * footprint.row_pitch as usize;
+            unsafe {
+                ::std::ptr::copy_nonoverlapping(src.as_ptr(), ptr.add(offset), row_size);
+            }
+        }
+        if !block.properties().contains(Properties::COHERENT) {
+            factory.flush_mapped_memory_ranges(Some((block.memory(), block.range())));
+        }
+        factory.unmap_memory(block.memory());
+
+        command::submit_once(factory, queue, pool, |_, cmd| {
+            command::image_barrier::<B>(
+                cmd,
+                image.borrow(),
+                range,
+                host_write..(parts.access, parts.layout),
+                PipelineStage::HOST..layout_stages(parts.layout),
+            );
+            Ok(())
+        })?;
+
+        let view = self.create_view(factory, &image)?;
+
+        let texture = Texture::assemble(parts, image, view, self.retained_source(), charge);
+        let stats = UploadStats {
+            bytes_uploaded: (row_size * extent.height as usize) as u64,
+            staging_buffer_size: 0,
+            subresources: 1,
+        };
+        Ok((texture, stats))
+    }
+
+    fn create_view<B>(
+        &self,
+        factory: &Factory<B>,
+        image: &Image<B>,
+    ) -> Result<B::ImageView, TextureError>
+    where
+        B: Backend,
+    {
+        create_view(
+            factory,
+            image,
+            self.kind,
+            self.format,
+            self.swizzle,
+            view_aspects(self.format, self.transient),
+            self.min_resident_level..self.levels(),
+        )
+    }
+}
+
+impl<'a> fmt::Display for TextureBuilder<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let size = self.data.len() + self.mip_data.iter().map(|data| data.len()).sum::<usize>();
+        describe(f, self.kind, self.format, self.levels(), self.usage, size)
+    }
+}
+
+impl<'a> Default for TextureBuilder<'a> {
+    /// Builder for single texel 2D texture.
+    fn default() -> Self {
+        TextureBuilder::new(Kind::D2(1, 1, 1, 1))
+    }
+}
+
+impl<'a> From<Kind> for TextureBuilder<'a> {
+    fn from(kind: Kind) -> Self {
+        TextureBuilder::new(kind)
+    }
+}
+
+/// Parameters existing image passed to `TextureBuilder::build_into` was created with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ImageParams {
+    /// Kind of the image.
+    pub kind: Kind,
+    /// Number of mip levels.
+    pub levels: Level,
+    /// Format of the image.
+    pub format: Format,
+    /// Usage flags of the image.
+    pub usage: Usage,
+}
+
+/// How `Texture::resize` transfers old content into the new image.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ResizeMode {
+    /// Copy overlapping region. Shrinking crops the content.
+    Crop,
+    /// Blit whole content stretching it over the new extent.
+    Scale(Filter),
+}
+
+/// What `TextureBuilder::build` does when device runs out of memory for the image.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum OomPolicy {
+    /// Return `TextureError::OutOfMemory`.
+    #[default]
+    Fail,
+    /// Retry once after eviction callback of `build_with_eviction` is called.
+    Retry,
+    /// Create texture with only levels not larger than specified extent.
+    /// Resulting texture has kind of the first of them.
+    MipTail(u32),
+}
+
+/// Clockwise rotation composition code should apply to the texture.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PreTransform {
+    /// No rotation.
+    #[default]
+    Identity,
+    /// Rotate by 90 degrees.
+    Rotate90,
+    /// Rotate by 180 degrees.
+    Rotate180,
+    /// Rotate by 270 degrees.
+    Rotate270,
+}
+
+/// Command `TextureBuilder::record_build` records for the image being built.
+#[derive(Clone, Debug, PartialEq)]
+enum BuildCommand {
+    /// Barrier for the whole image.
+    Barrier(Range<(Access, Layout)>, Range<PipelineStage>),
+    /// Copy of staged data into the image.
+    Copy,
+}
+
+/// Fields of `Texture` other than image, view and ownership,
+/// so that every way of creating texture goes through `Texture::assemble`.
+#[derive(Clone, Copy, Debug)]
+struct TextureParts {
+    kind: Kind,
+    format: Format,
+    swizzle: Swizzle,
+    sampling: Option<TextureSampling>,
+    lod_clamp: Option<LodClamp>,
+    pre_transform: PreTransform,
+    levels: Level,
+    min_resident_level: Level,
+    resident: u64,
+    layout: Layout,
+    access: Access,
+    usage: Usage,
+    tiling: Tiling,
+    storage_flags: StorageFlags,
+    transient: bool,
+}
+
+/// Texture is persistent image accessible by GPU as sampled.
+/// Texture is `Send` and `Sync` for any backend
+/// as `Backend` requires it from image, view and memory types.
+/// Texture always owns memory of its image. gfx-hal exposes no external memory,
+/// so images of other APIs or processes (e.g. dma-buf) can't be imported without copying.
+#[derive(Debug)]
+pub struct Texture<B: Backend> {
+    kind: Kind,
+    format: Format,
+    swizzle: Swizzle,
+    sampling: Option<TextureSampling>,
+    lod_clamp: Option<LodClamp>,
+    pre_transform: PreTransform,
+    levels: Level,
+    min_resident_level: Level,
+    resident: u64,
+    layout: Layout,
+    access: Access,
+    usage: Usage,
+    image: Image<B>,
+    view: B::ImageView,
+    tiling: Tiling,
+    storage_flags: StorageFlags,
+    transient: bool,
+    source: Option<Box<TextureBuilder<'static>>>,
+    charge: Option<Charge>,
+    #[cfg(feature = "logging")]
+    id: u64,
+}
+
+impl<B> Texture<B>
+where
+    B: Backend,
+{
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new<'a>(kind: Kind) -> TextureBuilder<'a> {
+        TextureBuilder::new(kind)
+    }
+
+    pub fn image(&self) -> &Image<B> {
+        &self.image
+    }
+
+    pub fn view(&self) -> &B::ImageView {
+        &self.view
+    }
+
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    /// Whether texels are stored in compressed blocks.
+    pub fn is_compressed(&self) -> bool {
+        self.block_dimensions() != (1, 1)
+    }
+
+    /// Width and height of format's block in texels.
+    /// Regions updated in compressed textures must be aligned to blocks.
+    pub fn block_dimensions(&self) -> (u8, u8) {
+        block_dimensions(self.format)
+    }
+
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    /// Swizzle of the texture view.
+    pub fn swizzle(&self) -> Swizzle {
+        self.swizzle
+    }
+
+    /// Sampler state the texture is intended to be used with.
+    pub fn sampling(&self) -> Option<&TextureSampling> {
+        self.sampling.as_ref()
+    }
+
+    /// All aspects of the image, e.g. `DEPTH | STENCIL` for combined depth-stencil formats.
+    pub fn aspects(&self) -> Aspects {
+        aspects_for(self.format)
+    }
+
+    /// Level of detail clamp and bias the texture is intended to be sampled with.
+    pub fn lod_clamp(&self) -> Option<&LodClamp> {
+        self.lod_clamp.as_ref()
+    }
+
+    /// Sampler info combining `sampling` with `lod_clamp`
+    /// to be baked into descriptor set layout as immutable sampler.
+    /// Returns `None` if texture has no sampling state.
+    pub fn immutable_sampler_info(&self) -> Option<SamplerInfo> {
+        self.sampling
+            .map(|sampling| clamped_sampler_info(sampling, self.lod_clamp))
+    }
+
+    /// Rotation composition code should apply to the texture.
+    pub fn pre_transform(&self) -> PreTransform {
+        self.pre_transform
+    }
+
+    /// Estimated size of the image in bytes, as charged to the budget.
+    pub fn estimated_size(&self) -> u64 {
+        image_size(self.kind, self.format, self.levels)
+    }
+
+    /// Properties of the memory the image is bound to.
+    pub fn memory_properties(&self) -> Properties {
+        self.image.block().properties()
+    }
+
+    /// Check if image memory can be mapped.
+    pub fn is_host_visible(&self) -> bool {
+        is_mappable(self.memory_properties())
+    }
+
+    /// Extent of the texture in texels.
+    ///
+    /// ```
+    /// extern crate gfx_hal as hal;
+    /// extern crate gfx_texture;
+    ///
+    /// use gfx_texture::Texture;
+    ///
+    /// fn describe<B: hal::Backend>(texture: &Texture<B>) -> String {
+    ///     let (width, height, depth) = texture.dimensions();
+    ///     assert_eq!(texture.extent().width, width);
+    ///     format!(
+    ///         "{}x{}x{}, {} layers, {} samples",
+    ///         width,
+    ///         height,
+    ///         depth,
+    ///         texture.layers(),
+    ///         texture.samples()
+    ///     )
+    /// }
+    /// # fn main() {}
+    /// ```
+    pub fn extent(&self) -> Extent {
+        self.kind.extent()
+    }
+
+    /// Width, height and depth of the texture in texels.
+    pub fn dimensions(&self) -> (u32, u32, u32) {
+        let extent = self.kind.extent();
+        (extent.width, extent.height, extent.depth)
+    }
+
+    /// Number of array layers.
+    pub fn layers(&self) -> Layer {
+        self.kind.num_layers()
+    }
+
+    /// Number of samples per texel.
+    pub fn samples(&self) -> NumSamples {
+        self.kind.num_samples()
+    }
+
+    /// Number of mip levels in the image.
+    pub fn levels(&self) -> Level {
+        self.levels
+    }
+
+    /// Most detailed level accessible through the view.
+    pub fn min_resident_level(&self) -> Level {
+        self.min_resident_level
+    }
+
+    /// Contiguous range of levels with uploaded data that ends at the least detailed one.
+    /// Levels uploaded past a gap are not included until the gap is filled.
+    pub fn resident_mips(&self) -> Range<Level> {
+        resident_range(self.resident)
+    }
+
+    /// Level of detail clamp keeping sampling within resident levels.
+    /// Levels of detail are relative to `min_resident_level` the view starts from.
+    /// Bias of `lod_clamp` is preserved.
+    pub fn resident_lod_clamp(&self) -> LodClamp {
+        let resident = self.resident_mips();
+        let base = self.min_resident_level;
+        LodClamp {
+            min_lod: resident.start.saturating_sub(base) as f32,
+            max_lod: resident.end.saturating_sub(base + 1) as f32,
+            lod_bias: self.lod_clamp.map_or(0.0, |clamp| clamp.lod_bias),
+        }
+    }
+
+    /// Builder this texture was created from if it was retained.
+    pub fn source(&self) -> Option<&TextureBuilder<'static>> {
+        self.source.as_deref()
+    }
+
+    /// Build new texture identical to this one from retained builder.
+    /// Intended for recovery after device loss.
+    pub fn recreate(
+        &self,
+        family: QueueFamilyId,
+        factory: &mut Factory<B>,
+    ) -> Result<Texture<B>, TextureError> {
+        match self.source {
+            Some(ref source) => source.build(family, factory),
+            None => Err(TextureError::SourceNotRetained)?,
+        }
+    }
+
+    /// Current layout of the image.
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    /// Access that was last made to the image.
+    pub fn access(&self) -> Access {
+        self.access
+    }
+
+    /// Usage flags the image was created with.
+    pub fn usage(&self) -> Usage {
+        self.usage
+    }
+
+    /// Tiling the image was created with.
+    /// Textures built into memory written directly by host are linear.
+    pub fn tiling(&self) -> Tiling {
+        self.tiling
+    }
+
+    /// Storage flags the image was created with.
+    pub fn storage_flags(&self) -> StorageFlags {
+        self.storage_flags
+    }
+
+    /// Check if texture is transient attachment in lazily allocated memory.
+    pub fn is_transient(&self) -> bool {
+        self.transient
+    }
+
+    /// Unique id of the texture used in creation and destruction logs.
+    #[cfg(feature = "logging")]
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Create texture from its parts and log its creation.
+    fn assemble(
+        parts: TextureParts,
+        image: Image<B>,
+        view: B::ImageView,
+        source: Option<Box<TextureBuilder<'static>>>,
+        charge: Option<Charge>,
+    ) -> Self {
+        let texture = Texture {
+            kind: parts.kind,
+            format: parts.format,
+            swizzle: parts.swizzle,
+            sampling: parts.sampling,
+            lod_clamp: parts.lod_clamp,
+            pre_transform: parts.pre_transform,
+            levels: parts.levels,
+            min_resident_level: parts.min_resident_level,
+            resident: parts.resident,
+            layout: parts.layout,
+            access: parts.access,
+            usage: parts.usage,
+            image,
+            view,
+            tiling: parts.tiling,
+            storage_flags: parts.storage_flags,
+            transient: parts.transient,
+            source,
+            charge,
+            #[cfg(feature = "logging")]
+            id: next_id(),
+        };
+        #[cfg(feature = "logging")]
+        parts.log_created(texture.id);
+        texture
+    }
+
+    /// Parts of the texture other than image and view, to create similar texture from.
+    fn parts(&self) -> TextureParts {
+        TextureParts {
+            kind: self.kind,
+            format: self.format,
+            swizzle: self.swizzle,
+            sampling: self.sampling,
+            lod_clamp: self.lod_clamp,
+            pre_transform: self.pre_transform,
+            levels: self.levels,
+            min_resident_level: self.min_resident_level,
+            resident: self.resident,
+            layout: self.layout,
+            access: self.access,
+            usage: self.usage,
+            tiling: self.tiling,
+            storage_flags: self.storage_flags,
+            transient: self.transient,
+        }
+    }
+
+    /// Copy the region from `src` texture into this one.
+    /// Commands are submitted to `queue` using command buffer from `pool` and waited for,
+    /// as `Factory` can't submit commands on its own.
+    /// See `record_copy_from` for requirements.
+    pub fn copy_from(
+        &mut self,
+        factory: &mut Factory<B>,
+        queue: &mut B::CommandQueue,
+        pool: &mut B::CommandPool,
+        src: &Texture<B>,
+        region: ImageCopy,
+    ) -> Result<(), TextureError> {
+        command::submit_once(factory, queue, pool, |_, cmd| {
+            self.record_copy_from(cmd, src, region)
+        })
+    }
+
+    /// Record copy of the `region` from `src` texture into this one.
+    /// Both images are moved to transfer layouts for the copy and then returned to tracked layouts.
+    /// `src` must be created with `Usage::TRANSFER_SRC`.
+    /// Formats must have same texel (or block) size.
+    pub fn record_copy_from(
+        &mut self,
+        cmd: &mut B::CommandBuffer,
+        src: &Texture<B>,
+        region: ImageCopy,
+    ) -> Result<(), TextureError> {
+        if !src.usage.contains(Usage::TRANSFER_SRC) {
+            Err(TextureError::MissingUsage {
+                usage: Usage::TRANSFER_SRC,
+            })?;
+        }
+        if !self.usage.contains(Usage::TRANSFER_DST) {
+            Err(TextureError::MissingUsage {
+                usage: Usage::TRANSFER_DST,
+            })?;
+        }
+        let (src_desc, dst_desc) = (src.format.surface_desc(), self.format.surface_desc());
+        if src_desc.bits != dst_desc.bits || src_desc.dim != dst_desc.dim {
+            Err(TextureError::IncompatibleFormats {
+                src: src.format,
+                dst: self.format,
+            })?;
+        }
+        src.check_region(&region.src_subresource, region.src_offset, region.extent)?;
+        self.check_region(&region.dst_subresource, region.dst_offset, region.extent)?;
+
+        let src_state = (src.access, src.layout);
+        let dst_state = (self.access, self.layout);
+        let src_transfer = (Access::TRANSFER_READ, Layout::TransferSrcOptimal);
+        let dst_transfer = (Access::TRANSFER_WRITE, Layout::TransferDstOptimal);
+
+        src.barrier(cmd, src_state..src_transfer);
+        self.barrier(cmd, dst_state..dst_transfer);
+        cmd.copy_image(
+            src.image.borrow(),
+            Layout::TransferSrcOptimal,
+            self.image.borrow(),
+            Layout::TransferDstOptimal,
+            Some(region),
+        );
+        src.barrier(cmd, src_transfer..src_state);
+        self.barrier(cmd, dst_transfer..dst_state);
+        Ok(())
+    }
+
+    /// Blit the region of this texture into region of `dst` with scaling.
+    /// Commands are submitted to `queue` using command buffer from `pool` and waited for,
+    /// as `Factory` can't submit commands on its own.
+    /// See `record_blit_to` for requirements.
+    pub fn blit_to(
+        &self,
+        factory: &mut Factory<B>,
+        queue: &mut B::CommandQueue,
+        pool: &mut B::CommandPool,
+        dst: &mut Texture<B>,
+        region: ImageBlit,
+        filter: Filter,
+    ) -> Result<(), TextureError> {
+        command::submit_once(factory, queue, pool, |factory, cmd| {
+            self.record_blit_to(factory, cmd, dst, region, filter)
+        })
+    }
+
+    /// Record blit of the region of this texture into region of `dst` with scaling.
+    /// Bounds may be reversed to flip the image.
+    /// Both images are moved to transfer layouts for the blit and then returned to tracked layouts.
+    /// This texture must be created with `Usage::TRANSFER_SRC` and `dst` with `Usage::TRANSFER_DST`.
+    pub fn record_blit_to(
+        &self,
+        factory: &Factory<B>,
+        cmd: &mut B::CommandBuffer,
+        dst: &mut Texture<B>,
+        region: ImageBlit,
+        filter: Filter,
+    ) -> Result<(), TextureError> {
+        if !self.usage.contains(Usage::TRANSFER_SRC) {
+            Err(TextureError::MissingUsage {
+                usage: Usage::TRANSFER_SRC,
+            })?;
+        }
+        if !dst.usage.contains(Usage::TRANSFER_DST) {
+            Err(TextureError::MissingUsage {
+                usage: Usage::TRANSFER_DST,
+            })?;
+        }
+
+        let physical = factory.physical();
+        let src_features = physical.format_properties(Some(self.format)).optimal_tiling;
+        let dst_features = physical.format_properties(Some(dst.format)).optimal_tiling;
+        let mut required = ImageFeature::BLIT_SRC;
+        if filter == Filter::Linear {
+            required |= ImageFeature::SAMPLED_LINEAR;
+        }
+        if !src_features.contains(required) {
+            Err(TextureError::BlitUnsupported {
+                format: self.format,
+            })?;
+        }
+        if !dst_features.contains(ImageFeature::BLIT_DST) {
+            Err(TextureError::BlitUnsupported { format: dst.format })?;
+        }
+
+        // Depth-stencil images can be blitted only between same formats
+        // and integer formats only between same signedness.
+        let integer = |format: Format| match format.base_format().1 {
+            ChannelType::Uint => Some(false),
+            ChannelType::Int => Some(true),
+            _ => None,
+        };
+        let src_aspects = self.format.surface_desc().aspects;
+        let compatible = if src_aspects.contains(Aspects::COLOR) {
+            dst.format.surface_desc().aspects == src_aspects
+                && integer(self.format) == integer(dst.format)
+        } else {
+            self.format == dst.format
+        };
+        if !compatible {
+            Err(TextureError::IncompatibleFormats {
+                src: self.format,
+                dst: dst.format,
+            })?;
+        }
+
+        let (src_offset, src_extent) = bounds_region(&region.src_bounds);
+        let (dst_offset, dst_extent) = bounds_region(&region.dst_bounds);
+        self.check_region(&region.src_subresource, src_offset, src_extent)?;
+        dst.check_region(&region.dst_subresource, dst_offset, dst_extent)?;
+
+        let src_state = (self.access, self.layout);
+        let dst_state = (dst.access, dst.layout);
+        let src_transfer = (Access::TRANSFER_READ, Layout::TransferSrcOptimal);
+        let dst_transfer = (Access::TRANSFER_WRITE, Layout::TransferDstOptimal);
+
+        self.barrier(cmd, src_state..src_transfer);
+        dst.barrier(cmd, dst_state..dst_transfer);
+        cmd.blit_image(
+            self.image.borrow(),
+            Layout::TransferSrcOptimal,
+            dst.image.borrow(),
+            Layout::TransferDstOptimal,
+            filter,
+            Some(region),
+        );
+        self.barrier(cmd, src_transfer..src_state);
+        dst.barrier(cmd, dst_transfer..dst_state);
+        Ok(())
+    }
+
+    /// Record resolve of multisampled texture into single-sampled `dst`.
+    /// All layers of the base level are resolved.
+    /// `dst` is left in `Layout::ShaderReadOnlyOptimal` ready for sampling.
+    /// Depth formats can't be resolved as backend has no depth resolve.
+    pub fn resolve_to(
+        &self,
+        cmd: &mut B::CommandBuffer,
+        dst: &mut Texture<B>,
+    ) -> Result<(), TextureError> {
+        if self.samples() <= 1 || dst.samples() != 1 {
+            Err(TextureError::ResolveSamples {
+                src: self.samples(),
+                dst: dst.samples(),
+            })?;
+        }
+        if self.format != dst.format {
+            Err(TextureError::IncompatibleFormats {
+                src: self.format,
+                dst: dst.format,
+            })?;
+        }
+        if self.format.surface_desc().aspects != Aspects::COLOR {
+            Err(TextureError::DepthResolveUnsupported {
+                format: self.format,
+            })?;
+        }
+        if self.extent() != dst.extent() || self.layers() != dst.layers() {
+            Err(TextureError::ExtentMismatch {
+                src: self.extent(),
+                dst: dst.extent(),
+            })?;
+        }
+        if !dst.usage.contains(Usage::TRANSFER_DST) {
+            Err(TextureError::MissingUsage {
+                usage: Usage::TRANSFER_DST,
+            })?;
+        }
+
+        let src_state = (self.access, self.layout);
+        let src_transfer = (Access::TRANSFER_READ, Layout::TransferSrcOptimal);
+        let dst_transfer = (Access::TRANSFER_WRITE, Layout::TransferDstOptimal);
+        let dst_final = (Access::SHADER_READ, Layout::ShaderReadOnlyOptimal);
+
+        let layers = SubresourceLayers {
+            aspects: Aspects::COLOR,
+            level: 0,
+            layers: 0..self.layers(),
+        };
+
+        self.barrier(cmd, src_state..src_transfer);
+        dst.barrier(cmd, (dst.access, dst.layout)..dst_transfer);
+        cmd.resolve_image(
+            self.image.borrow(),
+            Layout::TransferSrcOptimal,
+            dst.image.borrow(),
+            Layout::TransferDstOptimal,
+            Some(ImageResolve {
+                src_subresource: layers.clone(),
+                src_offset: Offset::ZERO,
+                dst_subresource: layers,
+                dst_offset: Offset::ZERO,
+                extent: self.extent(),
+            }),
+        );
+        self.barrier(cmd, src_transfer..src_state);
+        dst.barrier(cmd, dst_transfer..dst_final);
+        dst.access = dst_final.0;
+        dst.layout = dst_final.1;
+        Ok(())
+    }
+
+    /// Create texture with new kind and transfer the content into it.
+    /// Texture must be created with `Usage::TRANSFER_SRC`.
+    /// Area not covered by old content is left undefined unless `clear` is set.
+    /// New texture keeps storage flags and the layout this texture is tracked in.
+    /// Transfer is submitted to `queue` using command buffer from `pool` and waited for,
+    /// as `Factory` can't submit commands on its own.
+    /// Old view is destroyed,
+    /// so descriptor sets referencing it must be updated with view of the returned texture.
+    pub fn resize(
+        self,
+        factory: &mut Factory<B>,
+        queue: &mut B::CommandQueue,
+        pool: &mut B::CommandPool,
+        kind: Kind,
+        mode: ResizeMode,
+        clear: bool,
+    ) -> Result<Texture<B>, TextureError> {
+        if !self.usage.contains(Usage::TRANSFER_SRC) {
+            Err(TextureError::MissingUsage {
+                usage: Usage::TRANSFER_SRC,
+            })?;
+        }
+        if let ResizeMode::Scale(_) = mode {
+            let features = factory
+                .physical()
+                .format_properties(Some(self.format))
+                .optimal_tiling;
+            if !features.contains(ImageFeature::BLIT_SRC | ImageFeature::BLIT_DST) {
+                Err(TextureError::BlitUnsupported {
+                    format: self.format,
+                })?;
+            }
+        }
+
+        if !storage_flags_fit(self.storage_flags, kind) {
+            Err(TextureError::StorageFlagsConflict {
+                flags: self.storage_flags,
+                kind,
+            })?;
+        }
+
+        let levels = self.levels.min(mip::full_chain_levels(kind.extent()));
+        let layers = self.layers().min(kind.num_layers());
+        let usage = self.usage | Usage::TRANSFER_DST;
+        let charge = match self.charge {
+            Some(ref charge) => Some(Charge::new(
+                charge.budget(),
+                image_size(kind, self.format, levels),
+            )?),
+            None => None,
+        };
+        let image = factory
+            .create_image(
+                kind,
+                levels,
+                self.format,
+                Tiling::Optimal,
+                self.storage_flags,
+                usage,
+                Properties::DEVICE_LOCAL,
+            )
+            .map_err(|error| allocation_error(error, kind, levels, self.format, None))?;
+        let min_resident_level = self.min_resident_level.min(levels - 1);
+        let view = create_view(
+            factory,
+            &image,
+            kind,
+            self.format,
+            self.swizzle,
+            view_aspects(self.format, self.transient),
+            min_resident_level..levels,
+        )?;
+
+        let range = SubresourceRange {
+            aspects: aspects_for(self.format),
+            levels: 0..levels,
+            layers: 0..kind.num_layers(),
+        };
+        let state = (self.access, self.layout);
+        let src_transfer = (Access::TRANSFER_READ, Layout::TransferSrcOptimal);
+        let transfer_write = (Access::TRANSFER_WRITE, Layout::TransferDstOptimal);
+
+        command::submit_once(factory, queue, pool, |_, cmd| {
+            self.barrier(cmd, state..src_transfer);
+            command::image_barrier::<B>(
+                cmd,
+                image.borrow(),
+                range.clone(),
+                (Access::empty(), Layout::Undefined)..transfer_write,
+                PipelineStage::all()..PipelineStage::TRANSFER,
+            );
+
+            if clear {
+                cmd.clear_image(
+                    image.borrow(),
+                    Layout::TransferDstOptimal,
+                    ClearColor::Float([0.0; 4]).into(),
+                    ClearDepthStencil(0.0, 0).into(),
+                    Some(range.clone()),
+                );
+                command::image_barrier::<B>(
+                    cmd,
+                    image.borrow(),
+                    range.clone(),
+                    transfer_write..transfer_write,
+                    PipelineStage::TRANSFER..PipelineStage::TRANSFER,
+                );
+            }
+
+            let old_extent = self.extent();
+            let new_extent = kind.extent();
+            for level in 0..levels {
+                let src_extent = old_extent.at_level(level);
+                let dst_extent = new_extent.at_level(level);
+                let layers = SubresourceLayers {
+                    aspects: aspects_for(self.format),
+                    level,
+                    layers: 0..layers,
+                };
+                match mode {
+                    ResizeMode::Crop => cmd.copy_image(
+                        self.image.borrow(),
+                        Layout::TransferSrcOptimal,
+                        image.borrow(),
+                        Layout::TransferDstOptimal,
+                        Some(ImageCopy {
+                            src_subresource: layers.clone(),
+                            src_offset: Offset::ZERO,
+                            dst_subresource: layers,
+                            dst_offset: Offset::ZERO,
+                            extent: Extent {
+                                width: src_extent.width.min(dst_extent.width),
+                                height: src_extent.height.min(dst_extent.height),
+                                depth: src_extent.depth.min(dst_extent.depth),
+                            },
+                        }),
+                    ),
+                    ResizeMode::Scale(filter) => cmd.blit_image(
+                        self.image.borrow(),
+                        Layout::TransferSrcOptimal,
+                        image.borrow(),
+                        Layout::TransferDstOptimal,
+                        filter,
+                        Some(ImageBlit {
+                            src_subresource: layers.clone(),
+                            src_bounds: Offset::ZERO.into_bounds(&src_extent),
+                            dst_subresource: layers,
+                            dst_bounds: Offset::ZERO.into_bounds(&dst_extent),
+                        }),
+                    ),
+                }
+            }
+
+            command::image_barrier::<B>(
+                cmd,
+                image.borrow(),
+                range,
+                transfer_write..state,
+                PipelineStage::TRANSFER..PipelineStage::all(),
+            );
+            Ok(())
+        })?;
+
+        let parts = TextureParts {
+            kind,
+            levels,
+            min_resident_level,
+            resident: level_mask(min_resident_level..levels),
+            usage,
+            tiling: Tiling::Optimal,
+            ..self.parts()
+        };
+        self.dispose(factory);
+
+        Ok(Texture::assemble(parts, image, view, None, charge))
+    }
+
+    /// Create texture with same parameters and copy all subresources into it.
+    /// Commands are submitted to `queue` using command buffer from `pool` and waited for,
+    /// as `Factory` can't submit commands on its own.
+    /// See `record_duplicate` for requirements.
+    pub fn duplicate(
+        &self,
+        factory: &mut Factory<B>,
+        queue: &mut B::CommandQueue,
+        pool: &mut B::CommandPool,
+    ) -> Result<Texture<B>, TextureError> {
+        command::submit_once(factory, queue, pool, |factory, cmd| {
+            self.record_duplicate(factory, cmd)
+        })
+    }
+
+    /// Create texture with same parameters and record copy of all subresources into it.
+    /// Texture must be created with `Usage::TRANSFER_SRC`.
+    /// Copy keeps storage flags and is left in the layout this texture is tracked in,
+    /// and gets `Usage::TRANSFER_DST` in addition to the usage of this texture.
+    /// Barriers cover all stages as the stages accessing either texture are unknown.
+    pub fn record_duplicate(
+        &self,
+        factory: &mut Factory<B>,
+        cmd: &mut B::CommandBuffer,
+    ) -> Result<Texture<B>, TextureError> {
+        if !self.usage.contains(Usage::TRANSFER_SRC) {
+            Err(TextureError::MissingUsage {
+                usage: Usage::TRANSFER_SRC,
+            })?;
+        }
+
+        let usage = self.usage | Usage::TRANSFER_DST;
+        let charge = match self.charge {
+            Some(ref charge) => Some(Charge::new(
+                charge.budget(),
+                image_size(self.kind, self.format, self.levels),
+            )?),
+            None => None,
+        };
+        let image = factory
+            .create_image(
+                self.kind,
+                self.levels,
+                self.format,
+                Tiling::Optimal,
+                self.storage_flags,
+                usage,
+                Properties::DEVICE_LOCAL,
+            )
+            .map_err(|error| allocation_error(error, self.kind, self.levels, self.format, None))?;
+        let view = create_view(
+            factory,
+            &image,
+            self.kind,
+            self.format,
+            self.swizzle,
+            view_aspects(self.format, self.transient),
+            self.min_resident_level..self.levels,
+        )?;
+
+        let aspects = aspects_for(self.format);
+        let layers = self.kind.num_layers();
+        let state = (self.access, self.layout);
+        let src_transfer = (Access::TRANSFER_READ, Layout::TransferSrcOptimal);
+        let dst_transfer = (Access::TRANSFER_WRITE, Layout::TransferDstOptimal);
+        let range = SubresourceRange {
+            aspects,
+            levels: 0..self.levels,
+            layers: 0..layers,
+        };
+
+        self.barrier(cmd, state..src_transfer);
+        command::image_barrier::<B>(
+            cmd,
+            image.borrow(),
+            range.clone(),
+            (Access::empty(), Layout::Undefined)..dst_transfer,
+            PipelineStage::all()..PipelineStage::TRANSFER,
+        );
+        let extent = self.extent();
+        cmd.copy_image(
+            self.image.borrow(),
+            Layout::TransferSrcOptimal,
+            image.borrow(),
+            Layout::TransferDstOptimal,
+            (0..self.levels).map(|level| {
+                let layers = SubresourceLayers {
+                    aspects,
+                    level,
+                    layers: 0..layers,
+                };
+                ImageCopy {
+                    src_subresource: layers.clone(),
+                    src_offset: Offset::ZERO,
+                    dst_subresource: layers,
+                    dst_offset: Offset::ZERO,
+                    extent: extent.at_level(level),
+                }
+            }),
+        );
+        self.barrier(cmd, src_transfer..state);
+        command::image_barrier::<B>(
+            cmd,
+            image.borrow(),
+            range,
+            dst_transfer..state,
+            PipelineStage::TRANSFER..PipelineStage::all(),
+        );
+
+        Ok(Texture::assemble(
+            TextureParts {
+                usage,
+                tiling: Tiling::Optimal,
+                transient: false,
+                ..self.parts()
+            },
+            image,
+            view,
+            self.source.clone(),
+            charge,
+        ))
+    }
+
+    /// Destroy view and image of the texture.
+    /// Image is destroyed after commands recorded in current frame complete.
+    /// View is destroyed immediately.
+    /// Must be called on the thread owning `factory` after the texture is no longer
+    /// referenced by descriptor sets of commands that are being recorded.
+    /// Size of the texture is credited back to its budget.
+    pub fn dispose(self, factory: &mut Factory<B>) {
+        #[cfg(feature = "logging")]
+        debug!("Destroy texture #{}: {}", self.id, self);
+        factory.destroy_image_view(self.view);
+        factory.destroy_image(self.image);
+    }
+
+    /// Upload new content from `builder` into existing image keeping the view valid.
+    /// Builder must describe texture of same kind, format and level count.
+    /// Otherwise `TextureError::Incompatible` is returned and texture should be rebuilt.
+    pub fn reload(
+        &mut self,
+        factory: &mut Factory<B>,
+        family: QueueFamilyId,
+        builder: &TextureBuilder,
+    ) -> Result<(), TextureError> {
+        let reason = if builder.kind != self.kind {
+            Some("kind differs")
+        } else if builder.format != self.format {
+            Some("format differs")
+        } else if builder.levels() != self.levels {
+            Some("level count differs")
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            Err(TextureError::Incompatible { reason })?;
+        }
+        if !self.usage.contains(Usage::TRANSFER_DST) {
+            Err(TextureError::MissingUsage {
+                usage: Usage::TRANSFER_DST,
+            })?;
+        }
+        builder.validate()?;
+        builder.upload(factory, family, &mut self.image, self.layout, self.access)?;
+        Ok(())
+    }
+
+    /// Record barrier moving whole image into `layout` and update tracked state.
+    /// `stages` are the stages that last accessed the image and those that will access it next.
+    /// Transition keeping the layout still makes previous access visible to `access`.
+    /// Transition to the current layout and access is a no-op.
+    pub fn transition(
+        &mut self,
+        cmd: &mut B::CommandBuffer,
+        layout: Layout,
+        access: Access,
+        stages: Range<PipelineStage>,
+    ) {
+        let states = (self.access, self.layout)..(access, layout);
+        if !needs_barrier(&states) {
+            return;
+        }
+        command::image_barrier::<B>(
+            cmd,
+            self.image.borrow(),
+            SubresourceRange {
+                aspects: aspects_for(self.format),
+                levels: 0..self.levels,
+                layers: 0..self.kind.num_layers(),
+            },
+            states,
+            stages,
+        );
+        self.layout = layout;
+        self.access = access;
+    }
+
+    /// Record generation of levels `1..levels` with caller-provided compute pipeline.
+    /// Level `n` is generated by dispatch with `sets[n - 1]` bound as set 0,
+    /// which should read level `n - 1` and write level `n`, e.g. through `level_view`s.
+    /// Dispatch covers level extent in groups of `workgroup_size`,
+    /// with layers of array textures along z.
+    /// Levels are kept in `Layout::General` while dispatches run
+    /// and returned to the tracked layout afterwards.
+    /// Texture must be created with `Usage::STORAGE`.
+    pub fn record_compute_mipgen(
+        &self,
+        cmd: &mut B::CommandBuffer,
+        pipeline: &B::ComputePipeline,
+        layout: &B::PipelineLayout,
+        sets: &[B::DescriptorSet],
+        workgroup_size: [u32; 3],
+    ) -> Result<(), TextureError> {
+        if !self.usage.contains(Usage::STORAGE) {
+            Err(TextureError::MissingUsage {
+                usage: Usage::STORAGE,
+            })?;
+        }
+        if sets.len() + 1 != self.levels as usize {
+            Err(TextureError::DescriptorSetCount {
+                expected: self.levels as usize - 1,
+                actual: sets.len(),
+            })?;
+        }
+        if self.levels < 2 {
+            return Ok(());
+        }
+
+        let layers = self.kind.num_layers();
+        let extent = self.kind.extent();
+        cmd.bind_compute_pipeline(pipeline);
+        for command in mipgen_commands(self.levels, (self.access, self.layout)) {
+            match command {
+                MipgenCommand::Barrier(levels, states, stages) => command::image_barrier::<B>(
+                    cmd,
+                    self.image.borrow(),
+                    SubresourceRange {
+                        aspects: aspects_for(self.format),
+                        levels,
+                        layers: 0..layers,
+                    },
+                    states,
+                    stages,
+                ),
+                MipgenCommand::Dispatch(level) => {
+                    let set = &sets[level as usize - 1];
+                    cmd.bind_compute_descriptor_sets(layout, 0, Some(set), &[] as &[u32]);
+                    cmd.dispatch(dispatch_groups(
+                        extent.at_level(level),
+                        layers,
+                        workgroup_size,
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Create view of single level, e.g. to bind it for compute mip generation.
+    /// Returned view must be destroyed by the caller before the texture is disposed.
+    pub fn level_view(
+        &self,
+        factory: &Factory<B>,
+        level: Level,
+    ) -> Result<B::ImageView, TextureError> {
+        if level >= self.levels {
+            Err(TextureError::TooManyLevels {
+                levels: level + 1,
+                max: self.levels,
+            })?;
+        }
+        create_view(
+            factory,
+            &self.image,
+            self.kind,
+            self.format,
+            self.swizzle,
+            view_aspects(self.format, self.transient),
+            level..level + 1,
+        )
+    }
+
+    /// Record barrier for the whole image between states without updating tracked state.
+    /// Barrier covers all stages as the stages that accessed the image are unknown.
+    fn barrier(&self, cmd: &mut B::CommandBuffer, states: Range<(Access, Layout)>) {
+        if states.start.1 == states.end.1 {
+            return;
+        }
+        command::image_barrier::<B>(
+            cmd,
+            self.image.borrow(),
+            SubresourceRange {
+                aspects: aspects_for(self.format),
+                levels: 0..self.levels,
+                layers: 0..self.kind.num_layers(),
+            },
+            states,
+            PipelineStage::all()..PipelineStage::all(),
+        );
+    }
+
+    /// Check that region lies within the image.
+    fn check_region(
+        &self,
+        layers: &SubresourceLayers,
+        offset: Offset,
+        extent: Extent,
+    ) -> Result<(), TextureError> {
+        let level_extent = self.kind.extent().at_level(layers.level);
+        let fits = |offset: i32, size: u32, max: u32| {
+            offset >= 0 && offset as u64 + size as u64 <= max as u64
+        };
+        if layers.level >= self.levels
+            || layers.layers.start > layers.layers.end
+            || layers.layers.end > self.kind.num_layers()
+            || !fits(offset.x, extent.width, level_extent.width)
+            || !fits(offset.y, extent.height, level_extent.height)
+            || !fits(offset.z, extent.depth, level_extent.depth)
+        {
+            Err(TextureError::RegionOutOfBounds {
+                layers: layers.clone(),
+                offset,
+                extent,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Check that region is aligned to blocks of the format.
+    /// Region may end at the level edge with partial block.
+    fn check_alignment(
+        &self,
+        layers: &SubresourceLayers,
+        offset: Offset,
+        extent: Extent,
+    ) -> Result<(), TextureError> {
+        let level_extent = self.kind.extent().at_level(layers.level);
+        check_block_alignment(self.format, level_extent, offset, extent)
+    }
+
+    /// Upload tightly packed data for all layers of the mip level and mark it resident.
+    /// Level is not accessible through the view until `set_min_resident_level` is called.
+    /// Texture must be created with `Usage::TRANSFER_DST`.
+    pub fn upload_level(
+        &mut self,
+        factory: &mut Factory<B>,
+        family: QueueFamilyId,
+        level: Level,
+        data: &[u8],
+    ) -> Result<(), TextureError> {
+        if !self.usage.contains(Usage::TRANSFER_DST) {
+            Err(TextureError::MissingUsage {
+                usage: Usage::TRANSFER_DST,
+            })?;
+        }
+        if level >= self.levels {
+            Err(TextureError::TooManyLevels {
+                levels: level + 1,
+                max: self.levels,
+            })?;
+        }
+        check_level_size(self.format, self.kind, level, data)?;
+
+        let width = self.kind.extent().at_level(level).width;
+        let (block_width, _) = self.block_dimensions();
+        self.upload_mip(
+            factory,
+            family,
+            level,
+            data,
+            align(width, block_width as u32),
+        )
+    }
+
+    /// Upload data for all layers of the mip level with rows `data_width` texels apart
+    /// and mark it resident.
+    /// Level is not accessible through the view until `set_min_resident_level` is called.
+    /// Texture must be created with `Usage::TRANSFER_DST`.
+    pub fn upload_mip(
+        &mut self,
+        factory: &mut Factory<B>,
+        family: QueueFamilyId,
+        level: Level,
+        data: &[u8],
+        data_width: u32,
+    ) -> Result<(), TextureError> {
+        if !self.usage.contains(Usage::TRANSFER_DST) {
+            Err(TextureError::MissingUsage {
+                usage: Usage::TRANSFER_DST,
+            })?;
+        }
+        if level >= self.levels {
+            Err(TextureError::TooManyLevels {
+                levels: level + 1,
+                max: self.levels,
+            })?;
+        }
+
+        let extent = self.kind.extent().at_level(level);
+        if data_width < extent.width {
+            Err(TextureError::RowTooShort {
+                data_width,
+                width: extent.width,
+            })?;
+        }
+        let (_, block_height) = self.block_dimensions();
+        let data_height = align(extent.height, block_height as u32);
+        let layers = self.kind.num_layers();
+        let expected = format::strided_size(self.format, extent, data_width, data_height, layers);
+        if data.len() < expected {
+            Err(TextureError::LevelSize {
+                level,
+                expected,
+                actual: data.len(),
+            })?;
+        }
+
+        factory.upload_image(
+            &mut self.image,
+            family,
+            self.layout,
+            self.access,
+            SubresourceLayers {
+                aspects: data_aspects(self.format),
+                level,
+                layers: 0..layers,
+            },
+            Offset::ZERO,
+            extent,
+            data_width,
+            data_height,
+            &data[..expected],
+        )?;
+        self.resident |= level_mask(level..level + 1);
+        Ok(())
+    }
+
+    /// Upload tightly packed data into region of the texture.
+    /// Regions of compressed textures must be aligned to blocks except at the level edges.
+    /// Texture must be created with `Usage::TRANSFER_DST`.
+    pub fn update_region(
+        &mut self,
+        factory: &mut Factory<B>,
+        family: QueueFamilyId,
+        layers: SubresourceLayers,
+        offset: Offset,
+        extent: Extent,
+        data: &[u8],
+    ) -> Result<(), TextureError> {
+        if !self.usage.contains(Usage::TRANSFER_DST) {
+            Err(TextureError::MissingUsage {
+                usage: Usage::TRANSFER_DST,
+            })?;
+        }
+        self.check_region(&layers, offset, extent)?;
+        self.check_alignment(&layers, offset, extent)?;
+
+        let expected =
+            format::level_size(self.format, extent, layers.layers.end - layers.layers.start);
+        if data.len() != expected {
+            Err(TextureError::LevelSize {
+                level: layers.level,
+                expected,
+                actual: data.len(),
+            })?;
+        }
+
+        let (block_width, block_height) = self.block_dimensions();
+        factory.upload_image(
+            &mut self.image,
+            family,
+            self.layout,
+            self.access,
+            layers,
+            offset,
+            extent,
+            align(extent.width, block_width as u32),
+            align(extent.height, block_height as u32),
+            data,
+        )?;
+        Ok(())
+    }
+
+    /// Create additional view interpreting texels of the image as `format`.
+    /// Format must have same texel or block size and the image must be created
+    /// with `StorageFlags::MUTABLE_FORMAT` unless format is the same.
+    /// Returned view must be destroyed by the caller before the texture is disposed.
+    pub fn try_view_as(
+        &self,
+        factory: &Factory<B>,
+        format: Format,
+    ) -> Result<B::ImageView, TextureError> {
+        check_reinterpret(self.format, self.storage_flags, format)?;
+        create_view(
+            factory,
+            &self.image,
+            self.kind,
+            format,
+            self.swizzle,
+            view_aspects(format, self.transient),
+            self.min_resident_level..self.levels,
+        )
+    }
+
+    /// Create additional view over levels `skip_top_mips..levels` for lower quality tiers.
+    /// Returned view must be destroyed by the caller before the texture is disposed.
+    pub fn view_quality_tier(
+        &self,
+        factory: &Factory<B>,
+        skip_top_mips: Level,
+    ) -> Result<B::ImageView, TextureError> {
+        create_view(
+            factory,
+            &self.image,
+            self.kind,
+            self.format,
+            self.swizzle,
+            view_aspects(self.format, self.transient),
+            tier_levels(self.levels, skip_top_mips)?,
+        )
+    }
+
+    /// Restrict the view to levels starting from `level`.
+    /// Residency is controlled by recreating the view rather than by sampler LOD clamping,
+    /// so any sampler can be used with the texture.
+    /// Descriptor sets referencing the previous view must be updated with the new one.
+    /// Previous view is returned and must be destroyed by the caller
+    /// once no submitted commands reference it.
+    pub fn set_min_resident_level(
+        &mut self,
+        factory: &Factory<B>,
+        level: Level,
+    ) -> Result<B::ImageView, TextureError> {
+        if level >= self.levels {
+            Err(TextureError::TooManyLevels {
+                levels: level + 1,
+                max: self.levels,
+            })?;
+        }
+        let view = create_view(
+            factory,
+            &self.image,
+            self.kind,
+            self.format,
+            self.swizzle,
+            view_aspects(self.format, self.transient),
+            level..self.levels,
+        )?;
+        self.min_resident_level = level;
+        Ok(::std::mem::replace(&mut self.view, view))
+    }
+}
+
+impl<B> fmt::Display for Texture<B>
+where
+    B: Backend,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.parts(), f)
+    }
+}
+
+impl TextureParts {
+    /// Log creation of texture with these parts and `id`.
+    #[cfg(feature = "logging")]
+    fn log_created(&self, id: u64) {
+        debug!("Create texture #{}: {}", id, self);
+    }
+}
+
+impl fmt::Display for TextureParts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let extent = self.kind.extent();
+        let layers = self.kind.num_layers();
+        let size = (self.min_resident_level..self.levels)
+            .map(|level| format::level_size(self.format, extent.at_level(level), layers))
+            .sum();
+        describe(f, self.kind, self.format, self.levels, self.usage, size)
+    }
+}
+
+/// Wrap image creation error into `TextureError::OutOfMemory` if device is out of memory.
+fn allocation_error(
+    error: failure::Error,
+    kind: Kind,
+    levels: Level,
+    format: Format,
+    name: Option<String>,
+) -> TextureError {
+    let out_of_memory = match error.downcast_ref::<hal::image::CreationError>() {
+        Some(hal::image::CreationError::OutOfHostMemory)
+        | Some(hal::image::CreationError::OutOfDeviceMemory) => true,
+        _ => error.downcast_ref::<hal::device::OutOfMemory>().is_some(),
+    };
+    if !out_of_memory {
+        return error.into();
+    }
+    TextureError::OutOfMemory {
+        size: image_size(kind, format, levels),
+        kind,
+        format,
+        name,
+    }
+}
+
+/// Next id of created texture.
+#[cfg(feature = "logging")]
+fn next_id() -> u64 {
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Estimated size of image in bytes.
+fn image_size(kind: Kind, format: Format, levels: Level) -> u64 {
+    let extent = kind.extent();
+    let size = (0..levels)
+        .map(|level| format::level_size(format, extent.at_level(level), kind.num_layers()))
+        .sum::<usize>();
+    size as u64 * kind.num_samples() as u64
+}
+
+/// Write one-line summary of a texture.
+fn describe(
+    f: &mut fmt::Formatter,
+    kind: Kind,
+    format: Format,
+    levels: Level,
+    usage: Usage,
+    size: usize,
+) -> fmt::Result {
+    let extent = kind.extent();
+    let dim = match kind {
+        Kind::D1(..) => "1D",
+        Kind::D2(..) => "2D",
+        Kind::D3(..) => "3D",
+    };
+    write!(
+        f,
+        "{} {}x{}x{} {:?}, {} levels, {} layers, {} samples, usage {:?}, {} bytes",
+        dim,
+        extent.width,
+        extent.height,
+        extent.depth,
+        format,
+        levels,
+        kind.num_layers(),
+        kind.num_samples(),
+        usage,
+        size,
+    )
+}
+
+/// Check that tightly packed data matches size of all layers of the mip level.
+fn check_level_size(
+    format: Format,
+    kind: Kind,
+    level: Level,
+    data: &[u8],
+) -> Result<(), TextureError> {
+    let layers = kind.num_layers();
+    let layer_size = format::level_size(format, kind.extent().at_level(level), 1);
+    let expected = layer_size.saturating_mul(layers as usize);
+    if data.len() == expected {
+        return Ok(());
+    }
+    if layer_size != 0 && data.len().is_multiple_of(layer_size) {
+        Err(TextureError::LayerCount {
+            level,
+            expected: layers,
+            actual: (data.len() / layer_size) as u16,
+        })?;
+    }
+    Err(TextureError::LevelSize {
+        level,
+        expected,
+        actual: data.len(),
+    })?
+}
+
+fn create_view<B>(
+    factory: &Factory<B>,
+    image: &Image<B>,
+    kind: Kind,
+    format: Format,
+    swizzle: Swizzle,
+    aspects: Aspects,
+    levels: Range<Level>,
+) -> Result<B::ImageView, TextureError>
+where
+    B: Backend,
+{
+    let view = factory.create_image_view(
+        image.borrow(),
+        match kind {
+            Kind::D1(_, 1) => ViewKind::D1,
+            Kind::D1(_, _) => ViewKind::D1Array,
+            Kind::D2(_, _, 1, _) => ViewKind::D2,
+            Kind::D2(_, _, _, _) => ViewKind::D2Array,
+            Kind::D3(_, _, _) => ViewKind::D3,
+        },
+        format,
+        swizzle,
+        SubresourceRange {
+            aspects,
+            levels,
+            layers: 0..kind.num_layers(),
+        },
+    )?;
+    Ok(view)
+}
+
+/// Level with its data and texels between rows and rows between slices of the data.
+type LevelUpload<'a> = (Level, Cow<'a, [u8]>, u32, u32);
+
+/// Bit mask of levels in range.
+/// Levels past 63 can't exist as image extent is limited to `u32`.
+fn level_mask(levels: Range<Level>) -> u64 {
+    levels
+        .filter(|&level| level < 64)
+        .fold(0, |mask, level| mask | 1 << level)
+}
+
+/// Sampler info of `sampling` with level of detail range and bias of `lod_clamp`.
+fn clamped_sampler_info(sampling: TextureSampling, lod_clamp: Option<LodClamp>) -> SamplerInfo {
+    let mut info = sampling.sampler_info();
+    if let Some(clamp) = lod_clamp {
+        info.lod_bias = clamp.lod_bias.into();
+        info.lod_range = clamp.min_lod.into()..clamp.max_lod.into();
+    }
+    info
+}
+
+/// Contiguous range of levels in mask that ends at the highest one.
+fn resident_range(mask: u64) -> Range<Level> {
+    if mask == 0 {
+        return 0..0;
+    }
+    let end = 64 - mask.leading_zeros() as Level;
+    let mut start = end - 1;
+    while start > 0 && mask & (1 << (start - 1)) != 0 {
+        start -= 1;
+    }
+    start..end
+}
+
+/// All aspects of the format.
+/// Barriers and attachment views cover all of them.
+fn aspects_for(format: Format) -> Aspects {
+    format.surface_desc().aspects
+}
+
+/// Aspects data is uploaded to and sampled from.
+/// Depth-stencil formats use depth aspect.
+fn data_aspects(format: Format) -> Aspects {
+    let aspects = aspects_for(format);
+    if aspects.contains(Aspects::DEPTH) {
+        Aspects::DEPTH
+    } else {
+        aspects
+    }
+}
+
+/// Aspects of the texture view.
+/// Views of transient attachments cover all aspects of the format.
+fn view_aspects(format: Format, transient: bool) -> Aspects {
+    if transient {
+        aspects_for(format)
+    } else {
+        data_aspects(format)
+    }
+}
+
+/// Check that region of level with `level_extent` is aligned to blocks of `format`.
+/// Region may end at the level edge with partial block.
+fn check_block_alignment(
+    format: Format,
+    level_extent: Extent,
+    offset: Offset,
+    extent: Extent,
+) -> Result<(), TextureError> {
+    let (block_width, block_height) = format.surface_desc().dim;
+    let aligned = |offset: i32, size: u32, max: u32, block: u8| {
+        let block = block as u32;
+        (offset as u32).is_multiple_of(block)
+            && (size.is_multiple_of(block) || offset as u32 + size == max)
+    };
+    if !aligned(offset.x, extent.width, level_extent.width, block_width)
+        || !aligned(offset.y, extent.height, level_extent.height, block_height)
+    {
+        Err(TextureError::UnalignedRegion {
+            offset,
+            extent,
+            block: (block_width, block_height),
+        })?;
+    }
+    Ok(())
+}
+
+/// Check that image of `kind` can be created with storage `flags`.
+fn storage_flags_fit(flags: StorageFlags, kind: Kind) -> bool {
+    let cube_compatible = match kind {
+        Kind::D2(width, height, layers, 1) => width == height && layers >= 6,
+        _ => false,
+    };
+    let array_compatible = matches!(kind, Kind::D3(..));
+    (!flags.contains(StorageFlags::CUBE_VIEW) || cube_compatible)
+        && (!flags.contains(StorageFlags::ARRAY_2D) || array_compatible)
+}
+
+/// Access matching the way image in `layout` is used.
+fn layout_access(layout: Layout) -> Access {
+    match layout {
+        Layout::General => Access::SHADER_READ | Access::SHADER_WRITE,
+        Layout::ColorAttachmentOptimal => {
+            Access::COLOR_ATTACHMENT_READ | Access::COLOR_ATTACHMENT_WRITE
+        }
+        Layout::DepthStencilAttachmentOptimal => {
+            Access::DEPTH_STENCIL_ATTACHMENT_READ | Access::DEPTH_STENCIL_ATTACHMENT_WRITE
+        }
+        Layout::DepthStencilReadOnlyOptimal => {
+            Access::DEPTH_STENCIL_ATTACHMENT_READ | Access::SHADER_READ
+        }
+        Layout::ShaderReadOnlyOptimal => Access::SHADER_READ,
+        Layout::TransferSrcOptimal => Access::TRANSFER_READ,
+        Layout::TransferDstOptimal => Access::TRANSFER_WRITE,
+        _ => Access::empty(),
+    }
+}
+
+/// Check if moving image between `states` requires barrier,
+/// either to change layout or to make previous access visible to the next one.
+fn needs_barrier(states: &Range<(Access, Layout)>) -> bool {
+    states.start != states.end
+}
+
+/// Stages accessing image in `layout` with access returned by `layout_access`.
+fn layout_stages(layout: Layout) -> PipelineStage {
+    let shaders = PipelineStage::VERTEX_SHADER
+        | PipelineStage::FRAGMENT_SHADER
+        | PipelineStage::COMPUTE_SHADER;
+    let tests = PipelineStage::EARLY_FRAGMENT_TESTS | PipelineStage::LATE_FRAGMENT_TESTS;
+    match layout {
+        Layout::General | Layout::ShaderReadOnlyOptimal => shaders,
+        Layout::ColorAttachmentOptimal => PipelineStage::COLOR_ATTACHMENT_OUTPUT,
+        Layout::DepthStencilAttachmentOptimal => tests,
+        Layout::DepthStencilReadOnlyOptimal => tests | shaders,
+        Layout::TransferSrcOptimal | Layout::TransferDstOptimal => PipelineStage::TRANSFER,
+        _ => PipelineStage::BOTTOM_OF_PIPE,
+    }
+}
+
+/// Width and height of block of `format` in texels.
+fn block_dimensions(format: Format) -> (u8, u8) {
+    format.surface_desc().dim
+}
+
+/// Check if memory with `properties` can be mapped.
+fn is_mappable(properties: Properties) -> bool {
+    properties.contains(Properties::CPU_VISIBLE)
+}
+
+/// Check if device memory is unified, as with integrated GPUs sharing system memory.
+/// gfx-hal doesn't report device type, so memory is unified when every heap is device-local
+/// and some of it is host-visible. Discrete GPUs always have host heap,
+/// even when part of their memory is host-visible.
+fn is_unified_memory(memory: &MemoryProperties) -> bool {
+    let device_local = |heap| {
+        memory
+            .memory_types
+            .iter()
+            .any(|ty| ty.heap_index == heap && ty.properties.contains(Properties::DEVICE_LOCAL))
+    };
+    !memory.memory_heaps.is_empty()
+        && (0..memory.memory_heaps.len()).all(device_local)
+        && memory.memory_types.iter().any(|ty| {
+            ty.properties
+                .contains(Properties::DEVICE_LOCAL | Properties::CPU_VISIBLE)
+        })
+}
+
+/// Check that image of `format` created with storage `flags` can be viewed as `view_format`.
+fn check_reinterpret(
+    format: Format,
+    flags: StorageFlags,
+    view_format: Format,
+) -> Result<(), TextureError> {
+    if view_format != format {
+        let (src, dst) = (format.surface_desc(), view_format.surface_desc());
+        if src.bits != dst.bits || src.dim != dst.dim || src.aspects != dst.aspects {
+            Err(TextureError::IncompatibleFormats {
+                src: format,
+                dst: view_format,
+            })?;
+        }
+        if !flags.contains(StorageFlags::MUTABLE_FORMAT) {
+            Err(TextureError::MissingStorageFlags {
+                flags: StorageFlags::MUTABLE_FORMAT,
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// Levels remaining after skipping `skip_top_mips` most detailed of `levels`.
+/// At least one level must remain.
+fn tier_levels(levels: Level, skip_top_mips: Level) -> Result<Range<Level>, TextureError> {
+    if skip_top_mips >= levels {
+        Err(TextureError::TooManyLevels {
+            levels: skip_top_mips.saturating_add(1),
+            max: levels,
+        })?;
+    }
+    Ok(skip_top_mips..levels)
+}
+
+/// Command `Texture::record_compute_mipgen` records.
+#[derive(Clone, Debug, PartialEq)]
+enum MipgenCommand {
+    /// Barrier for levels of the image.
+    Barrier(Range<Level>, Range<(Access, Layout)>, Range<PipelineStage>),
+    /// Dispatch generating the level.
+    Dispatch(Level),
+}
+
+/// Commands generating `levels` of image tracked in `state` with compute dispatches.
+/// Stages that accessed the image before and will access it after are unknown,
+/// so barriers around generation cover all stages.
+fn mipgen_commands(levels: Level, state: (Access, Layout)) -> Vec<MipgenCommand> {
+    let read = (Access::SHADER_READ, Layout::General);
+    let write = (Access::SHADER_WRITE, Layout::General);
+    let compute = PipelineStage::COMPUTE_SHADER;
+
+    let mut commands = vec![
+        MipgenCommand::Barrier(0..1, state..read, PipelineStage::all()..compute),
+        MipgenCommand::Barrier(
+            1..levels,
+            (Access::empty(), Layout::Undefined)..write,
+            PipelineStage::all()..compute,
+        ),
+    ];
+    for level in 1..levels {
+        commands.push(MipgenCommand::Dispatch(level));
+        commands.push(MipgenCommand::Barrier(
+            level..level + 1,
+            write..read,
+            compute..compute,
+        ));
+    }
+    commands.push(MipgenCommand::Barrier(
+        0..levels,
+        read..state,
+        compute..PipelineStage::all(),
+    ));
+    commands
+}
+
+/// Number of workgroups covering `extent` with `layers` along z.
+fn dispatch_groups(extent: Extent, layers: Layer, workgroup_size: [u32; 3]) -> [u32; 3] {
+    [
+        extent.width.div_ceil(workgroup_size[0]),
+        extent.height.div_ceil(workgroup_size[1]),
+        (extent.depth * layers as u32).div_ceil(workgroup_size[2]),
+    ]
+}
+
+/// Offset and extent of the region between possibly reversed bounds.
+fn bounds_region(bounds: &Range<Offset>) -> (Offset, Extent) {
+    let offset = Offset {
+        x: bounds.start.x.min(bounds.end.x),
+        y: bounds.start.y.min(bounds.end.y),
+        z: bounds.start.z.min(bounds.end.z),
+    };
+    let extent = Extent {
+        width: (bounds.start.x - bounds.end.x).unsigned_abs(),
+        height: (bounds.start.y - bounds.end.y).unsigned_abs(),
+        depth: (bounds.start.z - bounds.end.z).unsigned_abs(),
+    };
+    (offset, extent)
+}
+
+fn align(value: u32, alignment: u32) -> u32 {
+    value.div_ceil(alignment) * alignment
+}
+
+/// Reinterpret vector of texels as bytes.
+/// Allocation is reused only if `T` has alignment of `u8`,
+/// otherwise it would be deallocated with wrong layout, so bytes are copied.
+fn cast_vec<T>(mut vec: Vec<T>) -> Vec<u8> {
+    use std::mem;
+
+    if mem::align_of::<T>() != 1 {
+        return cast_slice(&vec).to_vec();
+    }
+
+    let len = mem::size_of::<T>() * vec.len();
+    let cap = mem::size_of::<T>() * vec.capacity();
+
+    let ptr = vec.as_mut_ptr();
+    mem::forget(vec);
+    unsafe { Vec::from_raw_parts(ptr as _, len, cap) }
+}
+
+fn cast_slice<T>(slice: &[T]) -> &[u8] {
+    use std::{mem, slice::from_raw_parts};
+
+    unsafe { from_raw_parts(slice.as_ptr() as _, mem::size_of_val(slice)) }
+}
+
+fn cast_cow<T>(cow: Cow<[T]>) -> Cow<[u8]>
+where
+    T: Clone,
+{
+    match cow {
+        Cow::Borrowed(slice) => Cow::Borrowed(cast_slice(slice)),
+        Cow::Owned(vec) => Cow::Owned(cast_vec(vec)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hal::adapter::MemoryType;
+    use hal::image::WrapMode;
+
+    /// Level and message of records logged by all tests,
+    /// captured by logger installed on first call.
+    #[cfg(feature = "logging")]
+    pub(crate) fn captured_logs() -> &'static ::std::sync::Mutex<Vec<(log::Level, String)>> {
+        use log::{Log, Metadata, Record};
+        use std::sync::{Mutex, Once};
+
+        struct Capture(Mutex<Vec<(log::Level, String)>>);
+
+        impl Log for Capture {
+            fn enabled(&self, _: &Metadata) -> bool {
+                true
+            }
+
+            fn log(&self, record: &Record) {
+                let message = record.args().to_string();
+                self.0.lock().unwrap().push((record.level(), message));
+            }
+
+            fn flush(&self) {}
+        }
+
+        static CAPTURE: Capture = Capture(Mutex::new(Vec::new()));
+        static INSTALL: Once = Once::new();
+        INSTALL.call_once(|| {
+            log::set_logger(&CAPTURE).unwrap();
+            log::set_max_level(log::LevelFilter::Debug);
+        });
+        &CAPTURE.0
+    }
+
+    #[cfg(feature = "logging")]
+    #[test]
+    fn creation_is_logged() {
+        let logs = captured_logs();
+        let builder = TextureBuilder::new(Kind::D2(4, 4, 1, 1)).with_format(Format::Rgba8Unorm);
+        builder
+            .parts(1, builder.image_usage(), Tiling::Optimal)
+            .log_created(7);
+
+        let logs = logs.lock().unwrap();
+        let record = logs
+            .iter()
+            .find(|record| record.1.starts_with("Create texture #7: "))
+            .expect("Creation is logged");
+        assert_eq!(record.0, log::Level::Debug);
+        assert!(record
+            .1
+            .contains("2D 4x4x1 Rgba8Unorm, 1 levels, 1 layers, 1 samples"));
+        assert!(record.1.ends_with(", 64 bytes"));
+    }
+
+    /// Fails to compile if builder or texture stops being `Send` and `Sync`.
+    #[allow(dead_code)]
+    fn assert_send_sync<B: Backend>() {
+        fn assert<T: Send + Sync>() {}
+        assert::<TextureBuilder<'static>>();
+        assert::<Texture<B>>();
+    }
+
+    #[test]
+    fn queue_build_records_copy_of_every_level() {
+        let builder =
+            TextureBuilder::from_bytes(Kind::D2(16, 16, 1, 1), Format::Rgba8Unorm, vec![0; 1024])
+                .unwrap()
+                .with_generate_mips(true);
+        let unified = memory_properties(
+            &[(Properties::DEVICE_LOCAL | Properties::CPU_VISIBLE, 0)],
+            1,
+        );
+        // Mip chains are never written directly, so the build is recorded on caller's pool.
+        assert!(!builder.writes_directly(true, &unified));
+
+        let uploads = builder.upload_levels().unwrap();
+        let offsets = (0..uploads.len() as u64)
+            .map(|i| i * 1024)
+            .collect::<Vec<_>>();
+        let regions = builder.upload_regions(&uploads, &offsets);
+        let levels = regions
+            .iter()
+            .map(|region| (region.image_layers.level, region.image_extent.width))
+            .collect::<Vec<_>>();
+        assert_eq!(levels, [(0, 16), (1, 8), (2, 4), (3, 2), (4, 1)]);
+
+        let copies = builder
+            .build_commands(true)
+            .into_iter()
+            .filter(|command| *command == BuildCommand::Copy)
+            .count();
+        assert_eq!(copies, 1);
+    }
+
+    #[test]
+    fn built_texture_reflects_overrides() {
+        let usage = Usage::STORAGE | Usage::TRANSFER_SRC;
+        let builder =
+            TextureBuilder::from_bytes(Kind::D2(4, 4, 1, 1), Format::Rgba8Unorm, vec![0; 64])
+                .unwrap()
+                .with_usage(usage)
+                .with_storage_flags(StorageFlags::MUTABLE_FORMAT);
+
+        let staged = builder.staged_parts(1);
+        assert_eq!(staged.tiling, Tiling::Optimal);
+        assert_eq!(staged.usage, usage | Usage::TRANSFER_DST | Usage::SAMPLED);
+        assert_eq!(staged.storage_flags, StorageFlags::MUTABLE_FORMAT);
+
+        let unified = builder.unified_parts();
+        assert_eq!(unified.tiling, Tiling::Linear);
+        assert_eq!(unified.usage, usage | Usage::SAMPLED);
+        assert_eq!(unified.storage_flags, StorageFlags::MUTABLE_FORMAT);
+    }
+
+    #[test]
+    fn block_dimensions_of_formats() {
+        for &format in &[Format::Bc1RgbaUnorm, Format::Bc3Srgb, Format::Bc7Unorm] {
+            assert_eq!(block_dimensions(format), (4, 4), "{:?}", format);
+        }
+        for &format in &[Format::Rgba8Unorm, Format::R16Float, Format::D32Float] {
+            assert_eq!(block_dimensions(format), (1, 1), "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn host_visibility_follows_memory_properties() {
+        let optimal =
+            TextureBuilder::from_bytes(Kind::D2(4, 4, 1, 1), Format::Rgba8Unorm, vec![0; 64])
+                .unwrap()
+                .resolved();
+        assert_eq!(optimal.tiling, Tiling::Optimal);
+        assert!(!is_mappable(optimal.properties));
+
+        // Linear textures written directly are bound to unified memory.
+        assert!(is_mappable(
+            Properties::DEVICE_LOCAL | Properties::CPU_VISIBLE
+        ));
+        assert!(is_mappable(Properties::CPU_VISIBLE | Properties::COHERENT));
+    }
+
+    #[test]
+    fn transition_with_new_access_needs_barrier() {
+        let read = (Access::SHADER_READ, Layout::General);
+        let write = (Access::SHADER_WRITE, Layout::General);
+        assert!(needs_barrier(&(write..read)));
+        assert!(needs_barrier(
+            &(read..(Access::SHADER_READ, Layout::TransferSrcOptimal))
+        ));
+        assert!(!needs_barrier(&(read..read)));
+    }
+
+    #[test]
+    fn build_commands_end_in_final_layout_before_shaders() {
+        let shaders = PipelineStage::VERTEX_SHADER
+            | PipelineStage::FRAGMENT_SHADER
+            | PipelineStage::COMPUTE_SHADER;
+        let undefined = (Access::empty(), Layout::Undefined);
+        let transfer_write = (Access::TRANSFER_WRITE, Layout::TransferDstOptimal);
+        let sampled = (Access::SHADER_READ, Layout::ShaderReadOnlyOptimal);
+        let builder = TextureBuilder::new(Kind::D2(4, 4, 1, 1)).with_format(Format::Rgba8Unorm);
+
+        assert_eq!(
+            builder.build_commands(true),
+            vec![
+                BuildCommand::Barrier(
+                    undefined..transfer_write,
+                    PipelineStage::TOP_OF_PIPE..PipelineStage::TRANSFER,
+                ),
+                BuildCommand::Copy,
+                BuildCommand::Barrier(transfer_write..sampled, PipelineStage::TRANSFER..shaders),
+            ]
+        );
+        assert_eq!(
+            builder.build_commands(false),
+            vec![BuildCommand::Barrier(
+                undefined..sampled,
+                PipelineStage::TOP_OF_PIPE..shaders,
+            )]
+        );
+
+        let depth = TextureBuilder::new(Kind::D2(4, 4, 1, 1)).with_format(Format::D32Float);
+        let tests = PipelineStage::EARLY_FRAGMENT_TESTS | PipelineStage::LATE_FRAGMENT_TESTS;
+        match depth.build_commands(true).last() {
+            Some(BuildCommand::Barrier(states, stages)) => {
+                assert_eq!(states.end.1, Layout::DepthStencilReadOnlyOptimal);
+                assert_eq!(stages.end, tests | shaders);
+            }
+            other => panic!("Unexpected command {:?}", other),
+        }
+    }
+
+    /// Memory of device with `heaps` heaps and memory types of `(properties, heap)`.
+    fn memory_properties(types: &[(Properties, usize)], heaps: usize) -> MemoryProperties {
+        MemoryProperties {
+            memory_types: types
+                .iter()
+                .map(|&(properties, heap_index)| MemoryType {
+                    properties,
+                    heap_index,
+                })
+                .collect(),
+            memory_heaps: vec![1 << 30; heaps],
+        }
+    }
+
+    #[test]
+    fn unified_memory_texture_is_linear_in_final_state() {
+        let builder =
+            TextureBuilder::from_bytes(Kind::D2(4, 4, 1, 1), Format::Rgba8Unorm, vec![0; 64])
+                .unwrap();
+        let unified = memory_properties(
+            &[
+                (Properties::DEVICE_LOCAL, 0),
+                (Properties::DEVICE_LOCAL | Properties::CPU_VISIBLE, 0),
+            ],
+            1,
+        );
+        // Discrete GPU with resizable BAR exposing all of its memory to host.
+        let discrete = memory_properties(
+            &[
+                (Properties::DEVICE_LOCAL, 0),
+                (Properties::DEVICE_LOCAL | Properties::CPU_VISIBLE, 0),
+                (Properties::CPU_VISIBLE | Properties::COHERENT, 1),
+            ],
+            2,
+        );
+
+        assert!(builder.writes_directly(true, &unified));
+        assert!(!builder.writes_directly(true, &discrete));
+        assert!(!builder.writes_directly(false, &unified));
+        assert!(!builder
+            .clone()
+            .with_prefer_unified_memory(false)
+            .writes_directly(true, &unified));
+        assert!(!builder
+            .clone()
+            .with_generate_mips(true)
+            .writes_directly(true, &unified));
+
+        let parts = builder.unified_parts();
+        assert_eq!(parts.tiling, Tiling::Linear);
+        assert_eq!(parts.layout, Layout::ShaderReadOnlyOptimal);
+        assert_eq!(parts.access, Access::SHADER_READ);
+        assert!(layout_stages(parts.layout).contains(PipelineStage::FRAGMENT_SHADER));
+
+        let parts = builder.with_final_layout(Layout::General).unified_parts();
+        assert_eq!(parts.tiling, Tiling::Linear);
+        assert_eq!(parts.layout, Layout::General);
+        assert_eq!(parts.access, Access::SHADER_READ | Access::SHADER_WRITE);
+    }
+
+    /// Builder of 16x16 texture with correctly sized data for `levels` levels.
+    fn explicit_levels(format: Format, layers: Layer, levels: Level) -> TextureBuilder<'static> {
+        let kind = Kind::D2(16, 16, layers, 1);
+        let extent = kind.extent();
+        let mut builder = TextureBuilder::new(kind)
+            .with_format(format)
+            .with_data_bytes(vec![0; format::level_size(format, extent, layers)]);
+        for level in 1..levels {
+            let size = format::level_size(format, extent.at_level(level), layers);
+            builder.set_mip_data(level, vec![0u8; size]);
+        }
+        builder
+    }
+
+    #[test]
+    fn explicit_level_sizes() {
+        // Format, level and size of single layer of the level in bytes.
+        let table = [
+            (Format::Rgba8Unorm, 1, 256),
+            (Format::Rgba8Unorm, 4, 4),
+            (Format::R16Float, 2, 32),
+            (Format::R16Float, 4, 2),
+            (Format::Bc1RgbUnorm, 2, 8),
+            (Format::Bc1RgbUnorm, 3, 8),
+            (Format::Bc1RgbUnorm, 4, 8),
+            (Format::Bc7Unorm, 1, 64),
+            (Format::Bc7Unorm, 4, 16),
+            (Format::Astc6x6Unorm, 1, 64),
+            (Format::Astc6x6Unorm, 3, 16),
+        ];
+        for &(format, level, size) in &table {
+            explicit_levels(format, 1, level + 1)
+                .validate()
+                .unwrap_or_else(|error| panic!("{:?} level {}: {}", format, level, error));
+            for &actual in &[size - 1, size + 1] {
+                let builder =
+                    explicit_levels(format, 1, level + 1).with_mip_data(level, vec![0u8; actual]);
+                match builder.validate() {
+                    Err(TextureError::LevelSize {
+                        level: error_level,
+                        expected,
+                        actual: error_actual,
+                    }) => assert_eq!(
+                        (error_level, expected, error_actual),
+                        (level, size, actual),
+                        "{:?}",
+                        format
+                    ),
+                    other => panic!("{:?} level {}: {:?}", format, level, other),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn explicit_level_layers_and_count() {
+        let builder = explicit_levels(Format::Rgba8Unorm, 2, 2).with_mip_data(1, vec![0u8; 256]);
+        match builder.validate() {
+            Err(TextureError::LayerCount {
+                level: 1,
+                expected: 2,
+                actual: 1,
+            }) => {}
+            other => panic!("{:?}", other),
+        }
+
+        match explicit_levels(Format::Rgba8Unorm, 1, 6).validate() {
+            Err(TextureError::TooManyLevels { levels: 6, max: 5 }) => {}
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn upload_regions_cover_resident_levels() {
+        let builder = explicit_levels(Format::Rgba8Unorm, 2, 3).with_min_resident_level(1);
+        let uploads = builder.upload_levels().unwrap();
+        let regions = builder.upload_regions(&uploads, &[0, 512]);
+        assert_eq!(regions.len(), 2);
+        for (region, &(level, offset)) in regions.iter().zip(&[(1, 0), (2, 512)]) {
+            assert_eq!(region.image_layers.level, level);
+            assert_eq!(region.image_layers.layers, 0..2);
+            assert_eq!(region.buffer_offset, offset);
+            assert_eq!(region.image_extent, builder.kind.extent().at_level(level));
+        }
+    }
+
+    #[test]
+    fn upload_stats_count_level_bytes() {
+        let builder = explicit_levels(Format::Rgba8Unorm, 2, 3);
+        let stats = builder.upload_stats(&builder.upload_levels().unwrap());
+        assert_eq!(
+            stats,
+            UploadStats {
+                bytes_uploaded: (1024 + 256 + 64) * 2,
+                staging_buffer_size: (1024 + 256 + 64) * 2,
+                subresources: 6,
+            }
+        );
+
+        let resident = builder.with_min_resident_level(2);
+        let stats = resident.upload_stats(&resident.upload_levels().unwrap());
+        assert_eq!(stats.bytes_uploaded, 64 * 2);
+        assert_eq!(stats.subresources, 2);
+    }
+
+    #[test]
+    fn image_params_must_match_builder() {
+        let builder = explicit_levels(Format::Rgba8Unorm, 1, 3);
+        let params = ImageParams {
+            kind: builder.kind,
+            levels: 3,
+            format: Format::Rgba8Unorm,
+            usage: Usage::TRANSFER_DST | Usage::SAMPLED,
+        };
+        builder.check_image_params(params).unwrap();
+
+        let mismatched = [
+            ImageParams {
+                kind: Kind::D2(8, 8, 1, 1),
+                ..params
+            },
+            ImageParams {
+                format: Format::Rgba8Srgb,
+                ..params
+            },
+            ImageParams {
+                levels: 1,
+                ..params
+            },
+        ];
+        for &params in &mismatched {
+            match builder.check_image_params(params) {
+                Err(TextureError::Incompatible { .. }) => {}
+                other => panic!("{:?}: {:?}", params, other),
+            }
+        }
+
+        match builder.check_image_params(ImageParams {
+            usage: Usage::SAMPLED,
+            ..params
+        }) {
+            Err(TextureError::MissingUsage { .. }) => {}
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn cast_vec_copies_aligned_texels() {
+        let texels = vec![[1.0f32, 0.5, 0.25, 0.0]; 3];
+        let bytes = cast_vec(texels.clone());
+        assert_eq!(bytes.len(), 48);
+        assert_eq!(&bytes[..], cast_slice(&texels));
+        assert_eq!(cast_vec(vec![[1u8, 2, 3, 4]]), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn retained_source_recreates_same_texture() {
+        let builder = explicit_levels(Format::Bc1RgbUnorm, 2, 3).with_retain_source(true);
+        let source = builder.retained_source().unwrap();
+        assert_eq!(*source, builder);
+        assert_eq!((source.kind, source.format), (builder.kind, builder.format));
+        assert!(builder
+            .with_retain_source(false)
+            .retained_source()
+            .is_none());
+    }
+
+    /// Only needs to compile: data may be dropped as soon as `build_owned` returns.
+    #[allow(dead_code)]
+    fn build_owned_from_temporary<B: Backend>(
+        family: QueueFamilyId,
+        factory: &mut Factory<B>,
+    ) -> Result<Texture<B>, TextureError> {
+        let data = vec![0u8; 64];
+        let texture = TextureBuilder::new(Kind::D2(4, 4, 1, 1))
+            .with_data(&data[..])
+            .with_retain_source(true)
+            .build_owned(family, factory);
+        drop(data);
+        texture
+    }
+
+    #[test]
+    fn owned_builder_outlives_data() {
+        let data = vec![7u8; 64];
+        let builder = TextureBuilder::new(Kind::D2(4, 4, 1, 1)).with_data(&data[..]);
+        let owned = builder.clone().into_static();
+        drop(builder);
+        drop(data);
+        assert_eq!(&owned.data[..], &[7u8; 64][..]);
+    }
+
+    #[test]
+    fn storage_flags_compose_with_cube() {
+        let flags = StorageFlags::CUBE_VIEW | StorageFlags::MUTABLE_FORMAT;
+        let cube = TextureBuilder::new(Kind::D2(4, 4, 6, 1))
+            .with_data_bytes(vec![0u8; 4 * 4 * 4 * 6])
+            .with_storage_flags(flags);
+        cube.validate().unwrap();
+        assert_eq!(cube.storage_flags(), flags);
+
+        let flat = cube.clone().with_kind(Kind::D2(4, 2, 6, 1));
+        match flat.validate() {
+            Err(TextureError::StorageFlagsConflict {
+                flags: error_flags, ..
+            }) => {
+                assert_eq!(error_flags, flags)
+            }
+            other => panic!("{:?}", other),
+        }
+        assert!(!storage_flags_fit(
+            StorageFlags::ARRAY_2D,
+            Kind::D2(4, 4, 6, 1)
+        ));
+        assert!(storage_flags_fit(StorageFlags::ARRAY_2D, Kind::D3(4, 4, 4)));
+    }
+
+    #[test]
+    fn big_endian_r16_is_swapped() {
+        let values = [0x0102u16, 0xa0b0, 0xfffe, 0x0001];
+        let big = values
+            .iter()
+            .flat_map(|value| value.to_be_bytes().to_vec())
+            .collect::<Vec<_>>();
+        let native = values
+            .iter()
+            .flat_map(|value| value.to_ne_bytes().to_vec())
+            .collect::<Vec<_>>();
+        let builder = TextureBuilder::new(Kind::D2(2, 2, 1, 1))
+            .with_format(Format::R16Uint)
+            .with_data_bytes(big)
+            .with_source_endianness(Endianness::Big);
+        builder.validate().unwrap();
+        assert_eq!(&builder.upload_levels().unwrap()[0].1[..], &native[..]);
+
+        match builder
+            .with_format(Format::Bc1RgbUnorm)
+            .with_kind(Kind::D2(4, 4, 1, 1))
+            .validate()
+        {
+            Err(TextureError::EndiannessUnsupported { .. }) => {}
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn volume_from_pushed_slices() {
+        let mut builder = TextureBuilder::new(Kind::D3(2, 2, 4));
+        for depth in 0..4u8 {
+            builder.push_slice(&[depth; 16]).unwrap();
+        }
+        builder.validate().unwrap();
+        assert_eq!(builder.data.len(), 64);
+        assert_eq!(&builder.data[48..], &[3u8; 16][..]);
+
+        match builder.push_slice(&[4; 16]) {
+            Err(TextureError::TooManySlices { depth: 4 }) => {}
+            other => panic!("{:?}", other.map(|_| ())),
+        }
+        match TextureBuilder::new(Kind::D3(2, 2, 4)).push_slice(&[0; 8]) {
+            Err(TextureError::SliceSize {
+                expected: 16,
+                actual: 8,
+            }) => {}
+            other => panic!("{:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn display_shows_format_and_extent() {
+        let summary = explicit_levels(Format::Bc7Unorm, 2, 3).to_string();
+        assert!(summary.contains("Bc7Unorm"), "{}", summary);
+        assert!(summary.contains("16x16x1"), "{}", summary);
+        assert!(summary.contains("3 levels, 2 layers"), "{}", summary);
+    }
+
+    #[test]
+    fn compressed_regions_are_block_aligned() {
+        let level = Extent {
+            width: 16,
+            height: 16,
+            depth: 1,
+        };
+        let extent = |width, height| Extent {
+            width,
+            height,
+            depth: 1,
+        };
+        let offset = |x, y| Offset { x, y, z: 0 };
+        let bc1 = Format::Bc1RgbUnorm;
+
+        match check_block_alignment(bc1, level, offset(0, 0), extent(2, 2)) {
+            Err(TextureError::UnalignedRegion { block: (4, 4), .. }) => {}
+            other => panic!("{:?}", other),
+        }
+        assert!(check_block_alignment(bc1, level, offset(2, 4), extent(4, 4)).is_err());
+        check_block_alignment(bc1, level, offset(4, 8), extent(4, 4)).unwrap();
+        // Partial block at the level edge.
+        let edge = Extent {
+            width: 14,
+            height: 14,
+            depth: 1,
+        };
+        check_block_alignment(bc1, edge, offset(12, 12), extent(2, 2)).unwrap();
+        check_block_alignment(Format::Rgba8Unorm, level, offset(1, 3), extent(2, 2)).unwrap();
+    }
+
+    #[test]
+    fn unspecified_levels_are_cleared() {
+        let builder = TextureBuilder::new(Kind::D2(4, 4, 1, 1))
+            .with_format(Format::Rgba8Unorm)
+            .with_data_bytes(vec![0u8; 64])
+            .with_max_levels(3)
+            .with_clear_unspecified_levels([1.0, 0.0, 0.0, 1.0]);
+        builder.validate().unwrap();
+        let uploads = builder.upload_levels().unwrap();
+        assert_eq!(uploads.len(), 3);
+        assert_eq!(&uploads[1].1[..], &[255, 0, 0, 255].repeat(4)[..]);
+        assert_eq!(&uploads[2].1[..], &[255, 0, 0, 255][..]);
+    }
+
+    #[test]
+    fn make_tileable_matches_borders_and_keeps_interior() {
+        let data = (0..64u32).map(|i| (i * 37 % 251) as u8).collect::<Vec<_>>();
+        let source = TextureBuilder::new(Kind::D2(8, 8, 1, 1))
+            .with_format(Format::R8Unorm)
+            .with_data_bytes(data.clone());
+        assert!(!source.is_tileable(0.0).unwrap());
+
+        let tileable = source.clone().with_make_tileable(2);
+        assert!(tileable.is_tileable(0.0).unwrap());
+        let blended = tileable.base_source();
+        for y in 2..6 {
+            for x in 2..6 {
+                assert_eq!(blended[y * 8 + x], data[y * 8 + x], "texel {}, {}", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn border_grows_extent() {
+        let builder = TextureBuilder::new(Kind::D2(2, 2, 1, 1))
+            .with_format(Format::R8Unorm)
+            .with_data_bytes(vec![1u8, 2, 3, 4]);
+
+        let solid = builder
+            .clone()
+            .with_border(1, BorderMode::Solid([9, 0, 0, 0]));
+        let padded = solid.padded().unwrap().unwrap();
+        assert_eq!(padded.kind, Kind::D2(4, 4, 1, 1));
+        assert_eq!(
+            &padded.data[..],
+            &[9, 9, 9, 9, 9, 1, 2, 9, 9, 3, 4, 9, 9, 9, 9, 9][..]
+        );
+        assert_eq!(solid.content_rect().0, Offset { x: 1, y: 1, z: 0 });
+
+        let clamped = builder.with_border(1, BorderMode::ClampEdge);
+        let padded = clamped.padded().unwrap().unwrap();
+        assert_eq!(
+            &padded.data[..],
+            &[1, 1, 2, 2, 1, 1, 2, 2, 3, 3, 4, 4, 3, 3, 4, 4][..]
+        );
+    }
+
+    #[test]
+    fn presets_select_format_mips_and_swizzle() {
+        let builder = TextureBuilder::new(Kind::D2(4, 4, 1, 1));
+        let albedo = builder.clone().with_preset(TexturePreset::Albedo);
+        assert_eq!(albedo.format, Format::Rgba8Srgb);
+        assert!(albedo.generate_mips && !albedo.mip_renormalize);
+        assert_eq!(albedo.mip_gamma_correct, Some(true));
+        assert_eq!(albedo.swizzle, Swizzle::NO);
+
+        let normal = builder.clone().with_preset(TexturePreset::NormalMap);
+        assert_eq!(normal.format, Format::Rgba8Unorm);
+        assert!(normal.generate_mips && normal.mip_renormalize);
+        assert_eq!(normal.mip_gamma_correct, Some(false));
+        assert_eq!(normal.swizzle, Swizzle::NO);
+
+        let packed = builder
+            .clone()
+            .with_preset(TexturePreset::MetallicRoughness);
+        assert_eq!(packed.format, Format::Rgba8Unorm);
+        assert!(packed.generate_mips && !packed.mip_renormalize);
+        assert_eq!(packed.mip_gamma_correct, Some(false));
+        assert_eq!(packed.swizzle, Swizzle::NO);
+
+        let occlusion = builder.clone().with_preset(TexturePreset::Occlusion);
+        assert_eq!(occlusion.format, Format::Rgba8Unorm);
+        assert!(occlusion.generate_mips && !occlusion.mip_renormalize);
+        assert_eq!(occlusion.mip_gamma_correct, Some(false));
+        assert_eq!(occlusion.swizzle, Swizzle::rrr1());
+
+        let overridden = builder
+            .with_preset(TexturePreset::Albedo)
+            .with_format(Format::Bgra8Srgb)
+            .with_generate_mips(false);
+        assert_eq!(overridden.format, Format::Bgra8Srgb);
+        assert!(!overridden.generate_mips);
+        assert_eq!(overridden.mip_gamma_correct, Some(true));
+    }
+
+    #[test]
+    fn reversed_bounds_cover_same_region() {
+        let start = Offset { x: 16, y: 0, z: 0 };
+        let end = Offset { x: 0, y: 16, z: 1 };
+        let expected = (
+            Offset { x: 0, y: 0, z: 0 },
+            Extent {
+                width: 16,
+                height: 16,
+                depth: 1,
+            },
+        );
+        assert_eq!(bounds_region(&(start..end)), expected);
+        assert_eq!(bounds_region(&(end..start)), expected);
+    }
+
+    #[test]
+    fn default_builder_is_single_texel() {
+        let builder = TextureBuilder::default();
+        assert_eq!(builder.kind, Kind::D2(1, 1, 1, 1));
+        assert_eq!(builder.format, Format::Rgba8Srgb);
+        assert_eq!((builder.data_width(), builder.data_height()), (1, 1));
+        assert!(builder.data.is_empty() && builder.mip_data.is_empty());
+        assert!(!builder.generate_mips);
+
+        let kind = Kind::D3(4, 2, 2);
+        let from = TextureBuilder::from(kind);
+        assert_eq!(from.kind, kind);
+        assert_eq!((from.data_width(), from.data_height()), (4, 2));
+        assert_eq!(from.format, TextureBuilder::new(kind).format);
+    }
+
+    #[test]
+    fn data_for_every_level_index_is_rejected() {
+        let mut builder = TextureBuilder::new(Kind::D2(16, 16, 1, 1));
+        builder.set_mip_data(Level::MAX, vec![0u8; 4]);
+        match builder.validate() {
+            Err(TextureError::TooManyLevels { levels, max }) => {
+                assert_eq!((levels, max), (Level::MAX, 5));
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn quality_tier_skips_top_levels() {
+        assert_eq!(tier_levels(5, 2).unwrap(), 2..5);
+        assert_eq!(tier_levels(5, 4).unwrap(), 4..5);
+        match tier_levels(5, 5) {
+            Err(TextureError::TooManyLevels { levels: 6, max: 5 }) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn from_bytes_requires_exact_size() {
+        let kind = Kind::D2(4, 4, 2, 1);
+        let builder = TextureBuilder::from_bytes(kind, Format::Rgba8Unorm, vec![7; 128]).unwrap();
+        assert_eq!(builder.kind, kind);
+        assert_eq!(builder.format, Format::Rgba8Unorm);
+        assert_eq!(&builder.data[..], &[7; 128][..]);
+
+        for &len in &[127, 129] {
+            match TextureBuilder::from_bytes(kind, Format::Rgba8Unorm, vec![0; len]) {
+                Err(TextureError::LevelSize {
+                    level: 0,
+                    expected: 128,
+                    actual,
+                }) => assert_eq!(actual, len),
+                other => panic!("Unexpected result {:?}", other),
+            }
+        }
+    }
+
+    /// Apply `OomPolicy` of `builder` to build failing first `attempts` times for lack of memory.
+    /// Returns extent of the built texture and number of evictions.
+    fn build_failing(
+        builder: &TextureBuilder<'_>,
+        attempts: usize,
+    ) -> (Result<Extent, TextureError>, usize) {
+        let mut state = (0, 0);
+        let result = builder.apply_oom_policy(
+            &mut state,
+            |&mut (ref mut calls, _), tail| {
+                let builder = tail.unwrap_or(builder);
+                *calls += 1;
+                if *calls <= attempts {
+                    Err(TextureError::OutOfMemory {
+                        size: image_size(builder.kind, builder.format, builder.levels()),
+                        kind: builder.kind,
+                        format: builder.format,
+                        name: builder.name.clone(),
+                    })
+                } else {
+                    Ok(builder.kind.extent())
+                }
+            },
+            |&mut (_, ref mut evictions), _| *evictions += 1,
+        );
+        (result, state.1)
+    }
+
+    #[test]
+    fn out_of_memory_policies() {
+        let builder = explicit_levels(Format::Rgba8Unorm, 1, 5).with_name("atlas");
+
+        let (result, evictions) = build_failing(&builder, 1);
+        match result {
+            Err(TextureError::OutOfMemory { size, name, .. }) => {
+                assert_eq!(size, 1024 + 256 + 64 + 16 + 4);
+                assert_eq!(name.as_deref(), Some("atlas"));
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+        assert_eq!(evictions, 0);
+
+        let retry = builder.clone().with_oom_policy(OomPolicy::Retry);
+        let (result, evictions) = build_failing(&retry, 1);
+        assert_eq!(result.unwrap(), builder.kind.extent());
+        assert_eq!(evictions, 1);
+        let (result, evictions) = build_failing(&retry, 2);
+        assert!(result.is_err());
+        assert_eq!(evictions, 1);
+
+        let tail = builder.clone().with_oom_policy(OomPolicy::MipTail(4));
+        let (result, evictions) = build_failing(&tail, 1);
+        assert_eq!(
+            result.unwrap(),
+            Extent {
+                width: 4,
+                height: 4,
+                depth: 1
+            }
+        );
+        assert_eq!(evictions, 0);
+        let short =
+            explicit_levels(Format::Rgba8Unorm, 1, 3).with_oom_policy(OomPolicy::MipTail(2));
+        let (result, _) = build_failing(&short, 1);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn gradient_midpoint_is_gray() {
+        let stops = [(0.0, [0.0, 0.0, 0.0, 1.0]), (1.0, [1.0; 4])];
+        let builder = TextureBuilder::gradient_1d(3, &stops, Format::Rgba8Unorm).unwrap();
+        assert_eq!(builder.kind, Kind::D1(3, 1));
+        assert_eq!(&builder.data[4..8], &[128, 128, 128, 255][..]);
+        assert_eq!(&builder.data[..4], &[43, 43, 43, 255][..]);
+
+        let invalid = [
+            [(0.5, [0.0; 4]), (0.25, [1.0; 4])],
+            [(0.0, [0.0; 4]), (1.5, [1.0; 4])],
+        ];
+        for stops in &invalid {
+            match TextureBuilder::gradient_1d(3, stops, Format::Rgba8Unorm) {
+                Err(TextureError::InvalidGradientStop { index: 1, .. }) => {}
+                other => panic!("Unexpected result {:?}", other),
+            }
+        }
+        match TextureBuilder::gradient_1d(3, &[], Format::Rgba8Unorm) {
+            Err(TextureError::EmptyGradient) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn reinterpret_requires_mutable_format() {
+        let (unorm, srgb) = (Format::Rgba8Unorm, Format::Rgba8Srgb);
+        match check_reinterpret(unorm, StorageFlags::empty(), srgb) {
+            Err(TextureError::MissingStorageFlags { flags }) => {
+                assert_eq!(flags, StorageFlags::MUTABLE_FORMAT);
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+        check_reinterpret(unorm, StorageFlags::MUTABLE_FORMAT, srgb).unwrap();
+        check_reinterpret(unorm, StorageFlags::empty(), unorm).unwrap();
+        match check_reinterpret(unorm, StorageFlags::MUTABLE_FORMAT, Format::Rg8Unorm) {
+            Err(TextureError::IncompatibleFormats { .. }) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn builders_without_data_are_valid() {
+        let transient =
+            TextureBuilder::transient_attachment(Kind::D2(4, 4, 1, 1), Format::D32Float);
+        transient.validate().unwrap();
+
+        let target = TextureBuilder::new(Kind::D2(4, 4, 1, 1)).with_format(Format::Rgba8Unorm);
+        target.validate().unwrap();
+        assert!(target.upload_levels().unwrap().is_empty());
+        assert!(!target.writes_directly(true, &memory_properties(&[(Properties::all(), 0)], 1)));
+
+        let cleared = target.with_clear_unspecified_levels([0.0, 0.0, 1.0, 1.0]);
+        let uploads = cleared.upload_levels().unwrap();
+        assert_eq!(uploads.len(), 1);
+        assert_eq!(&uploads[0].1[..], &[0, 0, 255, 255].repeat(16)[..]);
+
+        let generated = TextureBuilder::new(Kind::D2(4, 4, 1, 1)).with_generate_mips(true);
+        match generated.validate() {
+            Err(TextureError::DataSize { actual: 0, .. }) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn mipgen_dispatch_covers_levels() {
+        let extent = Kind::D2(16, 16, 2, 1).extent();
+        let groups = (1..4)
+            .map(|level| dispatch_groups(extent.at_level(level), 2, [4, 4, 1]))
+            .collect::<Vec<_>>();
+        assert_eq!(groups, [[2, 2, 2], [1, 1, 2], [1, 1, 2]]);
+
+        let odd = Extent {
+            width: 13,
+            height: 7,
+            depth: 3,
+        };
+        assert_eq!(dispatch_groups(odd, 1, [8, 8, 2]), [2, 1, 2]);
+    }
+
+    #[test]
+    fn mipgen_reads_each_level_after_it_is_written() {
+        let state = (Access::SHADER_READ, Layout::ShaderReadOnlyOptimal);
+        let read = (Access::SHADER_READ, Layout::General);
+        let write = (Access::SHADER_WRITE, Layout::General);
+        let undefined = (Access::empty(), Layout::Undefined);
+        let all = PipelineStage::all();
+        let compute = PipelineStage::COMPUTE_SHADER;
+
+        assert_eq!(
+            mipgen_commands(3, state),
+            vec![
+                MipgenCommand::Barrier(0..1, state..read, all..compute),
+                MipgenCommand::Barrier(1..3, undefined..write, all..compute),
+                MipgenCommand::Dispatch(1),
+                MipgenCommand::Barrier(1..2, write..read, compute..compute),
+                MipgenCommand::Dispatch(2),
+                MipgenCommand::Barrier(2..3, write..read, compute..compute),
+                MipgenCommand::Barrier(0..3, read..state, compute..all),
+            ]
+        );
+    }
+
+    #[test]
+    fn flat_normal_points_up() {
+        let builder = TextureBuilder::flat_normal();
+        assert_eq!(builder.kind, Kind::D2(1, 1, 1, 1));
+        assert_eq!(builder.format, Format::Rgba8Unorm);
+        assert_eq!(&*builder.data, &[128, 128, 255, 255][..]);
+        builder.validate().unwrap();
+    }
+
+    #[test]
+    fn lod_clamp_is_validated() {
+        let builder =
+            TextureBuilder::from_bytes(Kind::D2(4, 4, 1, 1), Format::Rgba8Unorm, vec![0; 64])
+                .unwrap();
+        for &(min_lod, max_lod) in &[(2.0, 1.0), (f32::NAN, 1.0), (0.0, f32::NAN)] {
+            let clamp = LodClamp::new(min_lod, max_lod);
+            match builder.clone().with_lod_clamp(clamp).validate() {
+                Err(TextureError::InvalidLodClamp { .. }) => {}
+                other => panic!("Unexpected result {:?}", other),
+            }
+        }
+        let clamp = LodClamp {
+            lod_bias: -0.5,
+            ..LodClamp::new(1.0, 1.0)
+        };
+        let builder = builder.with_lod_clamp(clamp);
+        builder.validate().unwrap();
+        assert_eq!(builder.lod_clamp, Some(clamp));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn lod_clamp_serde_round_trip() {
+        extern crate serde_json;
+
+        let clamp = LodClamp {
+            lod_bias: 0.25,
+            ..LodClamp::new(0.5, 4.0)
+        };
+        let builder = TextureBuilder::new(Kind::D2(4, 4, 1, 1)).with_lod_clamp(clamp);
+        let json = serde_json::to_string(&builder).unwrap();
+        let decoded: TextureBuilder = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.lod_clamp, Some(clamp));
+        assert_eq!(decoded, builder);
+    }
+
+    #[test]
+    fn base_size_does_not_overflow() {
+        let builder = TextureBuilder::new(Kind::D2(16384, 16384, 1, 1))
+            .with_format(Format::Rgba32Float)
+            .with_data(vec![0; 16]);
+        match builder.check_base_size() {
+            Err(TextureError::DataSize { required, .. }) => {
+                assert_eq!(required as u64, 1 << 32);
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+
+        let builder = builder.with_kind(Kind::D3(1 << 20, 1 << 20, 1 << 20));
+        match builder.check_base_size() {
+            Err(TextureError::DimensionsTooLarge { layers: 1, .. }) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn data_chunks_are_concatenated() {
+        let bands = (0..4u8)
+            .map(|band| vec![band; 16 * 4 * 4])
+            .collect::<Vec<_>>();
+        let chunks = bands.iter().map(Vec::as_slice).collect::<Vec<_>>();
+        let builder = TextureBuilder::new(Kind::D2(16, 16, 1, 1))
+            .with_format(Format::Rgba8Unorm)
+            .with_data_chunks(&chunks);
+        builder.validate().unwrap();
+        assert_eq!(builder.data.len(), 16 * 16 * 4);
+        for (row, texels) in builder.data.chunks(16 * 4).enumerate() {
+            assert!(texels.iter().all(|&byte| byte as usize == row / 4));
+        }
+
+        let short = TextureBuilder::new(Kind::D2(16, 16, 1, 1))
+            .with_format(Format::Rgba8Unorm)
+            .with_data_chunks(&chunks[..3]);
+        match short.validate() {
+            Err(TextureError::DataSize {
+                required: 1024,
+                actual: 768,
+                ..
+            }) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn pre_transform_defaults_to_identity() {
+        let mut builder = TextureBuilder::new(Kind::D2(1, 1, 1, 1));
+        assert_eq!(builder.pre_transform, PreTransform::Identity);
+        for &rotation in &[
+            PreTransform::Rotate90,
+            PreTransform::Rotate180,
+            PreTransform::Rotate270,
+            PreTransform::Identity,
+        ] {
+            builder.set_pre_transform(rotation);
+            assert_eq!(builder.clone_params().pre_transform, rotation);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn pre_transform_serde_round_trip() {
+        extern crate serde_json;
+
+        let builder =
+            TextureBuilder::new(Kind::D2(1, 1, 1, 1)).with_pre_transform(PreTransform::Rotate270);
+        let json = serde_json::to_string(&builder).unwrap();
+        let decoded: TextureBuilder = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.pre_transform, PreTransform::Rotate270);
+    }
+
+    #[test]
+    fn combined_depth_stencil_data_requires_opt_in() {
+        let builder = TextureBuilder::new(Kind::D2(4, 4, 1, 1))
+            .with_format(Format::D24UnormS8Uint)
+            .with_data(vec![0u8; 64]);
+        match builder.validate() {
+            Err(TextureError::CombinedAspectUploadUnsupported { format }) => {
+                assert_eq!(format, Format::D24UnormS8Uint);
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+        builder
+            .clone()
+            .with_per_aspect_upload(true)
+            .validate()
+            .unwrap();
+        builder.with_data(Vec::<u8>::new()).validate().unwrap();
+    }
+
+    #[test]
+    fn uploaded_levels_are_resident() {
+        let mut resident = 0;
+        assert_eq!(resident_range(resident), 0..0);
+        for level in (2..5).rev() {
+            resident |= level_mask(level..level + 1);
+        }
+        assert_eq!(resident_range(resident), 2..5);
+
+        // Level past the gap is not resident until the gap is filled.
+        resident |= level_mask(0..1);
+        assert_eq!(resident_range(resident), 2..5);
+        resident |= level_mask(1..2);
+        assert_eq!(resident_range(resident), 0..5);
+        assert_eq!(resident_range(level_mask(63..64)), 63..64);
+    }
+
+    #[test]
+    fn tight_rows_are_block_aligned() {
+        let rows = |format| {
+            let builder = TextureBuilder::new(Kind::D2(6, 6, 1, 1))
+                .with_format(format)
+                .with_data_width(10)
+                .with_tight_rows();
+            let size = format::strided_size(
+                format,
+                builder.kind.extent(),
+                builder.data_width(),
+                builder.data_height(),
+                1,
+            );
+            (builder.data_width(), builder.data_height(), size)
+        };
+        assert_eq!(rows(Format::Rgba8Unorm), (6, 6, 144));
+        assert_eq!(rows(Format::Bc1RgbaUnorm), (8, 8, 32));
+
+        // Tight rows are the default and follow the format set after the kind.
+        let bc1 = TextureBuilder::new(Kind::D2(6, 6, 1, 1)).with_format(Format::Bc1RgbaUnorm);
+        assert_eq!((bc1.data_width(), bc1.data_height()), (8, 8));
+        let rgba = bc1.with_format(Format::Rgba8Unorm);
+        assert_eq!((rgba.data_width(), rgba.data_height()), (6, 6));
+    }
+
+    #[test]
+    fn immutable_sampler_info_is_clamped() {
+        let sampling = TextureSampling::new(WrapMode::Clamp);
+        let unclamped = clamped_sampler_info(sampling, None);
+        assert_eq!(unclamped, sampling.sampler_info());
+        assert_eq!(unclamped.wrap_mode.0, WrapMode::Clamp);
+
+        let clamp = LodClamp {
+            min_lod: 1.0,
+            max_lod: 4.0,
+            lod_bias: -0.5,
+        };
+        let clamped = clamped_sampler_info(sampling, Some(clamp));
+        assert_eq!(clamped.lod_bias, (-0.5).into());
+        assert_eq!(clamped.lod_range, 1.0.into()..4.0.into());
+        assert_eq!(clamped.wrap_mode, unclamped.wrap_mode);
+    }
+
+    #[test]
+    fn base_data_size_is_checked() {
+        let builder = |len| {
+            TextureBuilder::new(Kind::D2(128, 128, 1, 1))
+                .with_format(Format::Rgba8Srgb)
+                .with_data(vec![0u8; len])
+        };
+        builder(65_536).validate().unwrap();
+
+        let short = builder(49_152).validate().unwrap_err();
+        assert_eq!(
+            short.to_string(),
+            "Data is 49,152 bytes but 128x128x1 Rgba8Srgb texture with 1 layers requires 65,536"
+        );
+
+        let long = builder(65_540);
+        match long.validate() {
+            Err(TextureError::DataSize {
+                required: 65_536,
+                allowed: 65_536,
+                actual: 65_540,
+                ..
+            }) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+        long.with_remaining_bytes_ignored(true).validate().unwrap();
+
+        // Padded rows are counted with their stride.
+        let padded = |len| builder(len).with_data_width(130);
+        padded(130 * 128 * 4).validate().unwrap();
+        for &len in &[130 * 128 * 4 - 1, 130 * 128 * 4 + 1] {
+            match padded(len).validate() {
+                Err(TextureError::DataSize {
+                    required, actual, ..
+                }) => assert_eq!((required, actual), (130 * 128 * 4, len)),
+                other => panic!("Unexpected result {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn byte_containers_are_borrowed() {
+        let kind = Kind::D2(2, 2, 1, 1);
+        let shared = Arc::new(vec![1u8; 16]);
+        let builder = TextureBuilder::new(kind)
+            .with_format(Format::Rgba8Unorm)
+            .with_data_ref(&*shared);
+        builder.validate().unwrap();
+        match builder.data {
+            Cow::Borrowed(data) => assert_eq!(data.as_ptr(), shared.as_ptr()),
+            Cow::Owned(_) => panic!("Shared data is copied"),
+        }
+
+        let boxed: Box<[u8]> = vec![2u8; 16].into_boxed_slice();
+        let builder = TextureBuilder::new(kind).with_data_ref(&boxed);
+        assert_eq!(&builder.data[..], &boxed[..]);
+
+        let owned = TextureBuilder::new(kind).with_data_bytes(vec![3u8; 16]);
+        assert!(matches!(owned.data, Cow::Owned(_)));
+    }
+
+    #[test]
+    fn depth_stencil_aspects() {
+        let format = Format::D24UnormS8Uint;
+        assert_eq!(aspects_for(format), Aspects::DEPTH | Aspects::STENCIL);
+        assert_eq!(data_aspects(format), Aspects::DEPTH);
+        assert_eq!(view_aspects(format, false), Aspects::DEPTH);
+        assert_eq!(
+            view_aspects(format, true),
+            Aspects::DEPTH | Aspects::STENCIL
+        );
+        assert_eq!(data_aspects(Format::Rgba8Unorm), Aspects::COLOR);
+    }
+
+    #[test]
+    fn checker_cell_must_not_be_empty() {
+        let checkerboard =
+            |cell| TextureBuilder::checkerboard(4, cell, [0; 4], [255; 4], Format::Rgba8Unorm);
+        match checkerboard(0) {
+            Err(TextureError::EmptyCheckerCell) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+        checkerboard(1).unwrap().validate().unwrap();
+    }
+
+    #[test]
+    fn red_is_encoded_for_bgra() {
+        let red = [255, 0, 0, 255];
+        let bgra_red = [0, 0, 255, 255];
+        let solid =
+            TextureBuilder::solid_color(Kind::D2(2, 2, 1, 1), Format::Bgra8Unorm, red).unwrap();
+        assert_eq!(&solid.data[..], &bgra_red.repeat(4)[..]);
+
+        let checkerboard =
+            TextureBuilder::checkerboard(2, 1, red, [0; 4], Format::Bgra8Srgb).unwrap();
+        assert_eq!(&checkerboard.data[..4], &bgra_red[..]);
+        assert_eq!(&checkerboard.data[12..], &bgra_red[..]);
+
+        let converted = TextureBuilder::new(Kind::D2(1, 1, 1, 1))
+            .with_format(Format::Bgra8Srgb)
+            .with_source_format(Format::Rgba8Srgb)
+            .with_data(red.to_vec());
+        let uploads = converted.upload_levels().unwrap();
+        assert_eq!(&uploads[0].1[..], &bgra_red[..]);
     }
 }
@@ -0,0 +1,141 @@
+//! Macros embedding textures into binary.
+
+use error::TextureError;
+use TextureBuilder;
+
+/// Embed image file into binary and create `TextureBuilder<'static>` from it.
+/// The path is relative to the file invoking the macro, same as for `include_bytes!`,
+/// and the result is `Result<TextureBuilder<'static>, TextureError>`.
+///
+/// With single argument file is decoded when the macro is evaluated by loader
+/// picked by extension: `.png` (decoded as sRGB) with `load-png` feature
+/// and `.dds` with `load-dds` feature.
+/// Other extensions or a disabled loader feature fail compilation
+/// with message naming the feature to enable.
+///
+/// With kind and format file is tightly packed base level texels
+/// passed to `TextureBuilder::from_bytes`.
+///
+/// ```
+/// #[macro_use]
+/// extern crate gfx_texture;
+/// extern crate gfx_hal as hal;
+///
+/// use hal::format::Format;
+/// use hal::image::Kind;
+///
+/// # #[cfg(feature = "load-png")]
+/// # fn png() {
+/// let checker = include_texture!("../tests/assets/checker.png").unwrap();
+/// checker.validate().unwrap();
+/// # }
+/// # #[cfg(not(feature = "load-png"))]
+/// # fn png() {}
+///
+/// fn main() {
+///     # png();
+///     let raw = include_texture!(
+///         "../tests/assets/checker.png",
+///         Kind::D1(77, 1),
+///         Format::R8Unorm
+///     )
+///     .unwrap();
+///     raw.validate().unwrap();
+/// }
+/// ```
+#[macro_export]
+macro_rules! include_texture {
+    ($path:expr) => {{
+        const LOADER: $crate::macros::Loader = $crate::macros::loader($path);
+        $crate::macros::load(LOADER, include_bytes!($path))
+    }};
+    ($path:expr, $kind:expr, $format:expr) => {
+        $crate::TextureBuilder::from_bytes($kind, $format, &include_bytes!($path)[..])
+    };
+}
+
+/// Loader `include_texture!` decodes file with.
+#[doc(hidden)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Loader {
+    Png,
+    Dds,
+}
+
+/// Pick loader by extension of the path.
+/// Evaluated in constant so failure is reported at compile time.
+#[doc(hidden)]
+pub const fn loader(path: &str) -> Loader {
+    if has_extension(path, "png") {
+        if cfg!(feature = "load-png") {
+            Loader::Png
+        } else {
+            panic!("enable the `load-png` feature of gfx-texture to include PNG files")
+        }
+    } else if has_extension(path, "dds") {
+        if cfg!(feature = "load-dds") {
+            Loader::Dds
+        } else {
+            panic!("enable the `load-dds` feature of gfx-texture to include DDS files")
+        }
+    } else {
+        panic!("include_texture! decodes .png and .dds files, pass kind and format for raw texels")
+    }
+}
+
+/// Decode embedded file with loader picked by `loader`.
+#[doc(hidden)]
+#[cfg_attr(
+    not(any(feature = "load-png", feature = "load-dds")),
+    allow(unused_variables)
+)]
+pub fn load(loader: Loader, data: &'static [u8]) -> Result<TextureBuilder<'static>, TextureError> {
+    match loader {
+        #[cfg(feature = "load-png")]
+        Loader::Png => TextureBuilder::from_png(data, true),
+        #[cfg(feature = "load-dds")]
+        Loader::Dds => TextureBuilder::from_dds(data),
+        #[allow(unreachable_patterns)]
+        _ => unreachable!("Loader of disabled feature is rejected at compile time"),
+    }
+}
+
+/// Check if path ends with `.extension` ignoring ASCII case.
+const fn has_extension(path: &str, extension: &str) -> bool {
+    let (path, extension) = (path.as_bytes(), extension.as_bytes());
+    if path.len() <= extension.len() || path[path.len() - extension.len() - 1] != b'.' {
+        return false;
+    }
+    let start = path.len() - extension.len();
+    let mut index = 0;
+    while index < extension.len() {
+        if path[start + index].to_ascii_lowercase() != extension[index] {
+            return false;
+        }
+        index += 1;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extension_is_matched_ignoring_case() {
+        assert!(has_extension("assets/icon.png", "png"));
+        assert!(has_extension("ICON.PNG", "png"));
+        assert!(!has_extension("icon.png.bak", "png"));
+        assert!(!has_extension("iconpng", "png"));
+        assert!(!has_extension(".png", "dds"));
+        assert!(!has_extension("png", "png"));
+    }
+
+    #[cfg(feature = "load-png")]
+    #[test]
+    fn png_is_decoded() {
+        let builder = include_texture!("../tests/assets/checker.png").unwrap();
+        assert_eq!(builder.kind, ::hal::image::Kind::D2(2, 2, 1, 1));
+        assert_eq!(&builder.data[..8], &[255, 255, 255, 255, 0, 0, 0, 255][..]);
+    }
+}
@@ -0,0 +1,131 @@
+//! Texel data read from memory-mapped files.
+
+use std::fs::File;
+use std::path::Path;
+
+use hal::format::Format;
+use hal::image::Kind;
+
+use memmap::Mmap;
+
+use error::TextureError;
+use format;
+use info::{DataSource, TextureInfo};
+use TextureBuilder;
+
+/// Read-only memory map of file with raw texels.
+/// Builders borrow the map, so it outlives any upload from it.
+/// Texels are copied from mapped pages into staging buffer without intermediate copy,
+/// but uploads are not split into chunks, so staging buffer as large as the file is allocated.
+#[derive(Debug)]
+pub struct MappedFile {
+    map: Mmap,
+}
+
+impl MappedFile {
+    /// Map whole file.
+    /// File must not be modified while mapped.
+    pub fn open<P>(path: P) -> Result<Self, TextureError>
+    where
+        P: AsRef<Path>,
+    {
+        let file = File::open(path)?;
+        let map = unsafe { Mmap::map(&file)? };
+        Ok(MappedFile { map })
+    }
+
+    /// Size of the file in bytes.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Check if the file is empty.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Create builder for image described by `info` with tightly packed
+    /// base level of all layers as the file content.
+    /// File of any other size fails with `TextureError::DataSize`.
+    pub fn builder<'a>(&'a self, info: TextureInfo) -> Result<TextureBuilder<'a>, TextureError> {
+        let extent = info.kind.extent();
+        let layers = info.kind.num_layers();
+        let required = format::level_size(info.format, extent, layers);
+        if self.map.len() != required {
+            Err(TextureError::DataSize {
+                format: info.format,
+                extent,
+                layers,
+                required,
+                allowed: required,
+                actual: self.map.len(),
+            })?;
+        }
+        TextureBuilder::from_parts(
+            info,
+            DataSource::Bytes {
+                data: (&self.map[..]).into(),
+                data_width: extent.width,
+                data_height: extent.height,
+            },
+        )
+    }
+
+    /// Create builder for headerless `.raw` volume of slices following each other.
+    pub fn raw_volume<'a>(
+        &'a self,
+        width: u32,
+        height: u32,
+        depth: u32,
+        format: Format,
+    ) -> Result<TextureBuilder<'a>, TextureError> {
+        let mut info = TextureInfo::new(Kind::D3(width, height, depth));
+        info.format = format;
+        self.builder(info)
+    }
+}
+
+impl AsRef<[u8]> for MappedFile {
+    fn as_ref(&self) -> &[u8] {
+        &self.map
+    }
+}
+
+impl<'a> TextureBuilder<'a> {
+    /// Create builder for image described by `info` from mapped file.
+    /// See `MappedFile::builder` and `MappedFile` for memory used by the upload.
+    pub fn from_mapped_file(file: &'a MappedFile, info: TextureInfo) -> Result<Self, TextureError> {
+        file.builder(info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use std::{env, fs, process};
+
+    use super::*;
+
+    #[test]
+    fn upload_borrows_mapped_file() {
+        let path = env::temp_dir().join(format!("gfx-texture-mapped-{}.raw", process::id()));
+        fs::write(&path, vec![0x7f; 4 * 4 * 4]).unwrap();
+        let file = MappedFile::open(&path).unwrap();
+
+        let mut info = TextureInfo::new(Kind::D2(4, 4, 1, 1));
+        info.format = Format::Rgba8Unorm;
+        let builder = TextureBuilder::from_mapped_file(&file, info).unwrap();
+        builder.validate().unwrap();
+        match builder.upload_levels().unwrap()[0].1 {
+            Cow::Borrowed(data) => assert_eq!(data.as_ptr(), file.as_ref().as_ptr()),
+            Cow::Owned(_) => panic!("Mapped data is copied before upload"),
+        }
+
+        info.kind = Kind::D2(8, 8, 1, 1);
+        assert!(file.builder(info).is_err());
+
+        drop(builder);
+        drop(file);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,838 @@
+//! CPU mip level generation.
+
+use std::f32::consts::PI;
+
+use hal::format::{ChannelType, Format, SurfaceType};
+use hal::image::{Extent, Layer, Level};
+
+use color::{linear_to_srgb, srgb_to_linear};
+use error::TextureError;
+use format::{f16_to_f32, f32_to_f16};
+
+/// Filter used to downsample image when generating mip levels on CPU.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum MipFilter {
+    /// Average of texels covered by destination texel.
+    #[default]
+    Box,
+    /// Tent filter. Blends neighbouring texels in linearly.
+    Triangle,
+    /// Kaiser-windowed sinc. Sharpest of all, may slightly ring near edges.
+    Kaiser,
+    /// Minimum of texels covered by destination texel.
+    /// Texels at odd edges are included into both neighbours.
+    /// Use for conservative depth pyramids.
+    Min,
+    /// Maximum of texels covered by destination texel.
+    /// Texels at odd edges are included into both neighbours.
+    /// Use for conservative depth pyramids with reversed depth.
+    Max,
+}
+
+impl MipFilter {
+    /// Radius of the filter in destination texels.
+    fn support(&self) -> f32 {
+        match *self {
+            MipFilter::Box | MipFilter::Min | MipFilter::Max => 0.5,
+            MipFilter::Triangle => 1.0,
+            MipFilter::Kaiser => 3.0,
+        }
+    }
+
+    /// Weight of the source texel at distance `t` measured in destination texels.
+    fn weight(&self, t: f32) -> f32 {
+        let t = t.abs();
+        match *self {
+            MipFilter::Box | MipFilter::Min | MipFilter::Max => {
+                if t <= 0.5 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            MipFilter::Triangle => (1.0 - t).max(0.0),
+            MipFilter::Kaiser => {
+                const ALPHA: f32 = 4.0;
+                let support = self.support();
+                if t >= support {
+                    0.0
+                } else {
+                    let r = t / support;
+                    sinc(t) * bessel_i0(ALPHA * (1.0 - r * r).sqrt()) / bessel_i0(ALPHA)
+                }
+            }
+        }
+    }
+}
+
+/// Number of levels in full mip chain for the extent.
+pub(crate) fn full_chain_levels(extent: Extent) -> Level {
+    let dominant = extent.width.max(extent.height).max(extent.depth);
+    (32 - dominant.leading_zeros()) as Level
+}
+
+/// Settings of CPU mip generation.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct MipOptions {
+    /// Number of levels including base level.
+    pub levels: Level,
+    /// Downsampling filter.
+    pub filter: MipFilter,
+    /// Renormalize vectors encoded in RGB channels.
+    pub renormalize: bool,
+    /// Filter color channels in linear space.
+    pub gamma_correct: bool,
+    /// Preserve alpha-test coverage at given threshold.
+    pub alpha_coverage: Option<f32>,
+}
+
+/// Generate data for levels `1 .. options.levels` from base level data.
+/// Base level rows are `data_width` texels apart and slices are `data_height` rows apart.
+/// Generated levels are tightly packed and contain all `layers`.
+pub(crate) fn generate(
+    format: Format,
+    extent: Extent,
+    data_width: u32,
+    data_height: u32,
+    data: &[u8],
+    layers: Layer,
+    options: &MipOptions,
+) -> Result<Vec<Vec<u8>>, TextureError> {
+    let mut encoding =
+        Encoding::of(format).ok_or(TextureError::MipGenUnsupportedFormat { format })?;
+    encoding.srgb = options.gamma_correct && encoding.kind.is_normalized();
+    if options.alpha_coverage.is_some() && encoding.channels != 4 {
+        Err(TextureError::NoAlphaChannel { format })?;
+    }
+
+    let layer_size =
+        data_width as usize * data_height as usize * extent.depth as usize * encoding.texel_size();
+    let mut mips = vec![Vec::new(); options.levels.saturating_sub(1) as usize];
+    for layer in 0..layers as usize {
+        let data = &data[layer * layer_size..];
+        let mut plane = encoding.decode(extent, data_width, data_height, data);
+        let coverage = options
+            .alpha_coverage
+            .map(|threshold| (threshold, plane.coverage(threshold)));
+
+        for mip in &mut mips {
+            plane = plane.downsample(options.filter);
+            if options.renormalize {
+                plane.renormalize();
+            }
+            match coverage {
+                Some((threshold, target)) => {
+                    let scale = plane.coverage_scale(threshold, target);
+                    let mut scaled = plane.clone();
+                    scaled.scale_alpha(scale);
+                    mip.extend(encoding.encode(&scaled));
+                }
+                None => mip.extend(encoding.encode(&plane)),
+            }
+        }
+    }
+    Ok(mips)
+}
+
+/// Check whether CPU can decode and encode texels of the format.
+pub(crate) fn is_supported(format: Format) -> bool {
+    Encoding::of(format).is_some()
+}
+
+/// Tightly packed data for `layers` of `extent` filled with linear `color`.
+/// Color is encoded to sRGB for sRGB formats.
+pub(crate) fn fill(
+    format: Format,
+    extent: Extent,
+    layers: Layer,
+    color: [f32; 4],
+) -> Option<Vec<u8>> {
+    let encoding = texel_encoding(format)?;
+    let mut texel = Vec::with_capacity(encoding.texel_size());
+    encode_texel(&encoding, format, color, &mut texel);
+    let texels =
+        extent.width as usize * extent.height as usize * extent.depth as usize * layers as usize;
+    Some(texel.repeat(texels))
+}
+
+/// Tightly packed row of `width` texels interpolating linear colors of sorted gradient `stops`.
+/// Texels before the first and after the last stop get their colors.
+pub(crate) fn gradient(format: Format, width: u32, stops: &[(f32, [f32; 4])]) -> Option<Vec<u8>> {
+    let encoding = texel_encoding(format)?;
+    let mut data = Vec::with_capacity(width as usize * encoding.texel_size());
+    for x in 0..width {
+        let t = (x as f32 + 0.5) / width as f32;
+        let color = match stops.iter().position(|&(offset, _)| offset >= t) {
+            Some(0) => stops[0].1,
+            Some(next) => {
+                let (start, from) = stops[next - 1];
+                let (end, to) = stops[next];
+                let f = (t - start) / (end - start);
+                let mut color = [0.0; 4];
+                for channel in 0..4 {
+                    color[channel] = from[channel] + (to[channel] - from[channel]) * f;
+                }
+                color
+            }
+            None => stops[stops.len() - 1].1,
+        };
+        encode_texel(&encoding, format, color, &mut data);
+    }
+    Some(data)
+}
+
+/// Encoding of the format writing sRGB values for sRGB formats.
+fn texel_encoding(format: Format) -> Option<Encoding> {
+    let mut encoding = Encoding::of(format)?;
+    encoding.srgb = format.base_format().1 == ChannelType::Srgb;
+    Some(encoding)
+}
+
+/// Append linear RGBA `color` encoded as single texel of the format.
+fn encode_texel(encoding: &Encoding, format: Format, color: [f32; 4], data: &mut Vec<u8>) {
+    let mut color = color;
+    match format.base_format().0 {
+        SurfaceType::B8_G8_R8 | SurfaceType::B8_G8_R8_A8 => color.swap(0, 2),
+        _ => {}
+    }
+    for (channel, &value) in color.iter().take(encoding.channels).enumerate() {
+        encoding.write(channel, value, data);
+    }
+}
+
+/// Cross-fade opposite edges of each slice over `blend` texels so that they match.
+/// Data keeps layout with rows `data_width` texels apart and slices `data_height` rows apart.
+/// Texels outside of blended bands are left intact.
+pub(crate) fn make_tileable(
+    format: Format,
+    extent: Extent,
+    data_width: u32,
+    data_height: u32,
+    data: &[u8],
+    layers: Layer,
+    blend: u32,
+) -> Option<Vec<u8>> {
+    let mut encoding = Encoding::of(format)?;
+    encoding.srgb = format.base_format().1 == ChannelType::Srgb;
+    let texel_size = encoding.texel_size();
+    let layer_size =
+        data_width as usize * data_height as usize * extent.depth as usize * texel_size;
+    let [width, height, depth] = [
+        extent.width as usize,
+        extent.height as usize,
+        extent.depth as usize,
+    ];
+    let blend_x = (blend as usize).min(width / 2);
+    let blend_y = (blend as usize).min(height / 2);
+
+    let mut result = data.to_vec();
+    let mut texel = Vec::with_capacity(texel_size);
+    for layer in 0..layers as usize {
+        let mut plane =
+            encoding.decode(extent, data_width, data_height, &data[layer * layer_size..]);
+        plane.make_tileable(blend_x, blend_y);
+        for z in 0..depth {
+            for y in 0..height {
+                for x in 0..width {
+                    let band =
+                        x < blend_x || x >= width - blend_x || y < blend_y || y >= height - blend_y;
+                    if !band {
+                        continue;
+                    }
+                    let index = ((z * height + y) * width + x) * plane.channels;
+                    texel.clear();
+                    for channel in 0..plane.channels {
+                        encoding.write(channel, plane.texels[index + channel], &mut texel);
+                    }
+                    let offset = layer * layer_size
+                        + ((z * data_height as usize + y) * data_width as usize + x) * texel_size;
+                    result[offset..offset + texel_size].copy_from_slice(&texel);
+                }
+            }
+        }
+    }
+    Some(result)
+}
+
+/// Decode texels of all `layers` into `f32` channels as stored, without color space conversion.
+/// Returns number of channels and channel values of texels in order.
+pub(crate) fn decode(
+    format: Format,
+    extent: Extent,
+    data_width: u32,
+    data_height: u32,
+    data: &[u8],
+    layers: Layer,
+) -> Option<(usize, Vec<f32>)> {
+    let encoding = Encoding::of(format)?;
+    let layer_size =
+        data_width as usize * data_height as usize * extent.depth as usize * encoding.texel_size();
+    let mut texels = Vec::new();
+    for layer in 0..layers as usize {
+        let data = &data[layer * layer_size..];
+        texels.extend(
+            encoding
+                .decode(extent, data_width, data_height, data)
+                .texels,
+        );
+    }
+    Some((encoding.channels, texels))
+}
+
+/// Channel encoding of formats supported by CPU mip generation.
+#[derive(Clone, Copy, Debug)]
+struct Encoding {
+    channels: usize,
+    kind: ChannelKind,
+    srgb: bool,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum ChannelKind {
+    Unorm8,
+    Unorm16,
+    Float16,
+    Float32,
+}
+
+impl ChannelKind {
+    fn size(&self) -> usize {
+        match *self {
+            ChannelKind::Unorm8 => 1,
+            ChannelKind::Unorm16 | ChannelKind::Float16 => 2,
+            ChannelKind::Float32 => 4,
+        }
+    }
+
+    fn is_normalized(&self) -> bool {
+        match *self {
+            ChannelKind::Unorm8 | ChannelKind::Unorm16 => true,
+            ChannelKind::Float16 | ChannelKind::Float32 => false,
+        }
+    }
+}
+
+impl Encoding {
+    fn of(format: Format) -> Option<Self> {
+        let (surface, channel) = format.base_format();
+        let (channels, bits) = match surface {
+            SurfaceType::R8 => (1, 8),
+            SurfaceType::R8_G8 => (2, 8),
+            SurfaceType::R8_G8_B8 | SurfaceType::B8_G8_R8 => (3, 8),
+            SurfaceType::R8_G8_B8_A8 | SurfaceType::B8_G8_R8_A8 => (4, 8),
+            SurfaceType::R16 => (1, 16),
+            SurfaceType::R16_G16 => (2, 16),
+            SurfaceType::R16_G16_B16 => (3, 16),
+            SurfaceType::R16_G16_B16_A16 => (4, 16),
+            SurfaceType::R32 => (1, 32),
+            SurfaceType::R32_G32 => (2, 32),
+            SurfaceType::R32_G32_B32 => (3, 32),
+            SurfaceType::R32_G32_B32_A32 => (4, 32),
+            _ => return None,
+        };
+
+        let kind = match (bits, channel) {
+            (8, ChannelType::Unorm) | (8, ChannelType::Srgb) => ChannelKind::Unorm8,
+            (16, ChannelType::Unorm) => ChannelKind::Unorm16,
+            (16, ChannelType::Float) => ChannelKind::Float16,
+            (32, ChannelType::Float) => ChannelKind::Float32,
+            _ => return None,
+        };
+
+        Some(Encoding {
+            channels,
+            kind,
+            srgb: false,
+        })
+    }
+
+    fn texel_size(&self) -> usize {
+        self.channels * self.kind.size()
+    }
+
+    fn decode(&self, extent: Extent, data_width: u32, data_height: u32, data: &[u8]) -> Plane {
+        let texel_size = self.texel_size();
+        let dims = [
+            extent.width as usize,
+            extent.height as usize,
+            extent.depth as usize,
+        ];
+        let mut texels = Vec::with_capacity(dims[0] * dims[1] * dims[2] * self.channels);
+        for z in 0..dims[2] {
+            for y in 0..dims[1] {
+                let row = (z * data_height as usize + y) * data_width as usize * texel_size;
+                let row = &data[row..][..dims[0] * texel_size];
+                texels.extend(
+                    row.chunks(self.kind.size())
+                        .enumerate()
+                        .map(|(index, bytes)| self.read(index % self.channels, bytes)),
+                );
+            }
+        }
+
+        Plane {
+            dims,
+            channels: self.channels,
+            texels,
+        }
+    }
+
+    fn encode(&self, plane: &Plane) -> Vec<u8> {
+        let mut data = Vec::with_capacity(plane.texels.len() * self.kind.size());
+        for (index, &value) in plane.texels.iter().enumerate() {
+            self.write(index % self.channels, value, &mut data);
+        }
+        data
+    }
+
+    /// Alpha channel is never sRGB-encoded.
+    fn is_srgb_channel(&self, channel: usize) -> bool {
+        self.srgb && !(self.channels == 4 && channel == 3)
+    }
+
+    fn read(&self, channel: usize, bytes: &[u8]) -> f32 {
+        let value = match self.kind {
+            ChannelKind::Unorm8 => bytes[0] as f32 / 255.0,
+            ChannelKind::Unorm16 => u16::from_ne_bytes([bytes[0], bytes[1]]) as f32 / 65535.0,
+            ChannelKind::Float16 => f16_to_f32(u16::from_ne_bytes([bytes[0], bytes[1]])),
+            ChannelKind::Float32 => {
+                f32::from_bits(u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            }
+        };
+
+        if self.is_srgb_channel(channel) {
+            srgb_to_linear(value)
+        } else {
+            value
+        }
+    }
+
+    fn write(&self, channel: usize, value: f32, data: &mut Vec<u8>) {
+        let value = if self.is_srgb_channel(channel) {
+            linear_to_srgb(value.clamp(0.0, 1.0))
+        } else {
+            value
+        };
+
+        match self.kind {
+            ChannelKind::Unorm8 => data.push(quantize(value, 255.0) as u8),
+            ChannelKind::Unorm16 => {
+                data.extend_from_slice(&(quantize(value, 65535.0) as u16).to_ne_bytes())
+            }
+            ChannelKind::Float16 => data.extend_from_slice(&f32_to_f16(value).to_ne_bytes()),
+            ChannelKind::Float32 => data.extend_from_slice(&value.to_bits().to_ne_bytes()),
+        }
+    }
+}
+
+/// Texels decoded into `f32` channels.
+#[derive(Clone)]
+struct Plane {
+    dims: [usize; 3],
+    channels: usize,
+    texels: Vec<f32>,
+}
+
+impl Plane {
+    /// Produce next mip level.
+    fn downsample(&self, filter: MipFilter) -> Plane {
+        let mut plane = Plane {
+            dims: self.dims,
+            channels: self.channels,
+            texels: self.texels.clone(),
+        };
+        for axis in 0..3 {
+            let dst = (plane.dims[axis] / 2).max(1);
+            if dst != plane.dims[axis] {
+                plane = plane.downsample_axis(axis, dst, filter);
+            }
+        }
+        plane
+    }
+
+    /// Resample along one axis to `dst` texels.
+    fn downsample_axis(&self, axis: usize, dst: usize, filter: MipFilter) -> Plane {
+        let weights = axis_weights(self.dims[axis], dst, filter);
+        let mut dims = self.dims;
+        dims[axis] = dst;
+
+        let strides = [
+            self.channels,
+            self.channels * self.dims[0],
+            self.channels * self.dims[0] * self.dims[1],
+        ];
+
+        let mut texels = Vec::with_capacity(dims[0] * dims[1] * dims[2] * self.channels);
+        for z in 0..dims[2] {
+            for y in 0..dims[1] {
+                for x in 0..dims[0] {
+                    let position = [x, y, z];
+                    for c in 0..self.channels {
+                        let base = (0..3)
+                            .filter(|&a| a != axis)
+                            .map(|a| position[a] * strides[a])
+                            .sum::<usize>()
+                            + c;
+                        let covered = weights[position[axis]].iter();
+                        let texel = |index: usize| self.texels[base + index * strides[axis]];
+                        let value = match filter {
+                            MipFilter::Min => covered
+                                .map(|&(index, _)| texel(index))
+                                .fold(f32::INFINITY, f32::min),
+                            MipFilter::Max => covered
+                                .map(|&(index, _)| texel(index))
+                                .fold(f32::NEG_INFINITY, f32::max),
+                            _ => covered.map(|&(index, weight)| texel(index) * weight).sum(),
+                        };
+                        texels.push(value);
+                    }
+                }
+            }
+        }
+
+        Plane {
+            dims,
+            channels: self.channels,
+            texels,
+        }
+    }
+
+    /// Cross-fade columns and rows with opposite ones over `blend_x` and `blend_y` texels.
+    /// Border texels become averages of opposite ones.
+    fn make_tileable(&mut self, blend_x: usize, blend_y: usize) {
+        let [width, height, depth] = self.dims;
+        let channels = self.channels;
+        let texels = &mut self.texels;
+        let mut mix = |a: usize, b: usize, t: f32| {
+            for channel in 0..channels {
+                let (va, vb) = (texels[a + channel], texels[b + channel]);
+                texels[a + channel] = va * (1.0 - t) + vb * t;
+                texels[b + channel] = vb * (1.0 - t) + va * t;
+            }
+        };
+        let index = |x: usize, y: usize, z: usize| ((z * height + y) * width + x) * channels;
+
+        for z in 0..depth {
+            for x in 0..blend_x {
+                let t = 0.5 * (1.0 - x as f32 / blend_x as f32);
+                for y in 0..height {
+                    mix(index(x, y, z), index(width - 1 - x, y, z), t);
+                }
+            }
+            for y in 0..blend_y {
+                let t = 0.5 * (1.0 - y as f32 / blend_y as f32);
+                for x in 0..width {
+                    mix(index(x, y, z), index(x, height - 1 - y, z), t);
+                }
+            }
+        }
+    }
+
+    /// Fraction of texels with alpha not less than `threshold`.
+    fn coverage(&self, threshold: f32) -> f32 {
+        let alpha = self.texels.chunks(4).map(|texel| texel[3]);
+        let covered = alpha.filter(|&alpha| alpha >= threshold).count();
+        covered as f32 / (self.texels.len() / 4) as f32
+    }
+
+    /// Find alpha scale that makes coverage at `threshold` as close to `target` as possible.
+    fn coverage_scale(&self, threshold: f32, target: f32) -> f32 {
+        let mut low = 0.0f32;
+        let mut high = 4.0f32;
+        let mut best = (1.0f32, (self.coverage(threshold) - target).abs());
+        for _ in 0..20 {
+            let scale = (low + high) * 0.5;
+            let coverage = self.coverage(threshold / scale);
+            let error = (coverage - target).abs();
+            if error < best.1 {
+                best = (scale, error);
+            }
+            if coverage < target {
+                low = scale;
+            } else {
+                high = scale;
+            }
+        }
+        best.0
+    }
+
+    /// Multiply alpha channel by `scale`.
+    fn scale_alpha(&mut self, scale: f32) {
+        for texel in self.texels.chunks_mut(4) {
+            texel[3] = (texel[3] * scale).min(1.0);
+        }
+    }
+
+    /// Renormalize vectors encoded in first three channels as `v * 0.5 + 0.5`.
+    fn renormalize(&mut self) {
+        if self.channels < 3 {
+            return;
+        }
+        for texel in self.texels.chunks_mut(self.channels) {
+            let v = [
+                texel[0] * 2.0 - 1.0,
+                texel[1] * 2.0 - 1.0,
+                texel[2] * 2.0 - 1.0,
+            ];
+            let len = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+            if len > 0.0 {
+                for i in 0..3 {
+                    texel[i] = v[i] / len * 0.5 + 0.5;
+                }
+            }
+        }
+    }
+}
+
+/// Compute source texels and their weights for each destination texel.
+/// Source texel contributions are computed in continuous coordinates
+/// so that odd extents split boundary texels between neighbours instead of dropping them.
+fn axis_weights(src: usize, dst: usize, filter: MipFilter) -> Vec<Vec<(usize, f32)>> {
+    let scale = src as f32 / dst as f32;
+    let radius = filter.support() * scale;
+    (0..dst)
+        .map(|i| {
+            let center = (i as f32 + 0.5) * scale;
+            let start = (center - radius).floor() as isize;
+            let end = (center + radius).ceil() as isize;
+            let mut weights = Vec::new();
+            for j in start..end {
+                let weight = match filter {
+                    MipFilter::Box | MipFilter::Min | MipFilter::Max => {
+                        overlap(j as f32, j as f32 + 1.0, center - radius, center + radius)
+                    }
+                    _ => filter.weight((j as f32 + 0.5 - center) / scale),
+                };
+                if weight != 0.0 {
+                    let index = j.max(0).min(src as isize - 1) as usize;
+                    weights.push((index, weight));
+                }
+            }
+            let total: f32 = weights.iter().map(|&(_, weight)| weight).sum();
+            for weight in &mut weights {
+                weight.1 /= total;
+            }
+            weights
+        })
+        .collect()
+}
+
+fn overlap(a0: f32, a1: f32, b0: f32, b1: f32) -> f32 {
+    (a1.min(b1) - a0.max(b0)).max(0.0)
+}
+
+fn quantize(value: f32, max: f32) -> f32 {
+    (value.clamp(0.0, 1.0) * max).round()
+}
+
+fn sinc(x: f32) -> f32 {
+    if x == 0.0 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+/// Zeroth order modified Bessel function of the first kind.
+fn bessel_i0(x: f32) -> f32 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let half = x * 0.5;
+    for k in 1..16 {
+        term *= half / k as f32;
+        sum += term * term;
+    }
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Single channel checkerboard of `cell` sized squares.
+    fn checkerboard(size: u32, cell: u32) -> Vec<u8> {
+        (0..size * size)
+            .map(|i| {
+                let (x, y) = (i % size / cell, i / size / cell);
+                if (x + y) % 2 == 1 {
+                    255
+                } else {
+                    0
+                }
+            })
+            .collect()
+    }
+
+    fn downsample(format: Format, size: u32, data: &[u8], options: &MipOptions) -> Vec<u8> {
+        let extent = Extent {
+            width: size,
+            height: size,
+            depth: 1,
+        };
+        generate(format, extent, size, size, data, 1, options)
+            .unwrap()
+            .remove(0)
+    }
+
+    fn options(filter: MipFilter) -> MipOptions {
+        MipOptions {
+            levels: 2,
+            filter,
+            renormalize: false,
+            gamma_correct: false,
+            alpha_coverage: None,
+        }
+    }
+
+    fn mean(data: &[u8]) -> f32 {
+        data.iter().map(|&value| f32::from(value)).sum::<f32>() / data.len() as f32
+    }
+
+    #[test]
+    fn box_and_kaiser_differ_on_checkerboard() {
+        let data = checkerboard(16, 2);
+        let boxed = downsample(Format::R8Unorm, 16, &data, &options(MipFilter::Box));
+        let kaiser = downsample(Format::R8Unorm, 16, &data, &options(MipFilter::Kaiser));
+
+        assert_eq!(boxed, checkerboard(8, 1));
+        assert!(kaiser.iter().any(|&value| value != 0 && value != 255));
+        assert_ne!(boxed, kaiser);
+    }
+
+    #[test]
+    fn filters_preserve_mean() {
+        let data = checkerboard(16, 2);
+        let expected = mean(&data);
+        for &filter in &[MipFilter::Box, MipFilter::Triangle, MipFilter::Kaiser] {
+            let level = downsample(Format::R8Unorm, 16, &data, &options(filter));
+            assert!(
+                (mean(&level) - expected).abs() < 2.0,
+                "{:?} mean {} differs from {}",
+                filter,
+                mean(&level),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn odd_extent_weights_cover_all_texels() {
+        for &filter in &[MipFilter::Box, MipFilter::Triangle, MipFilter::Kaiser] {
+            let weights = axis_weights(5, 2, filter);
+            let mut coverage = [0.0; 5];
+            for texel in &weights {
+                let total: f32 = texel.iter().map(|&(_, weight)| weight).sum();
+                assert!((total - 1.0).abs() < 1e-5);
+                for &(index, weight) in texel {
+                    coverage[index] += weight;
+                }
+            }
+            assert!(coverage[2] > 0.0, "{:?} drops middle texel", filter);
+        }
+    }
+
+    #[test]
+    fn srgb_checkerboard_averages_in_linear_space() {
+        let data = checkerboard(4, 1)
+            .into_iter()
+            .flat_map(|value| vec![value, value, value, 255])
+            .collect::<Vec<_>>();
+        let mut options = options(MipFilter::Box);
+
+        options.gamma_correct = true;
+        let corrected = downsample(Format::Rgba8Srgb, 4, &data, &options);
+        assert!(corrected.chunks(4).all(|texel| texel[..3]
+            .iter()
+            .all(|&value| (187..=189).contains(&value))
+            && texel[3] == 255));
+
+        options.gamma_correct = false;
+        let naive = downsample(Format::Rgba8Srgb, 4, &data, &options);
+        assert!(naive
+            .chunks(4)
+            .all(|texel| texel[..3].iter().all(|&value| value == 128)));
+    }
+
+    /// Fraction of RGBA8 texels with alpha not less than `threshold`.
+    fn alpha_coverage(data: &[u8], threshold: f32) -> f32 {
+        let covered = data
+            .chunks(4)
+            .filter(|texel| f32::from(texel[3]) / 255.0 >= threshold)
+            .count();
+        covered as f32 / (data.len() / 4) as f32
+    }
+
+    #[test]
+    fn alpha_coverage_is_preserved() {
+        // Deterministic noise so coverage of naive mips visibly drops.
+        let mut seed = 0x1234_5678u32;
+        let data = (0..32 * 32)
+            .flat_map(|_| {
+                seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                vec![255, 255, 255, (seed >> 24) as u8]
+            })
+            .collect::<Vec<_>>();
+        let extent = Extent {
+            width: 32,
+            height: 32,
+            depth: 1,
+        };
+        let threshold = 0.7;
+        let base = alpha_coverage(&data, threshold);
+
+        let mut options = MipOptions {
+            levels: 3,
+            ..options(MipFilter::Box)
+        };
+        let naive = generate(Format::Rgba8Unorm, extent, 32, 32, &data, 1, &options).unwrap();
+        assert!(alpha_coverage(&naive[1], threshold) < base - 0.1);
+
+        options.alpha_coverage = Some(threshold);
+        let preserved = generate(Format::Rgba8Unorm, extent, 32, 32, &data, 1, &options).unwrap();
+        for level in &preserved {
+            let coverage = alpha_coverage(level, threshold);
+            assert!(
+                (coverage - base).abs() < 0.05,
+                "coverage {} differs from {}",
+                coverage,
+                base
+            );
+        }
+    }
+
+    #[test]
+    fn min_max_reduction_includes_odd_edges() {
+        // Extremes are in the last column and row which halving would drop.
+        let mut depth = [0.5f32; 5 * 3];
+        depth[2 * 5 + 4] = 0.1;
+        depth[2 * 5] = 0.9;
+        let data = depth
+            .iter()
+            .flat_map(|value| value.to_ne_bytes().to_vec())
+            .collect::<Vec<_>>();
+        let extent = Extent {
+            width: 5,
+            height: 3,
+            depth: 1,
+        };
+        let reduce = |filter| {
+            let options = MipOptions {
+                levels: 3,
+                ..options(filter)
+            };
+            generate(Format::R32Float, extent, 5, 3, &data, 1, &options)
+                .unwrap()
+                .iter()
+                .map(|level| {
+                    level
+                        .chunks(4)
+                        .map(|bytes| f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(reduce(MipFilter::Min), [vec![0.5, 0.1], vec![0.1]]);
+        assert_eq!(reduce(MipFilter::Max), [vec![0.9, 0.5], vec![0.9]]);
+    }
+}
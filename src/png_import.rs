@@ -0,0 +1,155 @@
+//! Import of PNG images.
+
+use png::{BitDepth, ColorType, Decoder, Transformations};
+
+use hal::format::Format;
+use hal::image::Kind;
+
+use error::TextureError;
+use source::{expand_alpha, DecodedTexture, TextureSource};
+use TextureBuilder;
+
+impl TextureBuilder<'static> {
+    /// Create builder from PNG file.
+    /// See `PngImage` for the conversion.
+    pub fn from_png(data: &[u8], srgb: bool) -> Result<Self, TextureError> {
+        TextureBuilder::from_source(&PngImage { data, srgb })
+    }
+}
+
+/// PNG file as `TextureSource`.
+/// Palette and low bit depth images are expanded to 8 bits per channel
+/// and RGB data is expanded to RGBA.
+/// 16-bit data is converted to little endian and always treated as linear
+/// as there are no 16-bit sRGB formats.
+#[derive(Clone, Copy, Debug)]
+pub struct PngImage<'a> {
+    /// Content of the file.
+    pub data: &'a [u8],
+    /// Whether 8-bit data uses sRGB formats.
+    pub srgb: bool,
+}
+
+impl<'a> TextureSource for PngImage<'a> {
+    fn decode(&self) -> Result<DecodedTexture, TextureError> {
+        let mut decoder = Decoder::new(self.data);
+        decoder.set_transformations(Transformations::EXPAND);
+        let mut reader = decoder.read_info().map_err(TextureError::decode)?;
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader
+            .next_frame(&mut pixels)
+            .map_err(TextureError::decode)?;
+        pixels.truncate(info.buffer_size());
+
+        let wide = info.bit_depth == BitDepth::Sixteen;
+        if wide {
+            for channel in pixels.chunks_exact_mut(2) {
+                channel.swap(0, 1);
+            }
+        }
+        let format = |unorm, srgb, wide_format| match (wide, self.srgb) {
+            (true, _) => wide_format,
+            (false, true) => srgb,
+            (false, false) => unorm,
+        };
+        let channel_size = if wide { 2 } else { 1 };
+        let (format, data) = match info.color_type {
+            ColorType::Grayscale => (
+                format(Format::R8Unorm, Format::R8Srgb, Format::R16Unorm),
+                pixels,
+            ),
+            ColorType::GrayscaleAlpha => (
+                format(Format::Rg8Unorm, Format::Rg8Srgb, Format::Rg16Unorm),
+                pixels,
+            ),
+            ColorType::Rgb => (
+                format(Format::Rgba8Unorm, Format::Rgba8Srgb, Format::Rgba16Unorm),
+                expand_alpha(&pixels, channel_size),
+            ),
+            ColorType::Rgba => (
+                format(Format::Rgba8Unorm, Format::Rgba8Srgb, Format::Rgba16Unorm),
+                pixels,
+            ),
+            ColorType::Indexed => Err(TextureError::SourceFormatUnsupported {
+                container: "PNG",
+                format: "Indexed".into(),
+            })?,
+        };
+
+        Ok(DecodedTexture {
+            kind: Kind::D2(info.width, info.height, 1, 1),
+            format,
+            levels: vec![data],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use png::Encoder;
+
+    fn png_file(color: ColorType, depth: BitDepth, width: u32, data: &[u8]) -> Vec<u8> {
+        let mut file = Vec::new();
+        {
+            let mut encoder = Encoder::new(&mut file, width, 1);
+            encoder.set_color(color);
+            encoder.set_depth(depth);
+            let mut writer = encoder.write_header().unwrap();
+            writer.write_image_data(data).unwrap();
+        }
+        file
+    }
+
+    #[test]
+    fn rgb_is_expanded_to_rgba() {
+        let file = png_file(ColorType::Rgb, BitDepth::Eight, 2, &[1, 2, 3, 4, 5, 6]);
+        let decoded = PngImage {
+            data: &file,
+            srgb: true,
+        }
+        .decode()
+        .unwrap();
+        assert_eq!(decoded.kind, Kind::D2(2, 1, 1, 1));
+        assert_eq!(decoded.format, Format::Rgba8Srgb);
+        assert_eq!(decoded.levels, [vec![1, 2, 3, 255, 4, 5, 6, 255]]);
+    }
+
+    #[test]
+    fn wide_channels_are_little_endian() {
+        let file = png_file(
+            ColorType::GrayscaleAlpha,
+            BitDepth::Sixteen,
+            1,
+            &[1, 2, 3, 4],
+        );
+        let builder = TextureBuilder::from_png(&file, true).unwrap();
+        builder.validate().unwrap();
+        let decoded = PngImage {
+            data: &file,
+            srgb: true,
+        }
+        .decode()
+        .unwrap();
+        assert_eq!(decoded.format, Format::Rg16Unorm);
+        assert_eq!(decoded.levels, [vec![2, 1, 4, 3]]);
+    }
+
+    #[test]
+    fn low_bit_depth_is_expanded() {
+        let file = png_file(ColorType::Grayscale, BitDepth::One, 8, &[0b1010_0000]);
+        let decoded = PngImage {
+            data: &file,
+            srgb: false,
+        }
+        .decode()
+        .unwrap();
+        assert_eq!(decoded.format, Format::R8Unorm);
+        assert_eq!(decoded.levels, [vec![255, 0, 255, 0, 0, 0, 0, 0]]);
+
+        match TextureBuilder::from_png(&file[..16], false) {
+            Err(TextureError::Decode { .. }) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+}
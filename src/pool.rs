@@ -0,0 +1,339 @@
+//! Textures kept for reuse and evicted under memory pressure.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use hal::Backend;
+
+use render::Factory;
+
+use Texture;
+
+/// Textures keyed by asset id and evicted in least recently used order.
+/// Callers report use with `touch` and pin textures referenced by
+/// commands being recorded so they are never evicted.
+#[derive(Debug)]
+pub struct TexturePool<K, B: Backend> {
+    pool: Pool<K, Texture<B>>,
+}
+
+/// Keeps pooled texture from being evicted until dropped.
+#[derive(Debug)]
+pub struct TexturePin {
+    pins: Arc<AtomicUsize>,
+}
+
+impl Drop for TexturePin {
+    fn drop(&mut self) {
+        self.pins.fetch_sub(1, Ordering::Release);
+    }
+}
+
+impl<K, B> TexturePool<K, B>
+where
+    K: Clone + Eq + Hash,
+    B: Backend,
+{
+    /// Create empty pool.
+    pub fn new() -> Self {
+        TexturePool { pool: Pool::new() }
+    }
+
+    /// Add texture used at `frame`.
+    /// Returns texture previously stored with the id, caller must dispose it.
+    /// Pins of the previous texture keep the new one from being evicted.
+    pub fn insert(&mut self, id: K, texture: Texture<B>, frame: u64) -> Option<Texture<B>> {
+        self.pool.insert(id, texture, frame)
+    }
+
+    /// Texture stored with the id.
+    pub fn get(&self, id: &K) -> Option<&Texture<B>> {
+        self.pool.entries.get(id).map(|entry| &entry.item)
+    }
+
+    /// Record that texture is used at `frame`.
+    /// Returns `false` if there is no texture with the id.
+    pub fn touch(&mut self, id: &K, frame: u64) -> bool {
+        self.pool.touch(id, frame)
+    }
+
+    /// Keep texture from being evicted while returned guard is alive.
+    pub fn pin(&self, id: &K) -> Option<TexturePin> {
+        self.pool.pin(id)
+    }
+
+    /// Check if texture is pinned.
+    pub fn is_pinned(&self, id: &K) -> bool {
+        self.pool.is_pinned(id)
+    }
+
+    /// Take texture out of the pool regardless of pins.
+    pub fn remove(&mut self, id: &K) -> Option<Texture<B>> {
+        self.pool.remove(id)
+    }
+
+    /// Estimated size of all textures in bytes.
+    pub fn size(&self) -> u64 {
+        self.pool.size
+    }
+
+    /// Number of textures in the pool.
+    pub fn len(&self) -> usize {
+        self.pool.entries.len()
+    }
+
+    /// Check if the pool has no textures.
+    pub fn is_empty(&self) -> bool {
+        self.pool.entries.is_empty()
+    }
+
+    /// Dispose least recently used textures that are not pinned
+    /// until size of the pool is not larger than `budget` bytes.
+    /// Returns ids of disposed textures so descriptors referencing them can be invalidated.
+    /// Size may stay above budget if the rest of the textures are pinned.
+    pub fn evict_until(&mut self, budget: u64, factory: &mut Factory<B>) -> Vec<K> {
+        self.pool.evict_until(budget, factory)
+    }
+
+    /// Dispose all textures regardless of pins.
+    pub fn dispose(self, factory: &mut Factory<B>) {
+        for (_, entry) in self.pool.entries {
+            entry.item.dispose(factory);
+        }
+    }
+}
+
+impl<K, B> Default for TexturePool<K, B>
+where
+    K: Clone + Eq + Hash,
+    B: Backend,
+{
+    fn default() -> Self {
+        TexturePool::new()
+    }
+}
+
+/// Item owned by the pool.
+trait Pooled {
+    /// Destroys resources of the item.
+    type Disposer;
+
+    /// Estimated size in bytes counted against budget.
+    fn estimated_size(&self) -> u64;
+
+    /// Destroy the item.
+    fn dispose(self, disposer: &mut Self::Disposer);
+}
+
+impl<B> Pooled for Texture<B>
+where
+    B: Backend,
+{
+    type Disposer = Factory<B>;
+
+    fn estimated_size(&self) -> u64 {
+        Texture::estimated_size(self)
+    }
+
+    fn dispose(self, factory: &mut Factory<B>) {
+        Texture::dispose(self, factory)
+    }
+}
+
+/// Pool entry with its use tracking.
+#[derive(Debug)]
+struct Entry<T> {
+    item: T,
+    size: u64,
+    last_used: u64,
+    pins: Arc<AtomicUsize>,
+}
+
+/// Eviction and pinning logic independent of the device.
+#[derive(Debug)]
+struct Pool<K, T> {
+    entries: HashMap<K, Entry<T>>,
+    size: u64,
+}
+
+impl<K, T> Pool<K, T>
+where
+    K: Clone + Eq + Hash,
+    T: Pooled,
+{
+    fn new() -> Self {
+        Pool {
+            entries: HashMap::new(),
+            size: 0,
+        }
+    }
+
+    fn insert(&mut self, id: K, item: T, frame: u64) -> Option<T> {
+        let size = item.estimated_size();
+        self.size += size;
+        let pins = self
+            .entries
+            .get(&id)
+            .map(|entry| entry.pins.clone())
+            .unwrap_or_default();
+        let replaced = self.entries.insert(
+            id,
+            Entry {
+                item,
+                size,
+                last_used: frame,
+                pins,
+            },
+        );
+        replaced.map(|entry| {
+            self.size -= entry.size;
+            entry.item
+        })
+    }
+
+    fn touch(&mut self, id: &K, frame: u64) -> bool {
+        match self.entries.get_mut(id) {
+            Some(entry) => {
+                entry.last_used = entry.last_used.max(frame);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn pin(&self, id: &K) -> Option<TexturePin> {
+        let entry = self.entries.get(id)?;
+        entry.pins.fetch_add(1, Ordering::Acquire);
+        Some(TexturePin {
+            pins: entry.pins.clone(),
+        })
+    }
+
+    fn is_pinned(&self, id: &K) -> bool {
+        self.entries
+            .get(id)
+            .is_some_and(|entry| entry.pins.load(Ordering::Acquire) > 0)
+    }
+
+    fn remove(&mut self, id: &K) -> Option<T> {
+        let entry = self.entries.remove(id)?;
+        self.size -= entry.size;
+        Some(entry.item)
+    }
+
+    fn evict_until(&mut self, budget: u64, disposer: &mut T::Disposer) -> Vec<K> {
+        if self.size <= budget {
+            return Vec::new();
+        }
+
+        let mut candidates = self
+            .entries
+            .iter()
+            .filter(|&(_, entry)| entry.pins.load(Ordering::Acquire) == 0)
+            .map(|(id, entry)| (entry.last_used, id.clone()))
+            .collect::<Vec<_>>();
+        candidates.sort_by_key(|&(last_used, _)| last_used);
+
+        let mut evicted = Vec::new();
+        for (_, id) in candidates {
+            if self.size <= budget {
+                break;
+            }
+            if let Some(item) = self.remove(&id) {
+                item.dispose(disposer);
+                evicted.push(id);
+            }
+        }
+        evicted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Item recording its disposal.
+    #[derive(Debug, PartialEq)]
+    struct Mock(&'static str, u64);
+
+    impl Pooled for Mock {
+        type Disposer = Vec<&'static str>;
+
+        fn estimated_size(&self) -> u64 {
+            self.1
+        }
+
+        fn dispose(self, disposed: &mut Vec<&'static str>) {
+            disposed.push(self.0);
+        }
+    }
+
+    fn pool(items: &[(&'static str, u64, u64)]) -> Pool<&'static str, Mock> {
+        let mut pool = Pool::new();
+        for &(id, size, frame) in items {
+            assert_eq!(pool.insert(id, Mock(id, size), frame), None);
+        }
+        pool
+    }
+
+    #[test]
+    fn least_recently_used_are_evicted_first() {
+        let mut pool = pool(&[("a", 100, 1), ("b", 200, 2), ("c", 300, 3), ("d", 400, 4)]);
+        assert_eq!(pool.size, 1000);
+        assert!(pool.touch(&"a", 5));
+        assert!(!pool.touch(&"e", 5));
+        // Touching with older frame keeps the latest use.
+        assert!(pool.touch(&"d", 0));
+
+        let mut disposed = Vec::new();
+        assert!(pool.evict_until(1000, &mut disposed).is_empty());
+        assert_eq!(pool.evict_until(600, &mut disposed), ["b", "c"]);
+        assert_eq!(disposed, ["b", "c"]);
+        assert_eq!(pool.size, 500);
+        assert_eq!(pool.evict_until(0, &mut disposed), ["d", "a"]);
+        assert_eq!(pool.size, 0);
+        assert!(pool.entries.is_empty());
+    }
+
+    #[test]
+    fn pinned_are_never_evicted() {
+        let mut pool = pool(&[("a", 100, 1), ("b", 200, 2), ("c", 300, 3)]);
+        let pin = pool.pin(&"a").unwrap();
+        let other = pool.pin(&"a").unwrap();
+        assert!(pool.pin(&"e").is_none());
+        assert!(pool.is_pinned(&"a") && !pool.is_pinned(&"b"));
+
+        let mut disposed = Vec::new();
+        assert_eq!(pool.evict_until(0, &mut disposed), ["b", "c"]);
+        assert_eq!(pool.size, 100);
+
+        drop(pin);
+        assert!(pool.is_pinned(&"a"));
+        drop(other);
+        assert!(!pool.is_pinned(&"a"));
+        assert_eq!(pool.evict_until(0, &mut disposed), ["a"]);
+        assert_eq!(disposed, ["b", "c", "a"]);
+    }
+
+    #[test]
+    fn replacing_keeps_pins_and_size() {
+        let mut pool = pool(&[("a", 100, 1), ("b", 200, 2)]);
+        let pin = pool.pin(&"a").unwrap();
+        assert_eq!(pool.insert("a", Mock("a2", 50), 3), Some(Mock("a", 100)));
+        assert_eq!(pool.size, 250);
+        assert!(pool.is_pinned(&"a"));
+
+        let mut disposed = Vec::new();
+        assert_eq!(pool.evict_until(0, &mut disposed), ["b"]);
+        drop(pin);
+        assert_eq!(pool.evict_until(0, &mut disposed), ["a"]);
+        assert_eq!(disposed, ["b", "a2"]);
+
+        assert_eq!(pool.remove(&"a"), None);
+        pool.insert("c", Mock("c", 10), 4);
+        assert_eq!(pool.remove(&"c"), Some(Mock("c", 10)));
+        assert_eq!(pool.size, 0);
+    }
+}
@@ -0,0 +1,16 @@
+//! Configuration presets for common texture semantics.
+
+/// Texture semantics with established configuration.
+/// Presets assume 8-bit RGBA data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TexturePreset {
+    /// Base color. sRGB with mips filtered in linear space.
+    Albedo,
+    /// Tangent-space normals. Linear with renormalized mips.
+    NormalMap,
+    /// Packed metallic and roughness. Linear with mips.
+    MetallicRoughness,
+    /// Ambient occlusion in red channel. Linear with mips, red replicated to color.
+    Occlusion,
+}
@@ -0,0 +1,309 @@
+//! Reading texels of textures back to host.
+
+use std::borrow::Borrow;
+use std::ops::Range;
+
+use hal::command::{BufferImageCopy, RawCommandBuffer};
+use hal::format::{Aspects, Format};
+use hal::image::{
+    Access, Extent, Layout, Offset, Subresource, SubresourceFootprint, SubresourceLayers,
+    SubresourceRange, Tiling, Usage,
+};
+use hal::memory::{Barrier, Dependencies, Properties};
+use hal::pso::PipelineStage;
+use hal::{buffer, Backend, Device};
+
+use render::Factory;
+
+use command;
+use error::TextureError;
+use {aspects_for, data_aspects, Texture};
+
+/// Mapped memory of linear host-visible texture.
+/// Memory is unmapped when reader is dropped.
+#[derive(Debug)]
+pub struct MappedReader<'a, B: Backend> {
+    device: &'a B::Device,
+    memory: &'a B::Memory,
+    ptr: *const u8,
+    footprint: SubresourceFootprint,
+    row_size: usize,
+    height: u32,
+}
+
+impl<'a, B> MappedReader<'a, B>
+where
+    B: Backend,
+{
+    /// Bytes of texels in row `y` of base level and first layer.
+    /// Padding up to row pitch is not included.
+    pub fn row(&self, y: u32) -> &[u8] {
+        assert!(y < self.height);
+        let range = row_range(&self.footprint, self.row_size, y);
+        unsafe { ::std::slice::from_raw_parts(self.ptr.add(range.start), range.len()) }
+    }
+
+    /// Number of rows.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Number of bytes between starts of rows.
+    pub fn row_pitch(&self) -> u64 {
+        self.footprint.row_pitch
+    }
+}
+
+impl<'a, B> Drop for MappedReader<'a, B>
+where
+    B: Backend,
+{
+    fn drop(&mut self) {
+        self.device.unmap_memory(self.memory);
+    }
+}
+
+impl<B> Texture<B>
+where
+    B: Backend,
+{
+    /// Map memory of linear host-visible texture to read it without staging copy.
+    /// Such textures are built with `prefer_unified_memory`.
+    /// Writes of submitted commands must be complete before mapping.
+    pub fn map_read<'a>(
+        &'a self,
+        factory: &'a Factory<B>,
+    ) -> Result<MappedReader<'a, B>, TextureError> {
+        let block = self.image.block();
+        if self.tiling != Tiling::Linear || !block.properties().contains(Properties::CPU_VISIBLE) {
+            Err(TextureError::NotHostVisible)?;
+        }
+        let footprint = factory.get_image_subresource_footprint(
+            self.image.borrow(),
+            Subresource {
+                aspects: data_aspects(self.format),
+                level: 0,
+                layer: 0,
+            },
+        );
+        let ptr = factory.map_memory(block.memory(), block.range())?;
+        if !block.properties().contains(Properties::COHERENT) {
+            factory.invalidate_mapped_memory_ranges(Some((block.memory(), block.range())));
+        }
+        let extent = self.kind.extent();
+        Ok(MappedReader {
+            device: factory,
+            memory: block.memory(),
+            ptr,
+            footprint,
+            row_size: extent.width as usize * (self.format.surface_desc().bits as usize / 8),
+            height: extent.height,
+        })
+    }
+
+    /// Read depth of the texel at `x`, `y` of base level and first layer.
+    /// Copy is submitted to `queue` using command buffer from `pool`
+    /// and waited for, so this is meant for occasional queries like picking.
+    /// Texture must be created with `Usage::TRANSFER_SRC`.
+    /// Normalized formats are converted to `0.0 ..= 1.0` range.
+    pub fn read_depth(
+        &self,
+        factory: &mut Factory<B>,
+        queue: &mut B::CommandQueue,
+        pool: &mut B::CommandPool,
+        x: u32,
+        y: u32,
+    ) -> Result<f32, TextureError> {
+        let texel_size = match depth_texel_size(self.format) {
+            Some(size) => size,
+            None => Err(TextureError::DecodeUnsupportedFormat {
+                format: self.format,
+            })?,
+        };
+        if !self.usage.contains(Usage::TRANSFER_SRC) {
+            Err(TextureError::MissingUsage {
+                usage: Usage::TRANSFER_SRC,
+            })?;
+        }
+        let image_layers = SubresourceLayers {
+            aspects: Aspects::DEPTH,
+            level: 0,
+            layers: 0..1,
+        };
+        let image_offset = Offset {
+            x: x as i32,
+            y: y as i32,
+            z: 0,
+        };
+        let image_extent = Extent {
+            width: 1,
+            height: 1,
+            depth: 1,
+        };
+        if x > i32::MAX as u32 || y > i32::MAX as u32 {
+            Err(TextureError::RegionOutOfBounds {
+                layers: image_layers.clone(),
+                offset: image_offset,
+                extent: image_extent,
+            })?;
+        }
+        self.check_region(&image_layers, image_offset, image_extent)?;
+
+        let buffer = factory.create_buffer(
+            texel_size,
+            texel_size,
+            buffer::Usage::TRANSFER_DST,
+            Properties::CPU_VISIBLE,
+        )?;
+
+        let range = SubresourceRange {
+            aspects: aspects_for(self.format),
+            levels: 0..self.levels,
+            layers: 0..self.kind.num_layers(),
+        };
+        let state = (self.access, self.layout);
+        let transfer = (Access::TRANSFER_READ, Layout::TransferSrcOptimal);
+
+        let copied = command::submit_once(factory, queue, pool, |_, cmd| {
+            command::image_barrier::<B>(
+                cmd,
+                self.image.borrow(),
+                range.clone(),
+                state..transfer,
+                PipelineStage::all()..PipelineStage::TRANSFER,
+            );
+            cmd.copy_image_to_buffer(
+                self.image.borrow(),
+                Layout::TransferSrcOptimal,
+                buffer.borrow(),
+                Some(BufferImageCopy {
+                    buffer_offset: 0,
+                    buffer_width: 0,
+                    buffer_height: 0,
+                    image_layers,
+                    image_offset,
+                    image_extent,
+                }),
+            );
+            cmd.pipeline_barrier(
+                PipelineStage::TRANSFER..PipelineStage::HOST,
+                Dependencies::empty(),
+                Some(Barrier::Buffer {
+                    states: buffer::Access::TRANSFER_WRITE..buffer::Access::HOST_READ,
+                    target: buffer.borrow(),
+                }),
+            );
+            command::image_barrier::<B>(
+                cmd,
+                self.image.borrow(),
+                range,
+                transfer..state,
+                PipelineStage::TRANSFER..PipelineStage::all(),
+            );
+            Ok(())
+        });
+
+        let bytes =
+            copied.and_then(|()| command::read_staging(factory, &buffer, texel_size as usize));
+        factory.destroy_buffer(buffer);
+        Ok(decode_depth(self.format, &bytes?))
+    }
+}
+
+/// Bytes of row `y` in mapped memory without padding up to row pitch.
+fn row_range(footprint: &SubresourceFootprint, row_size: usize, y: u32) -> Range<usize> {
+    let start = (footprint.slice.start + u64::from(y) * footprint.row_pitch) as usize;
+    start..start + row_size
+}
+
+/// Size of depth aspect texel copied into buffer.
+/// Depth of packed depth-stencil formats is copied without stencil.
+fn depth_texel_size(format: Format) -> Option<u64> {
+    match format {
+        Format::D16Unorm | Format::D16UnormS8Uint => Some(2),
+        Format::X8D24Unorm | Format::D24UnormS8Uint | Format::D32Float | Format::D32FloatS8Uint => {
+            Some(4)
+        }
+        _ => None,
+    }
+}
+
+/// Decode depth texel copied into buffer.
+/// 24-bit depth occupies low bits of 32-bit word.
+fn decode_depth(format: Format, bytes: &[u8]) -> f32 {
+    match format {
+        Format::D16Unorm | Format::D16UnormS8Uint => {
+            f32::from(u16::from_ne_bytes([bytes[0], bytes[1]])) / f32::from(u16::MAX)
+        }
+        Format::X8D24Unorm | Format::D24UnormS8Uint => {
+            let word = u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+            (word & 0x00ff_ffff) as f32 / 0x00ff_ffff as f32
+        }
+        _ => f32::from_bits(u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mapped_rows_respect_pitch() {
+        // 3x2 RGBA8 image placed at offset 8 with rows padded to 16 bytes.
+        let mut memory = [0u8; 8 + 16 * 2];
+        memory[8 + 16 + 4..8 + 16 + 8].copy_from_slice(&[1, 2, 3, 4]);
+        let footprint = SubresourceFootprint {
+            slice: 8..40,
+            row_pitch: 16,
+            array_pitch: 32,
+            depth_pitch: 32,
+        };
+        let row = &memory[row_range(&footprint, 12, 1)];
+        assert_eq!(row.len(), 12);
+        assert_eq!(row[4..8], [1, 2, 3, 4]);
+        assert_eq!(row_range(&footprint, 12, 0), 8..20);
+    }
+
+    #[test]
+    fn depth_is_decoded_to_float() {
+        let depth = |format, bytes: &[u8]| {
+            assert_eq!(depth_texel_size(format), Some(bytes.len() as u64));
+            decode_depth(format, bytes)
+        };
+        assert_eq!(depth(Format::D16Unorm, &u16::MAX.to_ne_bytes()), 1.0);
+        assert_eq!(depth(Format::D16UnormS8Uint, &0u16.to_ne_bytes()), 0.0);
+        // Stencil bits in the high byte are ignored.
+        let word = 0xff00_0000u32 | 0x0080_0000;
+        assert!((depth(Format::D24UnormS8Uint, &word.to_ne_bytes()) - 0.5).abs() < 1e-6);
+        assert_eq!(depth(Format::D32Float, &0.25f32.to_ne_bytes()), 0.25);
+        assert_eq!(depth_texel_size(Format::Rgba8Unorm), None);
+    }
+
+    #[test]
+    fn depth_buffer_upload_reads_back() {
+        use hal::image::Kind;
+        use TextureBuilder;
+
+        let depths = (0..16).map(|i| i as f32 / 16.0).collect::<Vec<_>>();
+        let builder = TextureBuilder::new(Kind::D2(4, 4, 1, 1))
+            .with_format(Format::D32Float)
+            .with_data(depths.clone());
+        builder.validate().unwrap();
+        assert_eq!(
+            builder.resolved().final_layout,
+            Layout::DepthStencilReadOnlyOptimal
+        );
+
+        let uploads = builder.upload_levels().unwrap();
+        let regions = builder.upload_regions(&uploads, &[0]);
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions[0].image_layers.aspects, Aspects::DEPTH);
+        assert_eq!(regions[0].image_extent, Kind::D2(4, 4, 1, 1).extent());
+
+        let texels = uploads[0].1.chunks(4);
+        let read = texels
+            .map(|texel| decode_depth(Format::D32Float, texel))
+            .collect::<Vec<_>>();
+        assert_eq!(read, depths);
+    }
+}
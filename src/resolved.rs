@@ -0,0 +1,119 @@
+use hal::format::{Format, Swizzle};
+use hal::image::{Kind, Layer, Layout, Level, StorageFlags, Tiling, Usage};
+use hal::memory::Properties;
+
+#[cfg(feature = "compress-bc")]
+use compress::{BlockCompression, CompressionHint, CompressionQuality};
+use convert::{BorderMode, DitherMode};
+use format::{AstcProfile, Endianness};
+#[cfg(feature = "serde")]
+use kind;
+use mip::MipFilter;
+use sampling::{LodClamp, TextureSampling};
+use {OomPolicy, PreTransform};
+
+/// Parameters `TextureBuilder::build` uses with all defaults resolved.
+/// Suitable for serialization as reproducible cache key.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ResolvedTextureParams {
+    /// Kind of the image.
+    #[cfg_attr(feature = "serde", serde(with = "kind::serde_kind"))]
+    pub kind: Kind,
+    /// Format of the image.
+    pub format: Format,
+    /// Tiling of the image when uploaded through staging buffer.
+    pub tiling: Tiling,
+    /// Usage flags of the image.
+    pub usage: Usage,
+    /// Memory properties of the image when uploaded through staging buffer.
+    pub properties: Properties,
+    /// Storage flags of the image.
+    pub storage_flags: StorageFlags,
+    /// Whether unified memory is allowed for single level textures.
+    pub prefer_unified_memory: bool,
+    /// Number of mip levels.
+    pub levels: Level,
+    /// Number of array layers.
+    pub layers: Layer,
+    /// Row length of base level data in texels.
+    pub data_width: u32,
+    /// Image height of base level data in texels.
+    pub data_height: u32,
+    /// Most detailed level uploaded on build.
+    pub min_resident_level: Level,
+    /// Whether mip levels are generated on CPU.
+    pub generate_mips: bool,
+    /// Filter of generated mips.
+    pub mip_filter: MipFilter,
+    /// Whether generated normals are renormalized.
+    pub mip_renormalize: bool,
+    /// Whether mips are generated in linear space.
+    pub mip_gamma_correct: bool,
+    /// Alpha test threshold preserved in generated mips.
+    pub alpha_coverage: Option<f32>,
+    /// ASTC profile data is encoded with.
+    pub astc_profile: AstcProfile,
+    /// Byte order of multi-byte channels in source data.
+    pub source_endianness: Endianness,
+    /// Swizzle of the view.
+    pub swizzle: Swizzle,
+    /// Whether non-finite floating point values are replaced.
+    pub sanitize_non_finite: bool,
+    /// Sampler state the texture is intended to be used with.
+    pub sampling: Option<TextureSampling>,
+    /// Level of detail clamp and bias the texture is intended to be sampled with.
+    pub lod_clamp: Option<LodClamp>,
+    /// Whether wrapping sampling requires power-of-two extent.
+    pub pot_wrap_required: bool,
+    /// Key color and tolerance of chroma keying.
+    pub chroma_key: Option<([u8; 3], u8)>,
+    /// Whether keyed texels get color of opaque neighbours.
+    pub chroma_bleed: bool,
+    /// Format of the provided data.
+    pub source_format: Format,
+    /// Dithering applied on format conversion.
+    pub dither: DitherMode,
+    /// Color filling levels without provided data.
+    pub clear_unspecified_levels: Option<[f32; 4]>,
+    /// Width of edge band cross-faded to make texture tileable.
+    pub tileable_blend: Option<u32>,
+    /// Width and fill mode of border added around base level.
+    pub border: Option<(u32, BorderMode)>,
+    /// Block compression applied on build.
+    #[cfg(feature = "compress-bc")]
+    pub compress: Option<(BlockCompression, CompressionQuality)>,
+    /// Hint automatic block compression is chosen for on build.
+    #[cfg(feature = "compress-bc")]
+    pub auto_compression: Option<CompressionHint>,
+    /// Handling of out of memory error.
+    pub oom_policy: OomPolicy,
+    /// Rotation composition code should apply to the texture.
+    pub pre_transform: PreTransform,
+    /// Layout texture is left in after upload.
+    pub final_layout: Layout,
+    /// Whether data of depth-stencil formats is uploaded into depth aspect only.
+    pub per_aspect_upload: bool,
+    /// Whether base level data past last row and slice is ignored.
+    pub remaining_bytes_ignored: bool,
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    extern crate serde_json;
+
+    use super::*;
+    use TextureBuilder;
+
+    #[test]
+    fn serde_round_trip() {
+        let params = TextureBuilder::new(Kind::D2(16, 8, 2, 1))
+            .with_format(Format::Bc1RgbUnorm)
+            .with_generate_mips(true)
+            .resolved();
+        let json = serde_json::to_string(&params).unwrap();
+        let decoded: ResolvedTextureParams = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, params);
+        assert_eq!((decoded.levels, decoded.layers), (5, 2));
+    }
+}
@@ -0,0 +1,123 @@
+use hal::image::{Filter, PackedColor, SamplerInfo, WrapMode};
+use hal::{Backend, Features, PhysicalDevice};
+
+use render::Factory;
+
+/// Sampler state the texture is intended to be sampled with.
+/// Stored alongside the texture for engine code building samplers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TextureSampling {
+    /// Wrap mode along U axis.
+    pub wrap_u: WrapMode,
+    /// Wrap mode along V axis.
+    pub wrap_v: WrapMode,
+    /// Wrap mode along W axis.
+    pub wrap_w: WrapMode,
+    /// Border color for `WrapMode::Border`.
+    pub border: PackedColor,
+    /// Filter used for minification.
+    pub min_filter: Filter,
+    /// Filter used for magnification.
+    pub mag_filter: Filter,
+    /// Filter used between mip levels.
+    pub mip_filter: Filter,
+}
+
+impl TextureSampling {
+    /// Same wrap mode along all axes with linear filtering.
+    pub fn new(wrap: WrapMode) -> Self {
+        TextureSampling {
+            wrap_u: wrap,
+            wrap_v: wrap,
+            wrap_w: wrap,
+            border: PackedColor(0),
+            min_filter: Filter::Linear,
+            mag_filter: Filter::Linear,
+            mip_filter: Filter::Linear,
+        }
+    }
+
+    /// Check if any axis uses wrap mode other than `WrapMode::Clamp`.
+    pub fn wraps(&self) -> bool {
+        [self.wrap_u, self.wrap_v, self.wrap_w]
+            .iter()
+            .any(|&wrap| wrap != WrapMode::Clamp)
+    }
+
+    /// Sampler info with this state and default level of detail range.
+    pub fn sampler_info(&self) -> SamplerInfo {
+        let mut info = SamplerInfo::new(self.min_filter, self.wrap_u);
+        info.mag_filter = self.mag_filter;
+        info.mip_filter = self.mip_filter;
+        info.wrap_mode = (self.wrap_u, self.wrap_v, self.wrap_w);
+        info.border = self.border;
+        info
+    }
+}
+
+impl Default for TextureSampling {
+    fn default() -> Self {
+        TextureSampling::new(WrapMode::Tile)
+    }
+}
+
+/// Level of detail range and bias the texture is intended to be sampled with,
+/// e.g. to keep sampling away from mips that are not streamed in yet.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LodClamp {
+    /// Most detailed level of detail sampled.
+    pub min_lod: f32,
+    /// Least detailed level of detail sampled.
+    pub max_lod: f32,
+    /// Bias added to computed level of detail.
+    pub lod_bias: f32,
+}
+
+impl LodClamp {
+    /// Clamp level of detail to `min_lod ..= max_lod` without bias.
+    pub fn new(min_lod: f32, max_lod: f32) -> Self {
+        LodClamp {
+            min_lod,
+            max_lod,
+            lod_bias: 0.0,
+        }
+    }
+}
+
+/// Clamp requested anisotropy to device limit.
+/// Returns `1.0` when anisotropic filtering isn't supported.
+pub fn clamp_anisotropy<B>(factory: &Factory<B>, requested: f32) -> f32
+where
+    B: Backend,
+{
+    let physical = factory.physical();
+    clamp_to_limit(
+        physical.features(),
+        physical.limits().max_sampler_anisotropy,
+        requested,
+    )
+}
+
+/// Clamp requested anisotropy to `limit` of device with `features`.
+fn clamp_to_limit(features: Features, limit: f32, requested: f32) -> f32 {
+    if !features.contains(Features::SAMPLER_ANISOTROPY) {
+        return 1.0;
+    }
+    requested.max(1.0).min(limit.max(1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anisotropy_is_clamped_to_limit() {
+        let features = Features::SAMPLER_ANISOTROPY;
+        assert_eq!(clamp_to_limit(features, 8.0, 16.0), 8.0);
+        assert_eq!(clamp_to_limit(features, 8.0, 4.0), 4.0);
+        assert_eq!(clamp_to_limit(features, 8.0, 0.0), 1.0);
+        assert_eq!(clamp_to_limit(Features::empty(), 8.0, 16.0), 1.0);
+    }
+}
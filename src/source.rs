@@ -0,0 +1,217 @@
+//! Integration point for image decoders.
+
+use hal::format::Format;
+use hal::image::{Extent, Kind, Layer};
+
+use error::TextureError;
+use {check_level_size, TextureBuilder};
+
+/// Texture decoded from some image container.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecodedTexture {
+    /// Kind of the image.
+    pub kind: Kind,
+    /// Format of the data.
+    pub format: Format,
+    /// Tightly packed data of each mip level starting from base,
+    /// with all layers of the level following each other.
+    pub levels: Vec<Vec<u8>>,
+}
+
+/// Tightly packed texels of one layer of array texture.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LayerSource<'a> {
+    /// Width in texels.
+    pub width: u32,
+    /// Height in texels.
+    pub height: u32,
+    /// Texels of the layer.
+    pub data: &'a [u8],
+}
+
+/// Decoder of a texture from some image container.
+/// Implemented by loaders of this crate and can be implemented by other crates
+/// to plug their loaders into `TextureBuilder::from_source`.
+pub trait TextureSource {
+    /// Decode the texture.
+    /// Errors of the decoder can be wrapped with `TextureError::decode`.
+    fn decode(&self) -> Result<DecodedTexture, TextureError>;
+}
+
+impl TextureSource for DecodedTexture {
+    fn decode(&self) -> Result<DecodedTexture, TextureError> {
+        Ok(self.clone())
+    }
+}
+
+impl TextureBuilder<'static> {
+    /// Create builder from decoded texture.
+    /// Size of each level is checked against kind and format.
+    pub fn from_source(source: &dyn TextureSource) -> Result<Self, TextureError> {
+        let decoded = source.decode()?;
+        if decoded.levels.is_empty() {
+            check_level_size(decoded.format, decoded.kind, 0, &[])?;
+        }
+        for (level, data) in decoded.levels.iter().enumerate() {
+            check_level_size(decoded.format, decoded.kind, level as u8, data)?;
+        }
+        Ok(decoded.into_builder())
+    }
+}
+
+impl TextureBuilder<'static> {
+    /// Create builder of 2D array texture with layers in order of `layers`.
+    /// All layers must have extent of the first one and data of matching size,
+    /// and there must be at least one and at most `Layer::MAX` layers.
+    /// Data is copied into single owned buffer.
+    pub fn from_layers(layers: &[LayerSource], format: Format) -> Result<Self, TextureError> {
+        if layers.is_empty() || layers.len() > Layer::MAX as usize {
+            Err(TextureError::ArrayLayerCount {
+                count: layers.len(),
+            })?;
+        }
+        let (width, height) = (layers[0].width, layers[0].height);
+        let extent = |layer: &LayerSource| Extent {
+            width: layer.width,
+            height: layer.height,
+            depth: 1,
+        };
+        for (index, layer) in layers.iter().enumerate() {
+            if (layer.width, layer.height) != (width, height) {
+                Err(TextureError::LayerExtentMismatch {
+                    layer: index as Layer,
+                    extent: extent(layer),
+                    expected: extent(&layers[0]),
+                })?;
+            }
+            check_level_size(format, Kind::D2(width, height, 1, 1), 0, layer.data)?;
+        }
+
+        let chunks = layers.iter().map(|layer| layer.data).collect::<Vec<_>>();
+        Ok(
+            TextureBuilder::new(Kind::D2(width, height, layers.len() as Layer, 1))
+                .with_format(format)
+                .with_data_chunks(&chunks),
+        )
+    }
+}
+
+impl DecodedTexture {
+    /// Create builder without checking sizes of levels.
+    pub(crate) fn into_builder(self) -> TextureBuilder<'static> {
+        let mut levels = self.levels.into_iter();
+        let mut builder = TextureBuilder::new(self.kind)
+            .with_format(self.format)
+            .with_data(levels.next().unwrap_or_default());
+        for (level, data) in levels.enumerate() {
+            builder.set_mip_data(level as u8 + 1, data);
+        }
+        builder
+    }
+}
+
+/// Append opaque alpha to RGB texels with `channel_size` bytes per channel.
+#[cfg(any(feature = "gltf", feature = "load-png"))]
+pub(crate) fn expand_alpha(pixels: &[u8], channel_size: usize) -> Vec<u8> {
+    let texel_size = channel_size * 3;
+    let mut data = Vec::with_capacity(pixels.len() / 3 * 4);
+    for texel in pixels.chunks_exact(texel_size) {
+        data.extend_from_slice(texel);
+        data.extend(::std::iter::repeat_n(0xFF, channel_size));
+    }
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layers_are_stacked_into_array() {
+        let data = [[1u8; 16], [2; 16], [3; 16]];
+        let layers = data
+            .iter()
+            .map(|data| LayerSource {
+                data,
+                width: 2,
+                height: 2,
+            })
+            .collect::<Vec<_>>();
+        let builder = TextureBuilder::from_layers(&layers, Format::Rgba8Unorm).unwrap();
+        assert_eq!(builder.kind, Kind::D2(2, 2, 3, 1));
+        assert_eq!(&builder.data[..16], &[1; 16][..]);
+        assert_eq!(&builder.data[32..], &[3; 16][..]);
+        builder.validate().unwrap();
+
+        let mismatched = [
+            layers[0],
+            LayerSource {
+                data: &[0; 36],
+                width: 3,
+                height: 3,
+            },
+        ];
+        match TextureBuilder::from_layers(&mismatched, Format::Rgba8Unorm) {
+            Err(TextureError::LayerExtentMismatch { layer: 1, .. }) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+        match TextureBuilder::from_layers(&layers[..0], Format::Rgba8Unorm) {
+            Err(TextureError::ArrayLayerCount { count: 0 }) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+        let many = vec![layers[0]; Layer::MAX as usize + 1];
+        match TextureBuilder::from_layers(&many, Format::Rgba8Unorm) {
+            Err(TextureError::ArrayLayerCount { count }) => assert_eq!(count, 65_536),
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    /// Source as a third-party crate would implement it.
+    struct Stripes {
+        width: u32,
+        levels: u8,
+    }
+
+    impl TextureSource for Stripes {
+        fn decode(&self) -> Result<DecodedTexture, TextureError> {
+            let levels = (0..self.levels)
+                .map(|level| vec![level; (self.width >> level) as usize * 4])
+                .collect();
+            Ok(DecodedTexture {
+                kind: Kind::D1(self.width, 1),
+                format: Format::Rgba8Unorm,
+                levels,
+            })
+        }
+    }
+
+    #[test]
+    fn custom_source_round_trips() {
+        let builder = TextureBuilder::from_source(&Stripes {
+            width: 4,
+            levels: 3,
+        })
+        .unwrap();
+        assert_eq!(builder.kind, Kind::D1(4, 1));
+        assert_eq!(builder.format, Format::Rgba8Unorm);
+        assert_eq!(&*builder.data, &[0; 16][..]);
+        assert_eq!(builder.mip_data, [vec![1; 8], vec![2; 4]]);
+        builder.validate().unwrap();
+
+        let mut decoded = Stripes {
+            width: 4,
+            levels: 2,
+        }
+        .decode()
+        .unwrap();
+        decoded.levels[1].pop();
+        match TextureBuilder::from_source(&decoded) {
+            Err(TextureError::LevelSize {
+                level: 1,
+                expected: 8,
+                actual: 7,
+            }) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+}
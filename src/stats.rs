@@ -0,0 +1,143 @@
+//! Statistics of texture data.
+
+use hal::format::{Format, SurfaceType};
+
+/// Statistics of a single channel.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ChannelStats {
+    /// Minimal value.
+    pub min: f32,
+    /// Maximal value.
+    pub max: f32,
+    /// Average value.
+    pub mean: f32,
+    /// Number of texels with each of 256 values.
+    /// Available only for 8-bit channels.
+    pub histogram: Option<Vec<u32>>,
+}
+
+/// Statistics of base level data of all layers.
+/// Values are normalized as sampled by shader without color space conversion.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TextureStats {
+    /// Number of texels.
+    pub texels: usize,
+    /// Statistics of channels in RGBA order.
+    pub channels: Vec<ChannelStats>,
+    /// Fraction of texels with alpha not less than the threshold.
+    /// Available only for formats with alpha channel.
+    pub alpha_coverage: Option<f32>,
+}
+
+/// Amount of data uploaded when texture is built.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct UploadStats {
+    /// Bytes of data written into the image including row padding of the source data.
+    pub bytes_uploaded: u64,
+    /// Total size of staging buffers. Zero when data is written directly into mapped image.
+    pub staging_buffer_size: u64,
+    /// Number of level and layer pairs written.
+    pub subresources: u32,
+}
+
+/// Collect statistics from decoded texels.
+pub(crate) fn collect(
+    format: Format,
+    channels: usize,
+    texels: &[f32],
+    alpha_threshold: f32,
+) -> TextureStats {
+    let surface = format.base_format().0;
+    let bytes = format.surface_desc().bits as usize / 8 / channels;
+    let bgr = surface == SurfaceType::B8_G8_R8 || surface == SurfaceType::B8_G8_R8_A8;
+    let count = texels.len() / channels;
+
+    let mut stats = (0..channels)
+        .map(|_| ChannelStats {
+            min: f32::INFINITY,
+            max: f32::NEG_INFINITY,
+            mean: 0.0,
+            histogram: if bytes == 1 { Some(vec![0; 256]) } else { None },
+        })
+        .collect::<Vec<_>>();
+
+    let mut sums = vec![0.0f64; channels];
+    for texel in texels.chunks_exact(channels) {
+        for (channel, &value) in texel.iter().enumerate() {
+            let stats = &mut stats[channel];
+            stats.min = stats.min.min(value);
+            stats.max = stats.max.max(value);
+            sums[channel] += value as f64;
+            if let Some(ref mut histogram) = stats.histogram {
+                histogram[(value * 255.0 + 0.5) as usize] += 1;
+            }
+        }
+    }
+    for (stats, sum) in stats.iter_mut().zip(sums) {
+        if count > 0 {
+            stats.mean = (sum / count as f64) as f32;
+        }
+    }
+
+    if bgr {
+        stats.swap(0, 2);
+    }
+
+    let alpha_coverage = if channels == 4 && count > 0 {
+        let covered = texels
+            .chunks_exact(4)
+            .filter(|texel| texel[3] >= alpha_threshold)
+            .count();
+        Some(covered as f32 / count as f32)
+    } else {
+        None
+    };
+
+    TextureStats {
+        texels: count,
+        channels: stats,
+        alpha_coverage,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hal::image::Kind;
+
+    use super::*;
+    use TextureBuilder;
+
+    #[test]
+    fn rgba8_stats_skip_row_padding() {
+        // 2x2 texels in rows of 3, padding texels are opaque white.
+        let data = vec![
+            0, 0, 0, 0, 255, 0, 0, 128, 255, 255, 255, 255, //
+            0, 0, 0, 255, 255, 0, 0, 255, 255, 255, 255, 255,
+        ];
+        let stats = TextureBuilder::new(Kind::D2(2, 2, 1, 1))
+            .with_format(Format::Rgba8Unorm)
+            .with_data_bytes(data)
+            .with_data_width(3)
+            .statistics(0.5)
+            .unwrap();
+        assert_eq!(stats.texels, 4);
+        let red = &stats.channels[0];
+        assert_eq!((red.min, red.max, red.mean), (0.0, 1.0, 0.5));
+        let histogram = red.histogram.as_ref().unwrap();
+        assert_eq!((histogram[0], histogram[255]), (2, 2));
+        assert_eq!(stats.channels[2].max, 0.0);
+        assert_eq!(stats.alpha_coverage, Some(0.75));
+    }
+
+    #[test]
+    fn wide_channels_have_no_histogram() {
+        let texels = [0.0, 0.25, 0.5, 1.0];
+        let stats = collect(Format::R16Unorm, 1, &texels, 0.5);
+        assert_eq!(stats.channels[0].histogram, None);
+        assert_eq!(stats.channels[0].mean, 0.4375);
+        assert_eq!(stats.alpha_coverage, None);
+    }
+}
@@ -0,0 +1,326 @@
+//! Textures updated every frame through persistent staging buffers.
+
+use std::borrow::Borrow;
+
+use hal::command::{BufferImageCopy, CommandBufferFlags, RawCommandBuffer};
+use hal::image::{Access, Layout, Offset, SubresourceLayers, SubresourceRange, Usage};
+use hal::memory::Properties;
+use hal::pool::{CommandPoolCreateFlags, RawCommandBufferLevel, RawCommandPool};
+use hal::pso::PipelineStage;
+use hal::queue::{QueueFamilyId, RawCommandQueue, RawSubmission};
+use hal::{buffer, Backend, Device};
+
+use render::{Buffer, Factory};
+
+use command;
+use error::TextureError;
+use format;
+use {aspects_for, data_aspects, layout_stages, Texture};
+
+/// Staging buffer with commands copying it into the texture.
+#[derive(Debug)]
+struct Slot<B: Backend> {
+    buffer: Buffer<B>,
+    cmd: B::CommandBuffer,
+    fence: B::Fence,
+}
+
+/// Texture which base level is rewritten every frame.
+/// Frames are written into a ring of staging buffers,
+/// each reused only after the copy from it is complete.
+#[derive(Debug)]
+pub struct StreamingTexture<B: Backend> {
+    texture: Texture<B>,
+    pool: B::CommandPool,
+    slots: Vec<Slot<B>>,
+    ring: SlotRing,
+    frame_size: usize,
+}
+
+/// Order in which staging slots are reused.
+/// Slots are submitted in ring order, so the next one is the oldest.
+#[derive(Debug)]
+struct SlotRing {
+    next: usize,
+    len: usize,
+}
+
+impl SlotRing {
+    fn new(len: usize) -> Self {
+        SlotRing { next: 0, len }
+    }
+
+    /// Index of the oldest slot.
+    /// `wait` is called with it only if `complete` reports the slot still in flight.
+    fn acquire<C, W>(&self, complete: C, wait: W) -> usize
+    where
+        C: FnOnce(usize) -> bool,
+        W: FnOnce(usize),
+    {
+        if !complete(self.next) {
+            wait(self.next);
+        }
+        self.next
+    }
+
+    /// Move to the next slot after the acquired one is submitted.
+    fn submitted(&mut self) {
+        self.next = (self.next + 1) % self.len;
+    }
+}
+
+impl<B> StreamingTexture<B>
+where
+    B: Backend,
+{
+    /// Number of staging buffers enough to not wait for GPU at usual frame latency.
+    pub const DEFAULT_SLOTS: usize = 3;
+
+    /// Wrap texture to stream frames into it from queue of the `family`.
+    /// Texture must be created with `Usage::TRANSFER_DST` and `slots` must not be zero.
+    pub fn new(
+        texture: Texture<B>,
+        slots: usize,
+        family: QueueFamilyId,
+        factory: &mut Factory<B>,
+    ) -> Result<Self, TextureError> {
+        if slots == 0 {
+            Err(TextureError::NoStagingSlots)?;
+        }
+        if !texture.usage.contains(Usage::TRANSFER_DST) {
+            Err(TextureError::MissingUsage {
+                usage: Usage::TRANSFER_DST,
+            })?;
+        }
+
+        let extent = texture.kind.extent();
+        let frame_size = format::strided_size(
+            texture.format,
+            extent,
+            extent.width,
+            extent.height,
+            texture.kind.num_layers(),
+        );
+        let desc = texture.format.surface_desc();
+
+        let mut buffers = Vec::with_capacity(slots);
+        for _ in 0..slots {
+            match factory.create_buffer(
+                desc.bits as u64 / 8 * 4,
+                frame_size as u64,
+                buffer::Usage::TRANSFER_SRC,
+                Properties::CPU_VISIBLE,
+            ) {
+                Ok(buffer) => buffers.push(buffer),
+                Err(error) => {
+                    for buffer in buffers {
+                        factory.destroy_buffer(buffer);
+                    }
+                    return Err(error.into());
+                }
+            }
+        }
+
+        let mut pool =
+            factory.create_command_pool(family, CommandPoolCreateFlags::RESET_INDIVIDUAL);
+        let cmds = pool.allocate(slots, RawCommandBufferLevel::Primary);
+        let slots = buffers
+            .into_iter()
+            .zip(cmds)
+            .map(|(buffer, cmd)| Slot {
+                buffer,
+                cmd,
+                fence: factory.create_fence(true),
+            })
+            .collect::<Vec<_>>();
+
+        Ok(StreamingTexture {
+            texture,
+            pool,
+            ring: SlotRing::new(slots.len()),
+            slots,
+            frame_size,
+        })
+    }
+
+    /// Streamed texture.
+    pub fn texture(&self) -> &Texture<B> {
+        &self.texture
+    }
+
+    /// Number of staging buffers.
+    pub fn slots(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Size of tightly packed frame data in bytes.
+    pub fn frame_size(&self) -> usize {
+        self.frame_size
+    }
+
+    /// Write tightly packed base level data of all layers and submit copy to `queue`.
+    /// `queue` must belong to the family passed to `new`.
+    /// Waits for GPU only if copies from all staging buffers are still in flight.
+    pub fn write_frame(
+        &mut self,
+        factory: &mut Factory<B>,
+        queue: &mut B::CommandQueue,
+        data: &[u8],
+    ) -> Result<(), TextureError> {
+        if data.len() < self.frame_size {
+            Err(TextureError::LevelSize {
+                level: 0,
+                expected: self.frame_size,
+                actual: data.len(),
+            })?;
+        }
+
+        let index = {
+            let (slots, factory) = (&self.slots, &*factory);
+            self.ring.acquire(
+                |index| factory.get_fence_status(&slots[index].fence),
+                |index| {
+                    factory.wait_for_fence(&slots[index].fence, !0);
+                },
+            )
+        };
+        let slot = &mut self.slots[index];
+
+        command::write_staging(factory, &slot.buffer, &[&data[..self.frame_size]], &[0])?;
+
+        let texture = &self.texture;
+        let extent = texture.kind.extent();
+        let layers = texture.kind.num_layers();
+        let range = SubresourceRange {
+            aspects: aspects_for(texture.format),
+            levels: 0..1,
+            layers: 0..layers,
+        };
+        // Frame is read by shaders between writes, previous write may still be in flight.
+        let readers = layout_stages(texture.layout);
+
+        slot.cmd
+            .begin(CommandBufferFlags::ONE_TIME_SUBMIT, Default::default());
+        command::image_barrier::<B>(
+            &mut slot.cmd,
+            texture.image.borrow(),
+            range.clone(),
+            (texture.access, texture.layout)..(Access::TRANSFER_WRITE, Layout::TransferDstOptimal),
+            (PipelineStage::TRANSFER | readers)..PipelineStage::TRANSFER,
+        );
+        command::copy_buffer_to_image::<B>(
+            &mut slot.cmd,
+            &slot.buffer,
+            texture.image.borrow(),
+            &[BufferImageCopy {
+                buffer_offset: 0,
+                buffer_width: extent.width,
+                buffer_height: extent.height,
+                image_layers: SubresourceLayers {
+                    aspects: data_aspects(texture.format),
+                    level: 0,
+                    layers: 0..layers,
+                },
+                image_offset: Offset::ZERO,
+                image_extent: extent,
+            }],
+        );
+        command::image_barrier::<B>(
+            &mut slot.cmd,
+            texture.image.borrow(),
+            range,
+            (Access::TRANSFER_WRITE, Layout::TransferDstOptimal)..(texture.access, texture.layout),
+            PipelineStage::TRANSFER..readers,
+        );
+        slot.cmd.finish();
+
+        // Fence is reset only when the slot is submitted,
+        // so failed writes don't leave it unsignaled forever.
+        factory.reset_fence(&slot.fence);
+        unsafe {
+            queue.submit_raw(
+                RawSubmission {
+                    cmd_buffers: Some(&slot.cmd),
+                    wait_semaphores: &[],
+                    signal_semaphores: &[],
+                },
+                Some(&slot.fence),
+            );
+        }
+
+        self.ring.submitted();
+        Ok(())
+    }
+
+    /// Wait for all submitted copies, destroy staging buffers and return the texture.
+    pub fn into_texture(self, factory: &mut Factory<B>) -> Texture<B> {
+        let StreamingTexture {
+            texture,
+            mut pool,
+            slots,
+            ..
+        } = self;
+
+        let mut cmds = Vec::with_capacity(slots.len());
+        for slot in slots {
+            factory.wait_for_fence(&slot.fence, !0);
+            factory.destroy_fence(slot.fence);
+            factory.destroy_buffer(slot.buffer);
+            cmds.push(slot.cmd);
+        }
+        unsafe {
+            pool.free(cmds);
+        }
+        factory.destroy_command_pool(pool);
+        texture
+    }
+
+    /// Wait for all submitted copies and destroy the texture with staging buffers.
+    pub fn dispose(self, factory: &mut Factory<B>) {
+        self.into_texture(factory).dispose(factory);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::{Cell, RefCell};
+
+    /// Stream `frames` through ring of `slots` while GPU completes copies
+    /// `latency` frames after submission. Returns number of waits.
+    fn stream(slots: usize, latency: usize, frames: usize) -> usize {
+        let mut ring = SlotRing::new(slots);
+        // Frame each slot was last submitted at.
+        let submitted = RefCell::new(vec![None; slots]);
+        let completed = Cell::new(0);
+        let mut waits = 0;
+        for frame in 0..frames {
+            // GPU finished copies of frames older than latency.
+            completed.set(completed.get().max((frame + 1).saturating_sub(latency)));
+            let complete =
+                |index: usize| submitted.borrow()[index].is_none_or(|at| at < completed.get());
+            let index = ring.acquire(complete, |index| {
+                waits += 1;
+                let at = submitted.borrow()[index].unwrap();
+                completed.set(at + 1);
+            });
+            assert!(complete(index), "slot {} reused while in flight", index);
+            assert_eq!(index, frame % slots);
+            submitted.borrow_mut()[index] = Some(frame);
+            ring.submitted();
+        }
+        waits
+    }
+
+    #[test]
+    fn slots_rotate_without_waiting_when_enough() {
+        assert_eq!(stream(3, 2, 1000), 0);
+        assert_eq!(stream(2, 2, 1000), 0);
+    }
+
+    #[test]
+    fn oldest_slot_is_waited_for_when_all_in_flight() {
+        assert_eq!(stream(1, 2, 1000), 999);
+        assert_eq!(stream(2, 3, 1000), 998);
+    }
+}
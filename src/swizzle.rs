@@ -0,0 +1,44 @@
+//! Common swizzle presets.
+
+use hal::format::{Component, Swizzle};
+
+/// Named swizzles for grayscale and luminance textures.
+/// Bring the trait into scope to write `Swizzle::rrrr()`.
+pub trait SwizzlePresets {
+    /// Replicate red channel into all components.
+    fn rrrr() -> Self;
+
+    /// Replicate red channel into color components with opaque alpha.
+    fn rrr1() -> Self;
+
+    /// Keep red and green, set blue to zero and alpha to one.
+    fn rg01() -> Self;
+}
+
+impl SwizzlePresets for Swizzle {
+    fn rrrr() -> Self {
+        Swizzle(Component::R, Component::R, Component::R, Component::R)
+    }
+
+    fn rrr1() -> Self {
+        Swizzle(Component::R, Component::R, Component::R, Component::One)
+    }
+
+    fn rg01() -> Self {
+        Swizzle(Component::R, Component::G, Component::Zero, Component::One)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets() {
+        use hal::format::Component::{One, Zero, G, R};
+
+        assert_eq!(Swizzle::rrrr(), Swizzle(R, R, R, R));
+        assert_eq!(Swizzle::rrr1(), Swizzle(R, R, R, One));
+        assert_eq!(Swizzle::rg01(), Swizzle(R, G, Zero, One));
+    }
+}
@@ -0,0 +1,220 @@
+//! Arrays of textures bound to single descriptor binding.
+
+use std::collections::BTreeSet;
+use std::mem;
+use std::ops::Range;
+
+use hal::pso::{Descriptor, DescriptorSetWrite};
+use hal::Backend;
+
+use render::Factory;
+
+use Texture;
+
+/// Ordered collection of textures with stable indices into descriptor array.
+/// Empty slots are bound to fallback texture, e.g. one made with `TextureBuilder::flat_normal`.
+/// Slots changed since last `flush` are tracked so only they are rewritten.
+#[derive(Debug)]
+pub struct TextureSet<B: Backend> {
+    fallback: Texture<B>,
+    slots: Slots<Texture<B>>,
+}
+
+impl<B> TextureSet<B>
+where
+    B: Backend,
+{
+    /// Create empty set with texture bound to empty slots.
+    pub fn new(fallback: Texture<B>) -> Self {
+        TextureSet {
+            fallback,
+            slots: Slots::new(),
+        }
+    }
+
+    /// Add texture to the set.
+    /// Returns its index, the lowest one freed by `remove` if any.
+    pub fn insert(&mut self, texture: Texture<B>) -> u32 {
+        self.slots.insert(texture)
+    }
+
+    /// Take texture out of the set. Slot is bound to fallback texture on next `flush`.
+    /// Indices of other textures stay the same.
+    /// Returned texture may still be referenced by the descriptor set until then.
+    pub fn remove(&mut self, index: u32) -> Option<Texture<B>> {
+        self.slots.remove(index)
+    }
+
+    /// Texture at index.
+    pub fn get(&self, index: u32) -> Option<&Texture<B>> {
+        self.slots.get(index)
+    }
+
+    /// Texture bound to empty slots.
+    pub fn fallback(&self) -> &Texture<B> {
+        &self.fallback
+    }
+
+    /// Number of slots, including empty ones.
+    /// Descriptor array must be at least that large.
+    pub fn capacity(&self) -> u32 {
+        self.slots.items.len() as u32
+    }
+
+    /// Number of textures in the set.
+    pub fn len(&self) -> usize {
+        self.slots.items.len() - self.slots.free.len()
+    }
+
+    /// Check if the set has no textures.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Check if some slots changed since last `flush`.
+    pub fn is_dirty(&self) -> bool {
+        !self.slots.dirty.is_empty()
+    }
+
+    /// Indices of slots changed since last `flush` in ascending order.
+    pub fn dirty(&self) -> impl Iterator<Item = u32> + '_ {
+        self.slots.dirty.iter().cloned()
+    }
+
+    /// Produce writes updating changed slots of array `binding` in `set`
+    /// and mark all slots clean.
+    /// Consecutive changed slots are merged into single write.
+    pub fn flush<'a>(
+        &'a mut self,
+        set: &'a B::DescriptorSet,
+        binding: u32,
+    ) -> Vec<DescriptorSetWrite<'a, B, Vec<Descriptor<'a, B>>>> {
+        let runs = self.slots.take_dirty_runs();
+        let this = &*self;
+        runs.into_iter()
+            .map(|run| DescriptorSetWrite {
+                set,
+                binding,
+                array_offset: run.start as usize,
+                descriptors: run
+                    .map(|index| {
+                        let texture = this.get(index).unwrap_or(&this.fallback);
+                        Descriptor::Image(&texture.view, texture.layout)
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// Destroy all textures of the set including fallback.
+    pub fn dispose(self, factory: &mut Factory<B>) {
+        for texture in self.slots.items.into_iter().flatten() {
+            texture.dispose(factory);
+        }
+        self.fallback.dispose(factory);
+    }
+}
+
+/// Slots with stable indices, recycled free indices and changes tracked.
+#[derive(Debug)]
+struct Slots<T> {
+    items: Vec<Option<T>>,
+    free: BTreeSet<u32>,
+    dirty: BTreeSet<u32>,
+}
+
+impl<T> Slots<T> {
+    fn new() -> Self {
+        Slots {
+            items: Vec::new(),
+            free: BTreeSet::new(),
+            dirty: BTreeSet::new(),
+        }
+    }
+
+    fn insert(&mut self, item: T) -> u32 {
+        let index = match self.free.iter().next().cloned() {
+            Some(index) => {
+                self.free.remove(&index);
+                self.items[index as usize] = Some(item);
+                index
+            }
+            None => {
+                self.items.push(Some(item));
+                self.items.len() as u32 - 1
+            }
+        };
+        self.dirty.insert(index);
+        index
+    }
+
+    fn remove(&mut self, index: u32) -> Option<T> {
+        let item = self.items.get_mut(index as usize)?.take()?;
+        self.free.insert(index);
+        self.dirty.insert(index);
+        Some(item)
+    }
+
+    fn get(&self, index: u32) -> Option<&T> {
+        self.items.get(index as usize)?.as_ref()
+    }
+
+    /// Ranges of consecutive changed slots in ascending order.
+    /// All slots are clean afterwards.
+    fn take_dirty_runs(&mut self) -> Vec<Range<u32>> {
+        let mut runs: Vec<Range<u32>> = Vec::new();
+        for index in mem::take(&mut self.dirty) {
+            match runs.last_mut() {
+                Some(run) if run.end == index => run.end += 1,
+                _ => runs.push(index..index + 1),
+            }
+        }
+        runs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indices_are_stable_and_recycled() {
+        let mut slots = Slots::new();
+        let indices = ["a", "b", "c", "d"]
+            .iter()
+            .map(|&item| slots.insert(item))
+            .collect::<Vec<_>>();
+        assert_eq!(indices, [0, 1, 2, 3]);
+
+        assert_eq!(slots.remove(2), Some("c"));
+        assert_eq!(slots.remove(0), Some("a"));
+        assert_eq!(slots.remove(0), None);
+        assert_eq!(slots.remove(7), None);
+        assert_eq!((slots.get(1), slots.get(3)), (Some(&"b"), Some(&"d")));
+
+        assert_eq!(slots.insert("e"), 0);
+        assert_eq!(slots.insert("f"), 2);
+        assert_eq!(slots.insert("g"), 4);
+        assert_eq!(slots.get(3), Some(&"d"));
+    }
+
+    #[test]
+    fn dirty_slots_are_merged_into_writes() {
+        let mut slots = Slots::new();
+        for item in 0..6 {
+            slots.insert(item);
+        }
+        assert_eq!(slots.take_dirty_runs(), vec![0..6]);
+        assert_eq!(slots.take_dirty_runs(), []);
+
+        slots.remove(1);
+        slots.remove(4);
+        slots.remove(3);
+        assert_eq!(slots.take_dirty_runs(), [1..2, 3..5]);
+
+        assert_eq!(slots.insert(10), 1);
+        slots.remove(5);
+        assert_eq!(slots.take_dirty_runs(), [1..2, 5..6]);
+        assert_eq!(slots.get(3), None);
+    }
+}
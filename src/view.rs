@@ -0,0 +1,87 @@
+//! Views of single subresource used as render target.
+
+use std::borrow::Borrow;
+
+use hal::format::Swizzle;
+use hal::image::{Kind, Layer, Level, SubresourceRange, ViewKind};
+use hal::{Backend, Device};
+
+use render::Factory;
+
+use error::TextureError;
+use {aspects_for, Texture};
+
+/// `D2` view of single level and layer of the texture.
+/// Must be disposed before the texture it was created from.
+#[derive(Debug)]
+pub struct OwnedView<B: Backend> {
+    view: B::ImageView,
+    level: Level,
+    layer: Layer,
+}
+
+impl<B> OwnedView<B>
+where
+    B: Backend,
+{
+    /// Raw view.
+    pub fn raw(&self) -> &B::ImageView {
+        &self.view
+    }
+
+    /// Level of the texture the view covers.
+    pub fn level(&self) -> Level {
+        self.level
+    }
+
+    /// Layer of the texture the view covers.
+    pub fn layer(&self) -> Layer {
+        self.layer
+    }
+
+    /// Destroy the view.
+    pub fn dispose(self, factory: &mut Factory<B>) {
+        factory.destroy_image_view(self.view);
+    }
+}
+
+impl<B> Texture<B>
+where
+    B: Backend,
+{
+    /// Create `D2` view of single level and layer to render into.
+    /// Faces of cubemaps and slices of arrays are layers.
+    /// Attachments can't be swizzled, so the view ignores swizzle of the texture.
+    /// Returns `TextureError::NotTwoDimensional` for `D1` and `D3` textures.
+    pub fn attachment_view(
+        &self,
+        factory: &Factory<B>,
+        level: Level,
+        layer: Layer,
+    ) -> Result<OwnedView<B>, TextureError> {
+        if let Kind::D1(..) | Kind::D3(..) = self.kind {
+            Err(TextureError::NotTwoDimensional { kind: self.kind })?;
+        }
+        let layers = self.kind.num_layers();
+        if level >= self.levels || layer >= layers {
+            Err(TextureError::SubresourceOutOfRange {
+                level,
+                layer,
+                levels: self.levels,
+                layers,
+            })?;
+        }
+        let view = factory.create_image_view(
+            self.image.borrow(),
+            ViewKind::D2,
+            self.format,
+            Swizzle::NO,
+            SubresourceRange {
+                aspects: aspects_for(self.format),
+                levels: level..level + 1,
+                layers: layer..layer + 1,
+            },
+        )?;
+        Ok(OwnedView { view, level, layer })
+    }
+}
@@ -0,0 +1,260 @@
+//! Two-texture emulation of NV12 frames.
+//!
+//! gfx-hal exposes no multi-planar formats, so planes of YUV frames
+//! are uploaded into separate single-plane textures
+//! with chroma extent rounded up for odd luma extents.
+
+use std::borrow::Cow;
+
+use hal::format::{Aspects, Format};
+use hal::image::{Kind, Offset, SubresourceLayers};
+use hal::queue::QueueFamilyId;
+use hal::Backend;
+
+use render::Factory;
+
+use error::TextureError;
+use format;
+use {Texture, TextureBuilder};
+
+/// Builder of NV12 frame as `R8Unorm` luma texture
+/// and half resolution `Rg8Unorm` texture of interleaved chroma.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Nv12Builder<'a> {
+    y: TextureBuilder<'a>,
+    uv: TextureBuilder<'a>,
+}
+
+impl<'a> TextureBuilder<'a> {
+    /// Create builder for NV12 frame from tightly packed planes.
+    /// Chroma plane has half of luma extent rounded up.
+    pub fn nv12<Y, C>(width: u32, height: u32, y_plane: Y, uv_plane: C) -> Nv12Builder<'a>
+    where
+        Y: Into<Cow<'a, [u8]>>,
+        C: Into<Cow<'a, [u8]>>,
+    {
+        let (uv_width, uv_height) = chroma_extent(width, height);
+        Nv12Builder {
+            y: TextureBuilder::new(Kind::D2(width, height, 1, 1))
+                .with_format(Format::R8Unorm)
+                .with_data(y_plane.into()),
+            uv: TextureBuilder::new(Kind::D2(uv_width, uv_height, 1, 1))
+                .with_format(Format::Rg8Unorm)
+                .with_data(uv_plane.into()),
+        }
+    }
+}
+
+impl<'a> Nv12Builder<'a> {
+    /// Set row strides of luma and chroma planes in bytes.
+    /// Strides must cover a row and chroma stride must be even.
+    pub fn with_strides(mut self, y_stride: u32, uv_stride: u32) -> Result<Self, TextureError> {
+        self.set_strides(y_stride, uv_stride)?;
+        Ok(self)
+    }
+
+    /// Set row strides of luma and chroma planes in bytes.
+    /// Strides must cover a row and chroma stride must be even.
+    pub fn set_strides(
+        &mut self,
+        y_stride: u32,
+        uv_stride: u32,
+    ) -> Result<&mut Self, TextureError> {
+        let y_width = stride_texels(y_stride, self.y.kind.extent().width, 1)?;
+        let uv_width = stride_texels(uv_stride, self.uv.kind.extent().width, 2)?;
+        self.y.set_data_width(y_width);
+        self.uv.set_data_width(uv_width);
+        Ok(self)
+    }
+
+    /// Builder of luma texture.
+    pub fn y(&self) -> &TextureBuilder<'a> {
+        &self.y
+    }
+
+    /// Builder of chroma texture.
+    pub fn uv(&self) -> &TextureBuilder<'a> {
+        &self.uv
+    }
+
+    /// Build both textures.
+    pub fn build<B>(
+        &self,
+        family: QueueFamilyId,
+        factory: &mut Factory<B>,
+    ) -> Result<Nv12Texture<B>, TextureError>
+    where
+        B: Backend,
+    {
+        self.y.validate()?;
+        self.uv.validate()?;
+        let y = self.y.build(family, factory)?;
+        let uv = match self.uv.build(family, factory) {
+            Ok(uv) => uv,
+            Err(error) => {
+                y.dispose(factory);
+                return Err(error);
+            }
+        };
+        Ok(Nv12Texture { y, uv })
+    }
+}
+
+/// NV12 frame stored as luma and chroma textures.
+/// Shaders sample both and convert to RGB themselves.
+#[derive(Debug)]
+pub struct Nv12Texture<B: Backend> {
+    y: Texture<B>,
+    uv: Texture<B>,
+}
+
+impl<B> Nv12Texture<B>
+where
+    B: Backend,
+{
+    /// `R8Unorm` texture of luma plane.
+    pub fn y(&self) -> &Texture<B> {
+        &self.y
+    }
+
+    /// Half resolution `Rg8Unorm` texture of interleaved chroma plane.
+    pub fn uv(&self) -> &Texture<B> {
+        &self.uv
+    }
+
+    /// Upload new luma plane with row stride in bytes.
+    pub fn update_y(
+        &mut self,
+        factory: &mut Factory<B>,
+        family: QueueFamilyId,
+        data: &[u8],
+        stride: u32,
+    ) -> Result<(), TextureError> {
+        update_plane(&mut self.y, factory, family, data, stride)
+    }
+
+    /// Upload new chroma plane with row stride in bytes.
+    /// Stride must cover a row and be even.
+    pub fn update_uv(
+        &mut self,
+        factory: &mut Factory<B>,
+        family: QueueFamilyId,
+        data: &[u8],
+        stride: u32,
+    ) -> Result<(), TextureError> {
+        update_plane(&mut self.uv, factory, family, data, stride)
+    }
+
+    /// Destroy both textures.
+    pub fn dispose(self, factory: &mut Factory<B>) {
+        self.y.dispose(factory);
+        self.uv.dispose(factory);
+    }
+}
+
+/// Extent of chroma plane for luma extent.
+fn chroma_extent(width: u32, height: u32) -> (u32, u32) {
+    (width.div_ceil(2), height.div_ceil(2))
+}
+
+/// Number of texels in row `stride` bytes long covering `width` texels.
+fn stride_texels(stride: u32, width: u32, texel_size: u32) -> Result<u32, TextureError> {
+    let row_size = width * texel_size;
+    if stride < row_size || !stride.is_multiple_of(texel_size) {
+        Err(TextureError::InvalidStride {
+            stride,
+            row_size,
+            texel_size,
+        })?;
+    }
+    Ok(stride / texel_size)
+}
+
+/// Upload whole base level of the plane texture with rows `stride` bytes apart.
+fn update_plane<B>(
+    texture: &mut Texture<B>,
+    factory: &mut Factory<B>,
+    family: QueueFamilyId,
+    data: &[u8],
+    stride: u32,
+) -> Result<(), TextureError>
+where
+    B: Backend,
+{
+    let extent = texture.kind.extent();
+    let texel_size = texture.format.surface_desc().bits as u32 / 8;
+    let data_width = stride_texels(stride, extent.width, texel_size)?;
+    let expected = format::strided_size(texture.format, extent, data_width, extent.height, 1);
+    if data.len() < expected {
+        Err(TextureError::LevelSize {
+            level: 0,
+            expected,
+            actual: data.len(),
+        })?;
+    }
+    factory.upload_image(
+        &mut texture.image,
+        family,
+        texture.layout,
+        texture.access,
+        SubresourceLayers {
+            aspects: Aspects::COLOR,
+            level: 0,
+            layers: 0..1,
+        },
+        Offset::ZERO,
+        extent,
+        data_width,
+        extent.height,
+        &data[..expected],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chroma_plane_rounds_up() {
+        let builder = TextureBuilder::nv12(5, 3, vec![0; 15], vec![0; 12]);
+        assert_eq!(builder.y().kind, Kind::D2(5, 3, 1, 1));
+        assert_eq!(builder.y().format, Format::R8Unorm);
+        assert_eq!(builder.uv().kind, Kind::D2(3, 2, 1, 1));
+        assert_eq!(builder.uv().format, Format::Rg8Unorm);
+        builder.y().validate().unwrap();
+        builder.uv().validate().unwrap();
+    }
+
+    #[test]
+    fn strides_are_validated() {
+        let builder = TextureBuilder::nv12(5, 3, vec![0; 8 * 3], vec![0; 8 * 2])
+            .with_strides(8, 8)
+            .unwrap();
+        assert_eq!(builder.y().data_width(), 8);
+        assert_eq!(builder.uv().data_width(), 4);
+        builder.y().validate().unwrap();
+        builder.uv().validate().unwrap();
+
+        let invalid = [(4, 6, 4, 1), (5, 7, 7, 2), (5, 4, 4, 2)];
+        for &(y_stride, uv_stride, stride, texel_size) in &invalid {
+            match TextureBuilder::nv12(5, 3, vec![0; 15], vec![0; 12])
+                .with_strides(y_stride, uv_stride)
+            {
+                Err(TextureError::InvalidStride {
+                    stride: actual,
+                    texel_size: actual_size,
+                    ..
+                }) => assert_eq!((actual, actual_size), (stride, texel_size)),
+                other => panic!("Unexpected result {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn odd_extents_round_chroma_up() {
+        assert_eq!(chroma_extent(1, 1), (1, 1));
+        assert_eq!(chroma_extent(4, 2), (2, 1));
+        assert_eq!(chroma_extent(1919, 1081), (960, 541));
+    }
+}
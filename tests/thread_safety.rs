@@ -0,0 +1,6 @@
+extern crate trybuild;
+
+#[test]
+fn texture_is_send_and_sync() {
+    trybuild::TestCases::new().pass("tests/ui/send_sync.rs");
+}
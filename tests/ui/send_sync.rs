@@ -0,0 +1,11 @@
+extern crate gfx_backend_empty as empty;
+extern crate gfx_texture;
+
+use gfx_texture::{Texture, TextureBuilder};
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+fn main() {
+    assert_send_sync::<TextureBuilder<'static>>();
+    assert_send_sync::<Texture<empty::Backend>>();
+}